@@ -0,0 +1,93 @@
+//! Benchmarks for the binary writer.
+
+use std::io::{BufWriter, Cursor};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use fbxcel::{
+    low::{v7400::ArrayAttributeEncoding, FbxVersion},
+    tree::v7400::Tree,
+    writer::v7400::binary::Writer,
+};
+
+/// Number of elements in array benchmarks.
+const ARRAY_LEN: usize = 1 << 20;
+
+/// Size of the destination buffer.
+const SINK_LEN: usize = 64 << 20;
+
+/// Creates a buffered sink on the given buffer, as users usually do for files.
+fn new_sink(buf: &mut [u8]) -> BufWriter<Cursor<&mut [u8]>> {
+    BufWriter::new(Cursor::new(buf))
+}
+
+/// Generates a tree which looks like a scene with many small objects.
+fn scene_tree() -> Tree {
+    let mut tree = Tree::default();
+    let root = tree.root().node_id();
+    let objects = tree.append_new(root, "Objects");
+    for i in 0..1000 {
+        let geometry = tree.append_new(objects, "Geometry");
+        tree.append_attribute(geometry, i as i64);
+        tree.append_attribute(geometry, "Geometry::\u{0}\u{1}Geometry");
+        tree.append_attribute(geometry, "Mesh");
+        let props = tree.append_new(geometry, "Properties70");
+        for j in 0..10 {
+            let p = tree.append_new(props, "P");
+            tree.append_attribute(p, format!("Property{}", j));
+            tree.append_attribute(p, "double");
+            tree.append_attribute(p, "Number");
+            tree.append_attribute(p, "");
+            tree.append_attribute(p, j as f64);
+        }
+        let vertices = tree.append_new(geometry, "Vertices");
+        tree.append_attribute(vertices, vec![0.5f64; 300]);
+        let indices = tree.append_new(geometry, "PolygonVertexIndex");
+        tree.append_attribute(indices, (0..400).collect::<Vec<i32>>());
+    }
+    tree
+}
+
+fn array_attributes(c: &mut Criterion) {
+    let values = (0..ARRAY_LEN).map(|v| v as f64).collect::<Vec<_>>();
+    let mut buf = vec![0; SINK_LEN];
+
+    let mut group = c.benchmark_group("arr_f64");
+    group.throughput(Throughput::Elements(ARRAY_LEN as u64));
+    group.bench_function("from_iter", |b| {
+        b.iter(|| {
+            let mut writer = Writer::new(new_sink(&mut buf), FbxVersion::V7_4).unwrap();
+            let mut attrs = writer.new_node("Array").unwrap();
+            attrs
+                .append_arr_f64_from_iter(ArrayAttributeEncoding::Direct, values.iter().cloned())
+                .unwrap();
+            writer.close_node().unwrap();
+        })
+    });
+    group.bench_function("slice", |b| {
+        b.iter(|| {
+            let mut writer = Writer::new(new_sink(&mut buf), FbxVersion::V7_4).unwrap();
+            let mut attrs = writer.new_node("Array").unwrap();
+            attrs
+                .append_arr_f64(ArrayAttributeEncoding::Direct, &values)
+                .unwrap();
+            writer.close_node().unwrap();
+        })
+    });
+    group.finish();
+}
+
+fn write_tree(c: &mut Criterion) {
+    let tree = scene_tree();
+    let mut buf = vec![0; SINK_LEN];
+
+    c.bench_function("write_tree", |b| {
+        b.iter(|| {
+            let mut writer = Writer::new(new_sink(&mut buf), FbxVersion::V7_4).unwrap();
+            writer.write_tree(&tree).unwrap();
+        })
+    });
+}
+
+criterion_group!(benches, array_attributes, write_tree);
+criterion_main!(benches);
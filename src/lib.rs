@@ -16,6 +16,9 @@
 #![cfg_attr(not(feature = "writer"), doc = "`writer` ")]
 //! module provides writer types.
 //! To use `writer` module, enable `writer` feature.
+//!
+//! With `serde` feature, some types such as node attribute values and trees
+//! implement `Serialize` and `Deserialize` traits of [serde](https://serde.rs/).
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
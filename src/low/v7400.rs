@@ -1,8 +1,16 @@
 //! Low-level or primitive data types for FBX 7.4 and compatible versions.
 
+#[cfg(all(feature = "serde", feature = "tree"))]
+pub(crate) use self::attribute::serde::{
+    variant_name as attribute_variant_name, DESERIALIZE_NAME as ATTRIBUTE_VALUE_NAME,
+};
 pub use self::{
     array_attribute::ArrayAttributeEncoding,
-    attribute::{type_::AttributeType, value::AttributeValue},
+    attribute::{
+        approx::{ApproxEqOptions, FloatTolerance},
+        type_::AttributeType,
+        value::AttributeValue,
+    },
     fbx_footer::FbxFooter,
 };
 pub(crate) use self::{
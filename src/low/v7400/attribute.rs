@@ -1,4 +1,7 @@
 //! Node attribute.
 
+pub(crate) mod approx;
+#[cfg(feature = "serde")]
+pub(crate) mod serde;
 pub(crate) mod type_;
 pub(crate) mod value;
@@ -0,0 +1,245 @@
+//! Approximate comparison of node attribute values.
+
+use crate::low::v7400::AttributeValue;
+
+/// Tolerance of floating point number comparison.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum FloatTolerance {
+    /// Compares values bitwise, as [`AttributeValue::strict_eq`] does.
+    Bitwise,
+    /// Values are considered equal if the absolute difference is equal to or
+    /// less than the given value.
+    Absolute(f64),
+    /// Values are considered equal if the distance in ULPs (units in the last
+    /// place) is equal to or less than the given value.
+    ///
+    /// If `f32` and `f64` values are compared, the distance is measured as
+    /// `f32` values.
+    Ulps(u64),
+}
+
+impl Default for FloatTolerance {
+    #[inline]
+    fn default() -> Self {
+        Self::Bitwise
+    }
+}
+
+/// Options for approximate comparison of attribute values.
+///
+/// The default value compares values as strictly as
+/// [`AttributeValue::strict_eq`] does.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct ApproxEqOptions {
+    /// Tolerance of floating point number comparison.
+    ///
+    /// Unless the tolerance is [`FloatTolerance::Bitwise`], NaN is considered
+    /// equal to any NaN.
+    pub float_tolerance: FloatTolerance,
+    /// Whether to consider integers of different widths (`i16`, `i32`, and
+    /// `i64`) equal if they have the same value.
+    ///
+    /// This also applies to `i32` and `i64` arrays.
+    pub ignore_integer_width: bool,
+    /// Whether to consider `f32` and `f64` values equal if they are equal
+    /// within the tolerance.
+    ///
+    /// This also applies to `f32` and `f64` arrays.
+    pub ignore_float_width: bool,
+}
+
+impl ApproxEqOptions {
+    /// Creates a new options with the default values.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compares `f64` values.
+    fn f64_eq(&self, l: f64, r: f64) -> bool {
+        match self.float_tolerance {
+            FloatTolerance::Bitwise => l.to_bits() == r.to_bits(),
+            _ if l.is_nan() || r.is_nan() => l.is_nan() && r.is_nan(),
+            FloatTolerance::Absolute(tolerance) => l == r || (l - r).abs() <= tolerance,
+            FloatTolerance::Ulps(ulps) => ulps_f64(l, r) <= u128::from(ulps),
+        }
+    }
+
+    /// Compares `f32` values.
+    fn f32_eq(&self, l: f32, r: f32) -> bool {
+        match self.float_tolerance {
+            FloatTolerance::Bitwise => l.to_bits() == r.to_bits(),
+            _ if l.is_nan() || r.is_nan() => l.is_nan() && r.is_nan(),
+            FloatTolerance::Absolute(tolerance) => {
+                l == r || (f64::from(l) - f64::from(r)).abs() <= tolerance
+            }
+            FloatTolerance::Ulps(ulps) => ulps_f32(l, r) <= u128::from(ulps),
+        }
+    }
+
+    /// Compares `f32` and `f64` values.
+    fn f32_f64_eq(&self, l: f32, r: f64) -> bool {
+        if !self.ignore_float_width {
+            return false;
+        }
+        match self.float_tolerance {
+            FloatTolerance::Bitwise => f64::from(l).to_bits() == r.to_bits(),
+            FloatTolerance::Ulps(_) => self.f32_eq(l, r as f32),
+            _ => self.f64_eq(f64::from(l), r),
+        }
+    }
+
+    /// Compares integer values of possibly different widths.
+    fn int_eq(&self, l: i64, r: i64, same_width: bool) -> bool {
+        (same_width || self.ignore_integer_width) && l == r
+    }
+}
+
+/// Returns the distance of the `f64` values in ULPs.
+fn ulps_f64(l: f64, r: f64) -> u128 {
+    /// Maps the bits to the integer with the same order as the float value.
+    fn ordered(v: f64) -> i128 {
+        let bits = v.to_bits() as i64;
+        i128::from(if bits < 0 { i64::MIN - bits } else { bits })
+    }
+    (ordered(l) - ordered(r)).unsigned_abs()
+}
+
+/// Returns the distance of the `f32` values in ULPs.
+fn ulps_f32(l: f32, r: f32) -> u128 {
+    /// Maps the bits to the integer with the same order as the float value.
+    fn ordered(v: f32) -> i128 {
+        let bits = v.to_bits() as i32;
+        i128::from(if bits < 0 { i32::MIN - bits } else { bits })
+    }
+    (ordered(l) - ordered(r)).unsigned_abs()
+}
+
+/// Compares slices elementwise.
+fn slice_eq<L, R>(l: &[L], r: &[R], eq: impl Fn(&L, &R) -> bool) -> bool {
+    l.len() == r.len() && l.iter().zip(r).all(|(l, r)| eq(l, r))
+}
+
+impl AttributeValue {
+    /// Compares attribute values approximately.
+    ///
+    /// See [`ApproxEqOptions`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fbxcel::low::v7400::{ApproxEqOptions, AttributeValue, FloatTolerance};
+    ///
+    /// let mut options = ApproxEqOptions::new();
+    /// options.float_tolerance = FloatTolerance::Ulps(1);
+    /// options.ignore_float_width = true;
+    ///
+    /// let original = AttributeValue::from(vec![0.1f64, 0.2, 0.3]);
+    /// let round_tripped = AttributeValue::from(vec![0.1f32, 0.2, 0.3]);
+    /// assert!(!original.strict_eq(&round_tripped));
+    /// assert!(original.approx_eq(&round_tripped, &options));
+    /// ```
+    #[must_use]
+    pub fn approx_eq(&self, other: &Self, options: &ApproxEqOptions) -> bool {
+        use AttributeValue::*;
+
+        match (self, other) {
+            (Bool(l), Bool(r)) => l == r,
+            (I16(l), I16(r)) => l == r,
+            (I32(l), I32(r)) => l == r,
+            (I64(l), I64(r)) => l == r,
+            (I16(l), I32(r)) => options.int_eq(i64::from(*l), i64::from(*r), false),
+            (I16(l), I64(r)) => options.int_eq(i64::from(*l), *r, false),
+            (I32(l), I16(r)) => options.int_eq(i64::from(*l), i64::from(*r), false),
+            (I32(l), I64(r)) => options.int_eq(i64::from(*l), *r, false),
+            (I64(l), I16(r)) => options.int_eq(*l, i64::from(*r), false),
+            (I64(l), I32(r)) => options.int_eq(*l, i64::from(*r), false),
+            (F32(l), F32(r)) => options.f32_eq(*l, *r),
+            (F64(l), F64(r)) => options.f64_eq(*l, *r),
+            (F32(l), F64(r)) => options.f32_f64_eq(*l, *r),
+            (F64(l), F32(r)) => options.f32_f64_eq(*r, *l),
+            (ArrBool(l), ArrBool(r)) => l == r,
+            (ArrI32(l), ArrI32(r)) => l == r,
+            (ArrI64(l), ArrI64(r)) => l == r,
+            (ArrI32(l), ArrI64(r)) => {
+                slice_eq(l, r, |l, r| options.int_eq(i64::from(*l), *r, false))
+            }
+            (ArrI64(l), ArrI32(r)) => {
+                slice_eq(l, r, |l, r| options.int_eq(*l, i64::from(*r), false))
+            }
+            (ArrF32(l), ArrF32(r)) => slice_eq(l, r, |l, r| options.f32_eq(*l, *r)),
+            (ArrF64(l), ArrF64(r)) => slice_eq(l, r, |l, r| options.f64_eq(*l, *r)),
+            (ArrF32(l), ArrF64(r)) => slice_eq(l, r, |l, r| options.f32_f64_eq(*l, *r)),
+            (ArrF64(l), ArrF32(r)) => slice_eq(l, r, |l, r| options.f32_f64_eq(*r, *l)),
+            (Binary(l), Binary(r)) => l == r,
+            (String(l), String(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_is_strict() {
+        let options = ApproxEqOptions::new();
+        let values: &[AttributeValue] = &[
+            1i32.into(),
+            1i64.into(),
+            0.0f32.into(),
+            (-0.0f32).into(),
+            f64::NAN.into(),
+            vec![1.0f64].into(),
+            vec![1.0f32].into(),
+        ];
+        for l in values {
+            for r in values {
+                assert_eq!(l.approx_eq(r, &options), l.strict_eq(r), "{:?}, {:?}", l, r);
+            }
+        }
+    }
+
+    #[test]
+    fn tolerances() {
+        let mut options = ApproxEqOptions::new();
+        options.float_tolerance = FloatTolerance::Absolute(0.01);
+        assert!(AttributeValue::F64(1.0).approx_eq(&1.005f64.into(), &options));
+        assert!(!AttributeValue::F64(1.0).approx_eq(&1.02f64.into(), &options));
+        assert!(AttributeValue::F64(f64::NAN).approx_eq(&f64::NAN.into(), &options));
+        assert!(!AttributeValue::F64(1.0).approx_eq(&f64::NAN.into(), &options));
+        assert!(!AttributeValue::F64(1.0).approx_eq(&1.0f32.into(), &options));
+
+        options.float_tolerance = FloatTolerance::Ulps(1);
+        let next = f32::from_bits(1.0f32.to_bits() + 1);
+        let next2 = f32::from_bits(1.0f32.to_bits() + 2);
+        assert!(AttributeValue::F32(1.0).approx_eq(&next.into(), &options));
+        assert!(!AttributeValue::F32(1.0).approx_eq(&next2.into(), &options));
+        assert!(AttributeValue::F32(0.0).approx_eq(&(-0.0f32).into(), &options));
+        let tiny = f32::from_bits(1);
+        assert!(AttributeValue::F32(tiny).approx_eq(&(-0.0f32).into(), &options));
+        assert!(!AttributeValue::F32(tiny).approx_eq(&(-tiny).into(), &options));
+
+        options.ignore_float_width = true;
+        assert!(AttributeValue::F64(0.1).approx_eq(&0.1f32.into(), &options));
+        assert!(!AttributeValue::F64(0.1).approx_eq(&0.1001f32.into(), &options));
+    }
+
+    #[test]
+    fn integer_width() {
+        let mut options = ApproxEqOptions::new();
+        assert!(!AttributeValue::I32(42).approx_eq(&42i64.into(), &options));
+        options.ignore_integer_width = true;
+        assert!(AttributeValue::I32(42).approx_eq(&42i64.into(), &options));
+        assert!(AttributeValue::I64(42).approx_eq(&42i16.into(), &options));
+        assert!(!AttributeValue::I32(42).approx_eq(&43i64.into(), &options));
+        assert!(AttributeValue::from(vec![1i32, 2])
+            .approx_eq(&AttributeValue::from(vec![1i64, 2]), &options));
+        assert!(!AttributeValue::from(vec![1i32, 2])
+            .approx_eq(&AttributeValue::from(vec![1i64]), &options));
+    }
+}
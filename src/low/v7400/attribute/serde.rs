@@ -0,0 +1,490 @@
+//! Serde support for node attribute values.
+//!
+//! See the documentation of [`AttributeValue`] for the representation.
+
+use std::fmt::{self, Write};
+
+use serde::{
+    de::{self, EnumAccess, SeqAccess, VariantAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::low::v7400::AttributeValue;
+
+/// Type name of `AttributeValue`.
+const NAME: &str = "AttributeValue";
+
+/// Type name of `AttributeValue` passed to deserializers.
+///
+/// This is not a valid Rust identifier, so that deserializers of this crate
+/// can distinguish `AttributeValue` from user-defined enums with the same
+/// name.
+pub(crate) const DESERIALIZE_NAME: &str = "$fbxcel::AttributeValue";
+
+/// Variant names of `AttributeValue`, in the declaration order.
+const VARIANTS: &[&str] = &[
+    "Bool", "I16", "I32", "I64", "F32", "F64", "ArrBool", "ArrI32", "ArrI64", "ArrF32", "ArrF64",
+    "String", "Binary",
+];
+
+/// Returns the variant name of the attribute value.
+#[cfg(feature = "tree")]
+#[must_use]
+pub(crate) fn variant_name(value: &AttributeValue) -> &'static str {
+    match value {
+        AttributeValue::Bool(_) => "Bool",
+        AttributeValue::I16(_) => "I16",
+        AttributeValue::I32(_) => "I32",
+        AttributeValue::I64(_) => "I64",
+        AttributeValue::F32(_) => "F32",
+        AttributeValue::F64(_) => "F64",
+        AttributeValue::ArrBool(_) => "ArrBool",
+        AttributeValue::ArrI32(_) => "ArrI32",
+        AttributeValue::ArrI64(_) => "ArrI64",
+        AttributeValue::ArrF32(_) => "ArrF32",
+        AttributeValue::ArrF64(_) => "ArrF64",
+        AttributeValue::String(_) => "String",
+        AttributeValue::Binary(_) => "Binary",
+    }
+}
+
+/// Variant of `AttributeValue`.
+#[derive(Deserialize)]
+#[serde(variant_identifier)]
+enum Variant {
+    /// Single `bool`.
+    Bool,
+    /// Single `i16`.
+    I16,
+    /// Single `i32`.
+    I32,
+    /// Single `i64`.
+    I64,
+    /// Single `f32`.
+    F32,
+    /// Single `f64`.
+    F64,
+    /// Array of `bool`.
+    ArrBool,
+    /// Array of `i32`.
+    ArrI32,
+    /// Array of `i64`.
+    ArrI64,
+    /// Array of `f32`.
+    ArrF32,
+    /// Array of `f64`.
+    ArrF64,
+    /// UTF-8 string.
+    String,
+    /// Binary.
+    Binary,
+}
+
+impl Serialize for AttributeValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            AttributeValue::Bool(v) => serializer.serialize_newtype_variant(NAME, 0, "Bool", v),
+            AttributeValue::I16(v) => serializer.serialize_newtype_variant(NAME, 1, "I16", v),
+            AttributeValue::I32(v) => serializer.serialize_newtype_variant(NAME, 2, "I32", v),
+            AttributeValue::I64(v) => serializer.serialize_newtype_variant(NAME, 3, "I64", v),
+            AttributeValue::F32(v) => {
+                serializer.serialize_newtype_variant(NAME, 4, "F32", &F32Repr(*v))
+            }
+            AttributeValue::F64(v) => {
+                serializer.serialize_newtype_variant(NAME, 5, "F64", &F64Repr(*v))
+            }
+            AttributeValue::ArrBool(v) => {
+                serializer.serialize_newtype_variant(NAME, 6, "ArrBool", v)
+            }
+            AttributeValue::ArrI32(v) => serializer.serialize_newtype_variant(NAME, 7, "ArrI32", v),
+            AttributeValue::ArrI64(v) => serializer.serialize_newtype_variant(NAME, 8, "ArrI64", v),
+            AttributeValue::ArrF32(v) => {
+                serializer.serialize_newtype_variant(NAME, 9, "ArrF32", &FloatArray::F32(v))
+            }
+            AttributeValue::ArrF64(v) => {
+                serializer.serialize_newtype_variant(NAME, 10, "ArrF64", &FloatArray::F64(v))
+            }
+            AttributeValue::String(v) => {
+                serializer.serialize_newtype_variant(NAME, 11, "String", v)
+            }
+            AttributeValue::Binary(v) => {
+                serializer.serialize_newtype_variant(NAME, 12, "Binary", &Bytes(v))
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AttributeValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_enum(DESERIALIZE_NAME, VARIANTS, AttributeValueVisitor)
+    }
+}
+
+/// Visitor for `AttributeValue`.
+struct AttributeValueVisitor;
+
+impl<'de> Visitor<'de> for AttributeValueVisitor {
+    type Value = AttributeValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an FBX node attribute value")
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (variant, access) = data.variant()?;
+        let value = match variant {
+            Variant::Bool => AttributeValue::Bool(access.newtype_variant()?),
+            Variant::I16 => AttributeValue::I16(access.newtype_variant()?),
+            Variant::I32 => AttributeValue::I32(access.newtype_variant()?),
+            Variant::I64 => AttributeValue::I64(access.newtype_variant()?),
+            Variant::F32 => AttributeValue::F32(access.newtype_variant::<F32Repr>()?.0),
+            Variant::F64 => AttributeValue::F64(access.newtype_variant::<F64Repr>()?.0),
+            Variant::ArrBool => AttributeValue::ArrBool(access.newtype_variant()?),
+            Variant::ArrI32 => AttributeValue::ArrI32(access.newtype_variant()?),
+            Variant::ArrI64 => AttributeValue::ArrI64(access.newtype_variant()?),
+            Variant::ArrF32 => AttributeValue::ArrF32(
+                access
+                    .newtype_variant::<Vec<F32Repr>>()?
+                    .into_iter()
+                    .map(|v| v.0)
+                    .collect(),
+            ),
+            Variant::ArrF64 => AttributeValue::ArrF64(
+                access
+                    .newtype_variant::<Vec<F64Repr>>()?
+                    .into_iter()
+                    .map(|v| v.0)
+                    .collect(),
+            ),
+            Variant::String => AttributeValue::String(access.newtype_variant()?),
+            Variant::Binary => AttributeValue::Binary(access.newtype_variant::<ByteBuf>()?.0),
+        };
+        Ok(value)
+    }
+}
+
+/// Implement serde traits for floating point value wrappers.
+macro_rules! impl_float_repr {
+    ($(
+        $(#[$meta:meta])*
+        $repr:ident($ty:ident): $bits:ident, $serialize:ident, $deserialize:ident;
+    )*) => {$(
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        struct $repr($ty);
+
+        impl Serialize for $repr {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                let v = self.0;
+                if v.is_finite() || !serializer.is_human_readable() {
+                    serializer.$serialize(v)
+                } else if v == $ty::INFINITY {
+                    serializer.serialize_str("inf")
+                } else if v == $ty::NEG_INFINITY {
+                    serializer.serialize_str("-inf")
+                } else if v.to_bits() == $ty::NAN.to_bits() {
+                    serializer.serialize_str("NaN")
+                } else {
+                    serializer.collect_str(&format_args!("NaN:{:#x}", v.to_bits()))
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $repr {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                /// Visitor for the value.
+                struct ReprVisitor;
+
+                impl<'de> Visitor<'de> for ReprVisitor {
+                    type Value = $repr;
+
+                    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        f.write_str(concat!("an `", stringify!($ty), "` value"))
+                    }
+
+                    fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E> {
+                        Ok($repr(v as $ty))
+                    }
+
+                    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+                        Ok($repr(v as $ty))
+                    }
+
+                    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+                        Ok($repr(v as $ty))
+                    }
+
+                    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+                        Ok($repr(v as $ty))
+                    }
+
+                    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                    where
+                        E: de::Error,
+                    {
+                        let value = match v {
+                            "inf" => $ty::INFINITY,
+                            "-inf" => $ty::NEG_INFINITY,
+                            "NaN" => $ty::NAN,
+                            _ => v
+                                .strip_prefix("NaN:0x")
+                                .and_then(|hex| $bits::from_str_radix(hex, 16).ok())
+                                .map($ty::from_bits)
+                                .filter(|v| v.is_nan())
+                                .ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))?,
+                        };
+                        Ok($repr(value))
+                    }
+                }
+
+                if deserializer.is_human_readable() {
+                    // Non-finite values are strings.
+                    deserializer.deserialize_any(ReprVisitor)
+                } else {
+                    deserializer.$deserialize(ReprVisitor)
+                }
+            }
+        }
+    )*};
+}
+
+impl_float_repr! {
+    /// `f32` value with serde support of non-finite values.
+    F32Repr(f32): u32, serialize_f32, deserialize_f32;
+    /// `f64` value with serde support of non-finite values.
+    F64Repr(f64): u64, serialize_f64, deserialize_f64;
+}
+
+/// Serializable array of floating point values.
+enum FloatArray<'a> {
+    /// Array of `f32`.
+    F32(&'a [f32]),
+    /// Array of `f64`.
+    F64(&'a [f64]),
+}
+
+impl Serialize for FloatArray<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            FloatArray::F32(v) => serializer.collect_seq(v.iter().map(|&v| F32Repr(v))),
+            FloatArray::F64(v) => serializer.collect_seq(v.iter().map(|&v| F64Repr(v))),
+        }
+    }
+}
+
+/// Serializable binary.
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&Base64(self.0))
+        } else {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+}
+
+/// Deserializable binary.
+struct ByteBuf(Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(ByteBufVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(ByteBufVisitor)
+        }
+    }
+}
+
+/// Visitor for `ByteBuf`.
+struct ByteBufVisitor;
+
+impl<'de> Visitor<'de> for ByteBufVisitor {
+    type Value = ByteBuf;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a byte array or a base64 string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        decode_base64(v)
+            .map(ByteBuf)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(ByteBuf(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(ByteBuf(v))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(ByteBuf(bytes))
+    }
+}
+
+/// Base64 alphabet (RFC 4648, standard).
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Binary formatted as a padded base64 string.
+struct Base64<'a>(&'a [u8]);
+
+impl fmt::Display for Base64<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.0.chunks(3) {
+            let mut buf = [0; 3];
+            buf[..chunk.len()].copy_from_slice(chunk);
+            let bits = u32::from_be_bytes([0, buf[0], buf[1], buf[2]]);
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    let index = (bits >> (18 - 6 * i)) & 0x3f;
+                    f.write_char(char::from(BASE64_ALPHABET[index as usize]))?;
+                } else {
+                    f.write_char('=')?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a padded base64 string.
+///
+/// Returns `None` if the string is not valid base64.
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    /// Returns the 6-bit value of the base64 character.
+    fn sextet(c: u8) -> Option<u32> {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        Some(u32::from(v))
+    }
+
+    let s = s.as_bytes();
+    if s.len() % 4 != 0 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(s.len() / 4 * 3);
+    for (i, chunk) in s.chunks(4).enumerate() {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        let is_last = (i + 1) * 4 == s.len();
+        if padding > 2 || (padding != 0 && !is_last) {
+            return None;
+        }
+        let mut bits = 0;
+        for &c in &chunk[..(4 - padding)] {
+            bits = (bits << 6) | sextet(c)?;
+        }
+        bits <<= 6 * padding;
+        bytes.extend_from_slice(&bits.to_be_bytes()[1..(4 - padding)]);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+            (&[0x00, 0xff, 0xfe, 0x3e, 0x3f], "AP/+Pj8="),
+        ];
+        for &(bytes, encoded) in cases {
+            assert_eq!(Base64(bytes).to_string(), encoded);
+            assert_eq!(decode_base64(encoded).as_deref(), Some(bytes));
+        }
+
+        for invalid in ["Zg=", "Zg===", "Zg==Zm9v", "Zm9v!A==", "=Zm9"] {
+            assert_eq!(decode_base64(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn json_representation() {
+        let cases = [
+            (AttributeValue::I16(42), r#"{"I16":42}"#),
+            (AttributeValue::F32(1.5), r#"{"F32":1.5}"#),
+            (AttributeValue::F64(f64::NEG_INFINITY), r#"{"F64":"-inf"}"#),
+            (
+                AttributeValue::ArrF32(vec![0.0, f32::NAN, f32::from_bits(0x7fc0_0001)]),
+                r#"{"ArrF32":[0.0,"NaN","NaN:0x7fc00001"]}"#,
+            ),
+            (
+                AttributeValue::Binary(b"foob".to_vec()),
+                r#"{"Binary":"Zm9vYg=="}"#,
+            ),
+            (AttributeValue::from("foob"), r#"{"String":"foob"}"#),
+        ];
+        for (value, json) in cases {
+            assert_eq!(serde_json::to_string(&value).unwrap(), json);
+            let deserialized: AttributeValue = serde_json::from_str(json).unwrap();
+            assert!(deserialized.strict_eq(&value), "{:?}", value);
+        }
+
+        for invalid in [
+            r#"{"I16":100000}"#,
+            r#"{"F32":"nan"}"#,
+            r#"{"F64":"NaN:0x0"}"#,
+            r#"{"Binary":"Zm9"}"#,
+            r#"{"U8":0}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<AttributeValue>(invalid).is_err(),
+                "{:?}",
+                invalid
+            );
+        }
+    }
+}
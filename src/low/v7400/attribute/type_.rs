@@ -6,6 +6,7 @@ use crate::pull_parser::{error::DataError, v7400::FromReader, Error as ParserErr
 
 /// Node attribute type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AttributeType {
     /// Single `bool`.
     Bool,
@@ -58,8 +59,8 @@ impl AttributeType {
     }
 
     /// Returns the type code.
-    #[cfg(feature = "writer")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "writer")))]
+    #[cfg(any(feature = "tree", feature = "writer"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tree", feature = "writer"))))]
     #[must_use]
     pub(crate) fn type_code(self) -> u8 {
         match self {
@@ -14,6 +14,25 @@ use crate::low::v7400::AttributeType;
 ///     + If a value of the expected type available, returns `Ok(_)`.
 ///     + If not, returns `Ok(ty)` where `ty` is value type (same value as
 ///       returned by [`type_`][`Self::type_()`] method.
+///
+/// # Serde
+///
+/// With `serde` feature, attribute values are serialized as externally tagged
+/// enums, such as `{"I16": 42}` in JSON, so that the value types are
+/// preserved.
+///
+/// Some values are serialized differently for human-readable formats:
+///
+/// * Binary values are serialized as base64 strings for human-readable
+///   formats, and as byte arrays for other formats.
+/// * Non-finite floating point values are serialized as strings for
+///   human-readable formats, since formats such as JSON cannot represent them.
+///     + Infinities are `"inf"` and `"-inf"`.
+///     + NaN is `"NaN"`, and NaN with other bit patterns is `"NaN:0x..."`
+///       with the hexadecimal representation of the bits.
+///
+/// Note that `serde_json` requires `float_roundtrip` feature to deserialize
+/// floating point values without precision loss.
 #[derive(Debug, Clone, PartialEq)]
 pub enum AttributeValue {
     /// Single `bool`.
@@ -2,6 +2,7 @@
 
 /// FBX version.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FbxVersion(u32);
 
 impl FbxVersion {
@@ -199,7 +199,6 @@ impl ErrorContainer {
 
 impl error::Error for ErrorContainer {
     #[inline]
-    #[must_use]
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         Some(self.as_error())
     }
@@ -53,7 +53,7 @@ impl<R: io::Read> ReaderFnTable<R> {
         R: io::Seek,
     {
         while distance > 0 {
-            let part = std::cmp::min(distance, std::i64::MAX as u64);
+            let part = std::cmp::min(distance, i64::MAX as u64);
             reader.inner.seek(SeekFrom::Current(part as i64))?;
             reader.advance(part as usize);
             distance -= part;
@@ -141,6 +141,20 @@ impl<R: io::Read> Reader<R> {
         self.skip_distance(distance)
     }
 
+    /// Seeks to the given absolute position.
+    ///
+    /// Unlike [`skip_to`][`Self::skip_to`], this can seek backward.
+    #[cfg(feature = "writer")]
+    pub(crate) fn seek_to(&mut self, pos: u64) -> io::Result<()>
+    where
+        R: io::Seek,
+    {
+        let pos = self.inner.seek(SeekFrom::Start(pos))?;
+        self.position = usize::try_from(pos)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Position overflowed"))?;
+        Ok(())
+    }
+
     /// Advances the position counter.
     #[inline]
     fn advance(&mut self, n: usize) {
@@ -4,7 +4,7 @@
 
 pub(crate) use self::read::{FromParser, FromReader};
 pub use self::{
-    attribute::{Attributes, LoadAttribute},
+    attribute::{AttributeLocation, Attributes, LoadAttribute},
     event::{Event, StartNode},
     parser::Parser,
 };
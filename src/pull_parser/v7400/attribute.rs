@@ -19,6 +19,44 @@ pub mod iter;
 mod loader;
 pub mod loaders;
 
+/// Location of a node attribute in the FBX data.
+///
+/// This can be obtained by [`Attributes::locate_next`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AttributeLocation {
+    /// Byte offset of the attribute.
+    offset: u64,
+    /// Size of the attribute in bytes.
+    size: u64,
+    /// Attribute type.
+    attribute_type: AttributeType,
+}
+
+impl AttributeLocation {
+    /// Returns the byte offset of the attribute, i.e. the offset of the type
+    /// code.
+    #[inline]
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the size of the attribute in bytes, including the type code
+    /// and headers.
+    #[inline]
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the attribute type.
+    #[inline]
+    #[must_use]
+    pub fn attribute_type(&self) -> AttributeType {
+        self.attribute_type
+    }
+}
+
 /// Node attributes reader.
 #[derive(Debug)]
 pub struct Attributes<'a, R> {
@@ -130,6 +168,45 @@ impl<'a, R: 'a + io::Read> Attributes<'a, R> {
         })
     }
 
+    /// Locates the next node attribute without loading the value.
+    ///
+    /// The returned location can be used to patch the attribute value in
+    /// place, by `writer::v7400::binary::AttributePatcher` (available with
+    /// `writer` feature).
+    pub fn locate_next(&mut self) -> Result<Option<AttributeLocation>> {
+        self.do_with_health_check(|this, start_pos, _attr_index| {
+            let attribute_type = match this.read_next_attr_type()? {
+                Some(v) => v,
+                None => return Ok(None),
+            };
+            let value_size = match attribute_type {
+                AttributeType::Bool => 1,
+                AttributeType::I16 => 2,
+                AttributeType::I32 | AttributeType::F32 => 4,
+                AttributeType::I64 | AttributeType::F64 => 8,
+                AttributeType::ArrBool
+                | AttributeType::ArrI32
+                | AttributeType::ArrI64
+                | AttributeType::ArrF32
+                | AttributeType::ArrF64 => {
+                    let header = ArrayAttributeHeader::from_reader(this.parser.reader())?;
+                    u64::from(header.bytelen)
+                }
+                AttributeType::Binary | AttributeType::String => {
+                    let header = this.parser.parse::<SpecialAttributeHeader>()?;
+                    u64::from(header.bytelen)
+                }
+            };
+            this.update_next_attr_start_offset(value_size);
+
+            Ok(Some(AttributeLocation {
+                offset: start_pos,
+                size: this.next_attr_start_offset - start_pos,
+                attribute_type,
+            }))
+        })
+    }
+
     /// Lets loader load the next node attribute.
     ///
     /// This method prefers `V::load_{binary,string}_buffered` to
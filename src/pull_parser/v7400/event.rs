@@ -46,6 +46,14 @@ impl<'a, R: 'a + io::Read> StartNode<'a, R> {
         self.parser.current_node_name()
     }
 
+    /// Returns the parser.
+    #[cfg(feature = "tree")]
+    #[inline]
+    #[must_use]
+    pub(crate) fn into_parser(self) -> &'a mut Parser<R> {
+        self.parser
+    }
+
     /// Returns node attributes reader.
     #[inline]
     #[must_use]
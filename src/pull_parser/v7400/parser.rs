@@ -148,6 +148,18 @@ impl<R: io::Read> Parser<R> {
             .attributes_count
     }
 
+    /// Returns the start and end offsets of the current node.
+    ///
+    /// Returns `None` if there are no open nodes.
+    #[cfg(feature = "writer")]
+    #[inline]
+    #[must_use]
+    pub(crate) fn current_node_range(&self) -> Option<(u64, u64)> {
+        self.state
+            .current_node()
+            .map(|node| (node.node_start_offset, node.node_end_offset))
+    }
+
     /// Returns current node depth.
     ///
     /// Implicit root node is considered to be depth 0.
@@ -5,6 +5,8 @@ use std::io::{Read, Seek};
 use log::warn;
 
 pub use self::error::{Error, Result};
+#[cfg(feature = "writer")]
+use crate::writer;
 use crate::{
     low::{self, FbxVersion},
     pull_parser::{self, any::AnyParser},
@@ -69,4 +71,38 @@ impl AnyTree {
             Self::V7400(ver, _, _) => *ver,
         }
     }
+
+    /// Writes the tree as FBX binary of the given version, and returns the
+    /// sink after flushing.
+    ///
+    /// The writer appropriate for the target FBX version is used, and the FBX
+    /// footer is written with default values.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use fbxcel::{low::FbxVersion, tree::any::AnyTree};
+    ///
+    /// let file = std::fs::File::open("sample.fbx").expect("Failed to open file");
+    /// let reader = std::io::BufReader::new(file);
+    /// let tree = AnyTree::from_seekable_reader(reader).expect("Failed to load tree");
+    ///
+    /// let file = std::fs::File::create("out.fbx").expect("Failed to create file");
+    /// let sink = std::io::BufWriter::new(file);
+    /// tree.write_to(sink, FbxVersion::V7_4)
+    ///     .expect("Failed to write tree");
+    /// ```
+    #[cfg(feature = "writer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "writer")))]
+    pub fn write_to<W>(&self, sink: W, fbx_version: FbxVersion) -> writer::any::Result<W>
+    where
+        W: std::io::Write + Seek,
+    {
+        match (self, writer::any::AnyWriter::new(sink, fbx_version)?) {
+            (AnyTree::V7400(_, tree, _), writer::any::AnyWriter::V7400(mut writer)) => {
+                writer.write_tree(tree)?;
+                Ok(writer.finalize_and_flush(&Default::default())?)
+            }
+        }
+    }
 }
@@ -1,27 +1,65 @@
 //! FBX data tree for v7.4 or later.
 
-use std::fmt;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
 use indextree::Arena;
 use string_interner::{DefaultBackend, StringInterner};
 
 use crate::low::v7400::AttributeValue;
 
-use self::node::{NodeData, NodeNameSym};
 pub use self::{
-    error::LoadError,
+    approx::TreeApproxEqOptions,
+    canonical::CanonicalizeOptions,
+    diff::{DiffOptions, TreeDiff},
+    error::{EditError, LoadError},
+    events::{TreeEvent, TreeEvents},
+    hash::ContentHash,
     loader::Loader,
+    memory::{MemoryUsage, NodeMemoryUsage},
     node::{
         handle::{Children, ChildrenByName, NodeHandle},
+        handle_mut::{ChildrenMut, NodeHandleMut},
+        iter::{
+            Ancestors, BreadthFirst, Descendants, DescendantsPostOrder, FollowingSiblings,
+            PrecedingSiblings,
+        },
         NodeId,
     },
+    query::{Query, QueryError, Select},
+};
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub use self::{deserializer::NodeDeserializer, error::DeserializeError};
+use self::{
+    journal::{Change, Journal, Position},
+    name_index::ChildNameIndex,
+    node::{AttributePool, AttributeValues, NodeData, NodeNameSym, TreeId},
 };
 
 mod macros;
 
+mod approx;
+mod bulk;
+mod canonical;
+mod checked;
+#[cfg(feature = "serde")]
+mod deserializer;
+pub mod diff;
 mod error;
+pub mod events;
+mod hash;
+mod journal;
 mod loader;
+mod memory;
+mod name_index;
 mod node;
+pub mod query;
+#[cfg(feature = "serde")]
+mod serde;
 
 /// FBX data tree.
 ///
@@ -31,6 +69,9 @@ mod node;
 ///
 /// * Access
 ///     + [`root`][`Self::root`]
+///     + [`root_mut`][`Self::root_mut`]
+///     + [`get_node_mut`][`Self::get_node_mut`]
+///     + [`select`][`Self::select`]
 /// * Create nodes / modify tree
 ///     + [`create_node`][`Self::create_node`]
 ///     + [`append_new`][`Self::append_new`]
@@ -41,13 +82,74 @@ mod node;
 ///     + [`prepend`][`Self::prepend`]
 ///     + [`insert_after`][`Self::insert_after`]
 ///     + [`insert_before`][`Self::insert_before`]
+///     + [`clone_subtree`][`Self::clone_subtree`]
+///     + [`import_subtree`][`Self::import_subtree`]
 ///     + [`detach`][`Self::detach`]
+///     + [`remove_subtree`][`Self::remove_subtree`]
+///     + [`compact`][`Self::compact`]
+///     + [`canonicalize`][`Self::canonicalize`]
+///     + [`retain`][`Self::retain`]
 /// * Modify node
 ///     + [`append_attribute`][`Self::append_attribute`]
 ///     + [`get_attribute_mut`][`Self::get_attribute_mut`]
 ///     + [`take_attributes_vec`][`Self::take_attributes_vec`]
 ///     + [`set_attributes_vec`][`Self::set_attributes_vec`]
-#[derive(Debug, Clone, PartialEq)]
+///     + [`for_each_mut`][`Self::for_each_mut`]
+///     + [`map_attributes`][`Self::map_attributes`]
+/// * History
+///     + [`set_journal_enabled`][`Self::set_journal_enabled`]
+///     + [`begin_transaction`][`Self::begin_transaction`]
+///     + [`commit_transaction`][`Self::commit_transaction`]
+///     + [`rollback_transaction`][`Self::rollback_transaction`]
+///     + [`undo`][`Self::undo`]
+///     + [`redo`][`Self::redo`]
+///
+/// # Node IDs
+///
+/// A [`NodeId`] remembers the tree it belongs to. Methods taking node IDs panic
+/// if a given node ID belongs to another tree or the node has been removed,
+/// instead of silently accessing an unrelated node.
+/// Clones of a tree share the identity, and node IDs are valid in both.
+/// [`compact`][`Self::compact`] gives the tree a new identity.
+///
+/// Many editing methods have non-panicking `try_*` variants (such as
+/// [`try_append`][`Self::try_append`] and
+/// [`try_get_attribute_mut`][`Self::try_get_attribute_mut`]), which return
+/// [`EditError`] instead of panicking.
+///
+/// # Serde
+///
+/// With `serde` feature, a tree is serialized as a struct with `children`
+/// field, and a node is serialized as a struct with `name`, `attributes`, and
+/// `children` fields. For example, the tree below
+///
+/// ```text
+/// Node0: [42i16] {
+///     Node0_0: {}
+/// }
+/// ```
+///
+/// is serialized as the JSON below.
+///
+/// ```json
+/// {
+///   "children": [
+///     {
+///       "name": "Node0",
+///       "attributes": [{"I16": 42}],
+///       "children": [{"name": "Node0_0", "attributes": [], "children": []}]
+///     }
+///   ]
+/// }
+/// ```
+///
+/// On deserialization, `attributes` and `children` fields can be omitted, and
+/// unknown fields are ignored.
+/// See [`AttributeValue`] for the representation of attributes.
+///
+/// To deserialize nodes into user-defined types, use [`NodeHandle::deserialize`]
+/// (see [`NodeDeserializer`] for details).
+#[derive(Debug, Clone)]
 pub struct Tree {
     /// Tree data.
     arena: Arena<NodeData>,
@@ -55,6 +157,25 @@ pub struct Tree {
     node_names: StringInterner<DefaultBackend<NodeNameSym>>,
     /// (Implicit) root node ID.
     root_id: NodeId,
+    /// Attribute pool used in the compact storage mode.
+    attribute_pool: AttributePool,
+    /// Whether the compact storage mode is enabled.
+    compact_storage: bool,
+    /// Number of value slots in the attribute pool no longer used by any
+    /// node.
+    unused_pool_slots: usize,
+    /// Number of string symbol slots in the attribute pool no longer used by
+    /// any node.
+    unused_string_slots: usize,
+    /// Index of children by names.
+    child_name_index: Option<ChildNameIndex>,
+    /// Change journal.
+    journal: Option<Journal>,
+    /// Nodes removed while the change journal is enabled.
+    ///
+    /// They are kept detached in the arena so that the removal can be undone,
+    /// but are treated as removed.
+    removed_nodes: HashSet<indextree::NodeId>,
 }
 
 impl Tree {
@@ -65,21 +186,67 @@ impl Tree {
         NodeHandle::new(self, self.root_id)
     }
 
+    /// Returns the mutable root node handle.
+    #[inline]
+    #[must_use]
+    pub fn root_mut(&mut self) -> NodeHandleMut<'_> {
+        let root_id = self.root_id;
+        NodeHandleMut::new(self, root_id)
+    }
+
+    /// Returns the mutable node handle if the node ID is used in the tree.
+    #[inline]
+    #[must_use]
+    pub fn get_node_mut(&mut self, node_id: NodeId) -> Option<NodeHandleMut<'_>> {
+        if self.contains_node(node_id) {
+            Some(NodeHandleMut::new(self, node_id))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator of nodes selected by the given query.
+    ///
+    /// This is equivalent to `self.root().select(query)`.
+    /// See [`query`] module documentation for syntax.
+    #[inline]
+    pub fn select(&self, query: &str) -> Result<Select<'_>, QueryError> {
+        self.root().select(query)
+    }
+
     /// Creates a new `Tree`.
+    ///
+    /// The tree is given a new identity.
     #[inline]
     #[must_use]
     fn new(
         arena: Arena<NodeData>,
         node_names: StringInterner<DefaultBackend<NodeNameSym>>,
-        root_id: NodeId,
+        root_id: indextree::NodeId,
+        attribute_pool: AttributePool,
+        compact_storage: bool,
     ) -> Self {
         Self {
             arena,
             node_names,
-            root_id,
+            root_id: NodeId::new(root_id, TreeId::new()),
+            attribute_pool,
+            compact_storage,
+            unused_pool_slots: 0,
+            unused_string_slots: 0,
+            child_name_index: None,
+            journal: None,
+            removed_nodes: HashSet::new(),
         }
     }
 
+    /// Returns the identity of the tree.
+    #[inline]
+    #[must_use]
+    pub(crate) fn tree_id(&self) -> TreeId {
+        self.root_id.tree_id()
+    }
+
     /// Returns internally managed node data.
     ///
     /// # Panics
@@ -87,12 +254,10 @@ impl Tree {
     /// Panics if a node with the given node ID does not exist in the tree.
     #[must_use]
     pub(crate) fn node(&self, node_id: NodeId) -> &indextree::Node<NodeData> {
-        self.arena.get(node_id.raw()).unwrap_or_else(|| {
-            panic!(
-                "The given node ID is not used in the tree: node_id={:?}",
-                node_id
-            )
-        })
+        self.assert_node(node_id);
+        self.arena
+            .get(node_id.raw())
+            .expect("Should never fail: the node ID is checked")
     }
 
     /// Returns the string corresponding to the node name symbol.
@@ -113,10 +278,160 @@ impl Tree {
         self.node_names.get(name)
     }
 
+    /// Returns the attributes of the node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node with the given node ID does not exist in the tree.
+    #[must_use]
+    pub(crate) fn node_attributes(&self, node_id: NodeId) -> &[AttributeValue] {
+        self.node(node_id).get().attributes(&self.attribute_pool)
+    }
+
+    /// Returns an iterator of the attributes of the node, without creating
+    /// the cache of the attributes with interned string values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node with the given node ID does not exist in the tree.
+    #[must_use]
+    pub(crate) fn node_attribute_values(&self, node_id: NodeId) -> AttributeValues<'_> {
+        self.node(node_id)
+            .get()
+            .attribute_values(&self.attribute_pool)
+    }
+
+    /// Returns the mutable attributes of the node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node with the given node ID does not exist in the tree.
+    #[must_use]
+    pub(crate) fn node_attributes_mut(&mut self, node_id: NodeId) -> &mut [AttributeValue] {
+        self.assert_node(node_id);
+        self.restore_strings(node_id);
+        let node = self.arena.get_mut(node_id.raw()).expect("Invalid node ID");
+        node.get_mut().attributes_mut(&mut self.attribute_pool)
+    }
+
+    /// Returns the mutable attributes `Vec` of the node.
+    ///
+    /// If the attributes are in the attribute pool, they are moved out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node with the given node ID does not exist in the tree.
+    #[must_use]
+    pub(crate) fn node_attributes_vec_mut(&mut self, node_id: NodeId) -> &mut Vec<AttributeValue> {
+        self.assert_node(node_id);
+        let data = self
+            .arena
+            .get_mut(node_id.raw())
+            .expect("Invalid node ID")
+            .get_mut();
+        let (pooled, strings, _) = data.attributes_storage();
+        if pooled != 0 {
+            data.move_attributes_from_pool(&mut self.attribute_pool);
+            self.release_pool_slots(pooled, strings);
+        }
+        let node = self.arena.get_mut(node_id.raw()).expect("Invalid node ID");
+        node.get_mut().attributes_vec_mut(&mut self.attribute_pool)
+    }
+
+    /// Puts the interned string values of the node back into the attribute
+    /// pool, and marks the string symbol slots as unused.
+    fn restore_strings(&mut self, node_id: NodeId) {
+        let data = self.arena[node_id.raw()].get_mut();
+        let strings = data.restore_strings(&mut self.attribute_pool);
+        if strings != 0 {
+            self.release_pool_slots(0, strings);
+        }
+    }
+
+    /// Marks the given numbers of value slots and string symbol slots in the
+    /// attribute pool as unused, and repacks the pool if more than half of
+    /// either is unused.
+    fn release_pool_slots(&mut self, values: usize, strings: usize) {
+        self.unused_pool_slots += values;
+        self.unused_string_slots += strings;
+        let (values_len, strings_len) = self.attribute_pool.lens();
+        if self.unused_pool_slots * 2 <= values_len && self.unused_string_slots * 2 <= strings_len {
+            return;
+        }
+
+        let mut old_pool = std::mem::take(&mut self.attribute_pool);
+        let mut new_pool = old_pool.take_string_table();
+        for node in self.arena.iter_mut().filter(|node| !node.is_removed()) {
+            node.get_mut()
+                .repack_pooled_attributes(&mut old_pool, &mut new_pool);
+        }
+        new_pool.shrink_to_fit();
+        self.attribute_pool = new_pool;
+        self.unused_pool_slots = 0;
+        self.unused_string_slots = 0;
+    }
+
+    /// Returns whether the compact storage mode is enabled.
+    ///
+    /// See [`set_compact_storage`][`Self::set_compact_storage`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_compact_storage(&self) -> bool {
+        self.compact_storage
+    }
+
+    /// Enables or disables the compact storage mode.
+    ///
+    /// In the compact storage mode, attributes of all nodes are stored in a
+    /// single pool owned by the tree, instead of a separate `Vec` for each
+    /// node. This saves per-node heap allocations and unused capacities.
+    /// Heap buffers of arrays and binaries are also shrunk to fit, and string
+    /// values are interned in a string table of the pool, since FBX data
+    /// (such as `P` nodes in `Properties70`) repeats the same strings many
+    /// times.
+    ///
+    /// Attributes of a node with string values are created from the string
+    /// table and cached on the first access by [`NodeHandle::attributes`]
+    /// (and deserialization), since they are borrowed as a slice of
+    /// [`AttributeValue`]s. Other readers in this crate (such as comparison,
+    /// hashing, diff, writing, and events) do not create the cache.
+    /// Modifying the attributes puts the string values back into the pool.
+    ///
+    /// Nodes created or whose attributes are added or removed after enabling
+    /// this mode have their own `Vec`s, and the slots in the pool they used
+    /// become unused, as well as the slots of removed nodes.
+    /// The pool is repacked automatically when more than half of it becomes
+    /// unused.
+    /// Calling this method with `true` again moves the attributes of all nodes
+    /// into a repacked pool.
+    ///
+    /// Use [`Loader::compact_storage`] to load a tree in this mode from the
+    /// beginning.
+    pub fn set_compact_storage(&mut self, enabled: bool) {
+        let mut old_pool = std::mem::take(&mut self.attribute_pool);
+        self.unused_pool_slots = 0;
+        self.unused_string_slots = 0;
+        let live_nodes = self.arena.iter_mut().filter(|node| !node.is_removed());
+        if enabled {
+            let mut new_pool = AttributePool::default();
+            for node in live_nodes {
+                node.get_mut()
+                    .move_attributes_to_pool(&mut old_pool, &mut new_pool);
+            }
+            new_pool.shrink_to_fit();
+            self.attribute_pool = new_pool;
+        } else {
+            for node in live_nodes {
+                node.get_mut().move_attributes_from_pool(&mut old_pool);
+            }
+        }
+        self.compact_storage = enabled;
+    }
+
     /// Checks whether or not the given node ID is used in the tree.
     #[must_use]
     pub(crate) fn contains_node(&self, node_id: NodeId) -> bool {
-        self.arena.get(node_id.raw()).is_some()
+        self.check_node(node_id).is_ok()
     }
 
     /// Creates an orphan node in the arena.
@@ -127,7 +442,7 @@ impl Tree {
         let name_sym = self.node_names.get_or_intern(name);
         let new_node = self.arena.new_node(NodeData::new(name_sym, Vec::new()));
 
-        NodeId::new(new_node)
+        NodeId::new(new_node, self.tree_id())
     }
 
     /// Detaches the node and appends it to the given parent node.
@@ -140,7 +455,12 @@ impl Tree {
     /// * the `new_last_child` is `parent`, or
     /// * the `new_last_child` is an ancestor of `parent`.
     pub fn append(&mut self, new_last_child: NodeId, parent: NodeId) {
+        self.assert_node(new_last_child);
+        self.assert_node(parent);
+        self.record_with(|tree| Change::move_back(tree, new_last_child));
+        self.unlink_from_child_name_index(new_last_child);
         parent.raw().append(new_last_child.raw(), &mut self.arena);
+        self.link_to_child_name_index(new_last_child);
     }
 
     /// Detaches the node and prepends it to the given parent node.
@@ -153,7 +473,12 @@ impl Tree {
     /// * the `new_first_child` is `parent`, or
     /// * the `new_first_child` is an ancestor of `parent`.
     pub fn prepend(&mut self, new_first_child: NodeId, parent: NodeId) {
+        self.assert_node(new_first_child);
+        self.assert_node(parent);
+        self.record_with(|tree| Change::move_back(tree, new_first_child));
+        self.unlink_from_child_name_index(new_first_child);
         parent.raw().prepend(new_first_child.raw(), &mut self.arena);
+        self.link_to_child_name_index(new_first_child);
     }
 
     /// Detaches the node and inserts it after the given base node.
@@ -165,12 +490,17 @@ impl Tree {
     /// * any of the given node IDs are not used in the tree,
     /// * the `new_next_sibling` is `prev_sibling`.
     pub fn insert_after(&mut self, new_next_sibling: NodeId, prev_sibling: NodeId) {
+        self.assert_node(new_next_sibling);
+        self.assert_node(prev_sibling);
+        self.record_with(|tree| Change::move_back(tree, new_next_sibling));
+        self.unlink_from_child_name_index(new_next_sibling);
         prev_sibling
             .raw()
             .insert_after(new_next_sibling.raw(), &mut self.arena);
+        self.link_to_child_name_index(new_next_sibling);
     }
 
-    /// Detaches the node and inserts it after the given base node.
+    /// Detaches the node and inserts it before the given base node.
     ///
     /// # Panics
     ///
@@ -179,9 +509,14 @@ impl Tree {
     /// * any of the given node IDs are not used in the tree,
     /// * the `new_prev_sibling` is `next_sibling`.
     pub fn insert_before(&mut self, new_prev_sibling: NodeId, next_sibling: NodeId) {
+        self.assert_node(new_prev_sibling);
+        self.assert_node(next_sibling);
+        self.record_with(|tree| Change::move_back(tree, new_prev_sibling));
+        self.unlink_from_child_name_index(new_prev_sibling);
         next_sibling
             .raw()
-            .insert_after(new_prev_sibling.raw(), &mut self.arena);
+            .insert_before(new_prev_sibling.raw(), &mut self.arena);
+        self.link_to_child_name_index(new_prev_sibling);
     }
 
     /// Creates a new node and appends to the given parent node.
@@ -190,11 +525,14 @@ impl Tree {
     ///
     /// Panics if the given node ID is not used in the tree.
     pub fn append_new(&mut self, parent: NodeId, name: &str) -> NodeId {
+        self.assert_node(parent);
         let name_sym = self.node_names.get_or_intern(name);
         let new_child = self.arena.new_node(NodeData::new(name_sym, Vec::new()));
         parent.raw().append(new_child, &mut self.arena);
+        self.link_to_child_name_index(NodeId::new(new_child, self.tree_id()));
+        self.record_new_node(NodeId::new(new_child, self.tree_id()));
 
-        NodeId::new(new_child)
+        NodeId::new(new_child, self.tree_id())
     }
 
     /// Creates a new node and prepends to the given parent node.
@@ -203,11 +541,14 @@ impl Tree {
     ///
     /// Panics if the given node ID is not used in the tree.
     pub fn prepend_new(&mut self, parent: NodeId, name: &str) -> NodeId {
+        self.assert_node(parent);
         let name_sym = self.node_names.get_or_intern(name);
         let new_child = self.arena.new_node(NodeData::new(name_sym, Vec::new()));
         parent.raw().prepend(new_child, &mut self.arena);
+        self.link_to_child_name_index(NodeId::new(new_child, self.tree_id()));
+        self.record_new_node(NodeId::new(new_child, self.tree_id()));
 
-        NodeId::new(new_child)
+        NodeId::new(new_child, self.tree_id())
     }
 
     /// Creates a new node and inserts after the given sibling node.
@@ -216,12 +557,15 @@ impl Tree {
     ///
     /// Panics if the given node ID is invalid (i.e. not used or root node).
     pub fn insert_new_after(&mut self, sibling: NodeId, name: &str) -> NodeId {
+        self.assert_node(sibling);
         assert_ne!(sibling, self.root_id, "Root node should have no siblings");
         let name_sym = self.node_names.get_or_intern(name);
         let new_child = self.arena.new_node(NodeData::new(name_sym, Vec::new()));
         sibling.raw().insert_after(new_child, &mut self.arena);
+        self.link_to_child_name_index(NodeId::new(new_child, self.tree_id()));
+        self.record_new_node(NodeId::new(new_child, self.tree_id()));
 
-        NodeId::new(new_child)
+        NodeId::new(new_child, self.tree_id())
     }
 
     /// Creates a new node and inserts before the given sibling node.
@@ -230,12 +574,15 @@ impl Tree {
     ///
     /// Panics if the given node ID is invalid (i.e. not used or root node).
     pub fn insert_new_before(&mut self, sibling: NodeId, name: &str) -> NodeId {
+        self.assert_node(sibling);
         assert_ne!(sibling, self.root_id, "Root node should have no siblings");
         let name_sym = self.node_names.get_or_intern(name);
         let new_child = self.arena.new_node(NodeData::new(name_sym, Vec::new()));
         sibling.raw().insert_before(new_child, &mut self.arena);
+        self.link_to_child_name_index(NodeId::new(new_child, self.tree_id()));
+        self.record_new_node(NodeId::new(new_child, self.tree_id()));
 
-        NodeId::new(new_child)
+        NodeId::new(new_child, self.tree_id())
     }
 
     /// Detaches the subtree from the tree.
@@ -245,9 +592,224 @@ impl Tree {
     /// The detached node and its descendants are kept in the arena, so they can
     /// be inserted nearby some other nodes (in the same arena) later.
     pub fn detach(&mut self, subtree_root: NodeId) {
+        self.assert_node(subtree_root);
+        self.record_with(|tree| Change::move_back(tree, subtree_root));
+        self.unlink_from_child_name_index(subtree_root);
         subtree_root.raw().detach(&mut self.arena);
     }
 
+    /// Reorders the children of the node, and returns the old order.
+    ///
+    /// `children` should be a permutation of the children of `parent`.
+    /// The change is not recorded to the journal.
+    pub(crate) fn reorder_children(&mut self, parent: NodeId, children: &[NodeId]) -> Vec<NodeId> {
+        let old = parent
+            .raw()
+            .children(&self.arena)
+            .map(|id| NodeId::new(id, self.tree_id()))
+            .collect();
+        self.with_child_name_index_deferred(|tree| {
+            for &child in children {
+                tree.unlink_from_child_name_index(child);
+                child.raw().detach(&mut tree.arena);
+                parent.raw().append(child.raw(), &mut tree.arena);
+                tree.link_to_child_name_index(child);
+            }
+        });
+        old
+    }
+
+    /// Deep-copies the subtree in the tree, and appends it to the given parent
+    /// node.
+    ///
+    /// Returns the node ID of the root of the copied subtree.
+    ///
+    /// The parent can be in the source subtree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    ///
+    /// * any of the given node IDs are not used in the tree, or
+    /// * the `source` is the root node.
+    pub fn clone_subtree(&mut self, parent: NodeId, source: NodeId) -> NodeId {
+        let nodes = collect_subtree(source.to_handle(self), |node| node.name_sym());
+        self.append_subtree(parent, nodes)
+    }
+
+    /// Deep-copies the subtree from another tree, and appends it to the given
+    /// parent node.
+    ///
+    /// Returns the node ID of the root of the copied subtree.
+    ///
+    /// Node names are re-interned in this tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    ///
+    /// * the given parent node ID is not used in the tree, or
+    /// * the `source` is the root node of the source tree.
+    pub fn import_subtree(&mut self, parent: NodeId, source: NodeHandle<'_>) -> NodeId {
+        let node_names = &mut self.node_names;
+        let nodes = collect_subtree(source, |node| node_names.get_or_intern(node.name()));
+        self.append_subtree(parent, nodes)
+    }
+
+    /// Appends the nodes collected by [`collect_subtree`] to the given parent
+    /// node, and returns the ID of the first one.
+    fn append_subtree(&mut self, parent: NodeId, nodes: Vec<CollectedNode>) -> NodeId {
+        assert!(self.contains_node(parent), "Invalid node ID");
+        let mut new_ids: Vec<indextree::NodeId> = Vec::with_capacity(nodes.len());
+        for (name_sym, attributes, parent_index) in nodes {
+            let new_id = self.arena.new_node(NodeData::new(name_sym, attributes));
+            let new_parent = parent_index.map_or(parent.raw(), |i| new_ids[i]);
+            new_parent.append(new_id, &mut self.arena);
+            self.link_to_child_name_index(NodeId::new(new_id, self.tree_id()));
+            new_ids.push(new_id);
+        }
+        self.record_new_node(NodeId::new(new_ids[0], self.tree_id()));
+
+        NodeId::new(new_ids[0], self.tree_id())
+    }
+
+    /// Records the change removing the new node, if the journal is enabled.
+    fn record_new_node(&mut self, node: NodeId) {
+        self.record_with(|_| Change::Remove { node });
+    }
+
+    /// Removes the subtree from the tree.
+    ///
+    /// Unlike [`detach`][`Self::detach`], the node and its descendants are
+    /// removed from the arena and their attributes are dropped.
+    /// Node IDs of the removed nodes become invalid, and their slots will be
+    /// reused by nodes created later.
+    ///
+    /// Note that the arena itself does not shrink; use
+    /// [`compact`][`Self::compact`] to release the memory.
+    ///
+    /// If the change journal is enabled, the nodes are kept in the arena until
+    /// the removal can no longer be undone (see
+    /// [`set_journal_enabled`][`Self::set_journal_enabled`]), but their node
+    /// IDs become invalid as well.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given node is the root node.
+    pub fn remove_subtree(&mut self, subtree_root: NodeId) {
+        self.assert_node(subtree_root);
+        assert_ne!(
+            subtree_root, self.root_id,
+            "Root node should not be removed"
+        );
+        if self.is_journal_enabled() {
+            self.record_with(|tree| Change::Restore {
+                node: subtree_root,
+                to: Position::current(tree, subtree_root),
+            });
+            self.mark_subtree_removed(subtree_root);
+            return;
+        }
+        self.free_subtree(subtree_root);
+    }
+
+    /// Detaches the subtree and marks the nodes as removed, keeping them in
+    /// the arena.
+    pub(crate) fn mark_subtree_removed(&mut self, subtree_root: NodeId) {
+        self.unlink_from_child_name_index(subtree_root);
+        subtree_root.raw().detach(&mut self.arena);
+        self.removed_nodes
+            .extend(subtree_root.raw().descendants(&self.arena));
+    }
+
+    /// Clears the removed marks of the subtree.
+    pub(crate) fn unmark_subtree_removed(&mut self, subtree_root: NodeId) {
+        for id in subtree_root.raw().descendants(&self.arena) {
+            self.removed_nodes.remove(&id);
+        }
+    }
+
+    /// Removes the nodes marked as removed from the arena, except for the
+    /// subtrees the given changes can restore.
+    pub(crate) fn free_removed_nodes(&mut self, restorable: &HashSet<NodeId>) {
+        let tree_id = self.tree_id();
+        let roots = self
+            .removed_nodes
+            .iter()
+            .map(|&id| NodeId::new(id, tree_id))
+            .filter(|id| self.arena[id.raw()].parent().is_none() && !restorable.contains(id))
+            .collect::<Vec<_>>();
+        for root in roots {
+            self.unmark_subtree_removed(root);
+            self.free_subtree(root);
+        }
+    }
+
+    /// Removes the subtree from the arena, and releases its pool slots.
+    fn free_subtree(&mut self, subtree_root: NodeId) {
+        let (pooled, strings) = subtree_root
+            .raw()
+            .descendants(&self.arena)
+            .map(|id| self.arena[id].get().attributes_storage())
+            .fold((0, 0), |(values, strings), (v, s, _)| {
+                (values + v, strings + s)
+            });
+        self.unlink_from_child_name_index(subtree_root);
+        self.forget_subtree_in_child_name_index(subtree_root);
+        subtree_root.raw().remove_subtree(&mut self.arena);
+        if pooled != 0 {
+            self.release_pool_slots(pooled, strings);
+        }
+    }
+
+    /// Rebuilds the internal storage with only the nodes reachable from the
+    /// root node.
+    ///
+    /// Removed nodes, detached nodes (and their descendants), and node names
+    /// no longer used by reachable nodes are dropped.
+    ///
+    /// Returns the map from old node IDs to new node IDs of the reachable
+    /// nodes (including the root node).
+    /// All other node IDs become invalid.
+    ///
+    /// If the change journal is enabled, the history and open transactions are
+    /// dropped.
+    pub fn compact(&mut self) -> HashMap<NodeId, NodeId> {
+        let mut old = std::mem::take(self);
+        let mut map = HashMap::new();
+        map.insert(old.root_id, self.root_id);
+
+        let old_tree_id = old.tree_id();
+        let old_ids = old.root_id.raw().descendants(&old.arena).skip(1);
+        for old_id in old_ids.collect::<Vec<_>>() {
+            let node = old.arena.get_mut(old_id).expect("Should never fail");
+            let parent = NodeId::new(node.parent().expect("Should never fail"), old_tree_id);
+            let data = node.get_mut();
+            let name = old
+                .node_names
+                .resolve(data.name_sym())
+                .expect("Should never fail");
+            let name_sym = self.node_names.get_or_intern(name);
+            let attributes = std::mem::take(data.attributes_vec_mut(&mut old.attribute_pool));
+
+            let new_id = self.arena.new_node(NodeData::new(name_sym, attributes));
+            map[&parent].raw().append(new_id, &mut self.arena);
+            map.insert(
+                NodeId::new(old_id, old_tree_id),
+                NodeId::new(new_id, self.tree_id()),
+            );
+        }
+        if old.compact_storage {
+            self.set_compact_storage(true);
+        }
+        if old.has_child_name_index() {
+            self.build_child_name_index();
+        }
+        self.set_journal_enabled(old.is_journal_enabled());
+
+        map
+    }
+
     /// Creates a new node and inserts before the given sibling node.
     ///
     /// # Panics
@@ -264,8 +826,11 @@ impl Tree {
     /// Panics if the given node ID is invalid (i.e. not used or root node).
     fn append_attribute_impl(&mut self, node_id: NodeId, v: AttributeValue) {
         assert_ne!(node_id, self.root_id, "Root node should have no attributes");
-        let node = self.arena.get_mut(node_id.raw()).expect("Invalid node ID");
-        node.get_mut().append_attribute(v)
+        self.record_with(|tree| Change::RemoveAttribute {
+            node: node_id,
+            index: tree.node_attribute_values(node_id).len(),
+        });
+        self.node_attributes_vec_mut(node_id).push(v)
     }
 
     /// Returns a mutable reference to the node attribute at the given index.
@@ -275,8 +840,17 @@ impl Tree {
     /// Panics if the given node ID is invalid (i.e. not used or root node).
     #[must_use]
     pub fn get_attribute_mut(&mut self, node_id: NodeId, i: usize) -> Option<&mut AttributeValue> {
-        let node = self.arena.get_mut(node_id.raw()).expect("Invalid node ID");
-        node.get_mut().get_attribute_mut(i)
+        if let Some(value) = self.node_attribute_values(node_id).nth(i) {
+            if self.is_journal_enabled() {
+                let value = value.into_owned();
+                self.record_with(|_| Change::SetAttribute {
+                    node: node_id,
+                    index: i,
+                    value,
+                });
+            }
+        }
+        self.node_attributes_mut(node_id).get_mut(i)
     }
 
     /// Takes all attributes as a `Vec`.
@@ -287,8 +861,12 @@ impl Tree {
     ///
     /// Panics if the given node ID is invalid (i.e. not used or root node).
     pub fn take_attributes_vec(&mut self, node_id: NodeId) -> Vec<AttributeValue> {
-        let node = self.arena.get_mut(node_id.raw()).expect("Invalid node ID");
-        node.get_mut().replace_attributes(Default::default())
+        let attributes = std::mem::take(self.node_attributes_vec_mut(node_id));
+        self.record_with(|_| Change::SetAttributes {
+            node: node_id,
+            attributes: attributes.clone(),
+        });
+        attributes
     }
 
     /// Sets the given `Vec` of attribute values as the node attributes.
@@ -299,9 +877,11 @@ impl Tree {
     ///
     /// Panics if the given node ID is invalid (i.e. not used or root node).
     pub fn set_attributes_vec(&mut self, node_id: NodeId, new: Vec<AttributeValue>) {
-        let node = self.arena.get_mut(node_id.raw()).expect("Invalid node ID");
-        // Ignore the returned value.
-        node.get_mut().replace_attributes(new);
+        let old = std::mem::replace(self.node_attributes_vec_mut(node_id), new);
+        self.record_with(|_| Change::SetAttributes {
+            node: node_id,
+            attributes: old,
+        });
     }
 
     /// Compares trees strictly.
@@ -318,6 +898,24 @@ impl Tree {
         self.root().strict_eq(&other.root())
     }
 
+    /// Compares trees approximately.
+    ///
+    /// See [`TreeApproxEqOptions`] for details.
+    #[inline]
+    #[must_use]
+    pub fn approx_eq(&self, other: &Self, options: &TreeApproxEqOptions) -> bool {
+        self.root().approx_eq(&other.root(), options)
+    }
+
+    /// Computes the structural diff from this tree to the other.
+    ///
+    /// See [`TreeDiff`] for details.
+    #[inline]
+    #[must_use]
+    pub fn diff(&self, other: &Self, options: &DiffOptions) -> TreeDiff {
+        TreeDiff::new(self.root(), other.root(), options)
+    }
+
     /// Pretty-print the tree for debugging purpose.
     ///
     /// Be careful, this output format may change in future.
@@ -328,21 +926,84 @@ impl Tree {
     }
 }
 
+impl PartialEq for Tree {
+    /// Compares internal states of the trees, except for the identities of
+    /// the trees, the index of children by names, and the change journal.
+    fn eq(&self, other: &Self) -> bool {
+        self.arena == other.arena
+            && self.node_names == other.node_names
+            && self.root_id.raw() == other.root_id.raw()
+            && self.attribute_pool == other.attribute_pool
+            && self.compact_storage == other.compact_storage
+    }
+}
+
 impl Default for Tree {
     fn default() -> Self {
         let mut arena = Arena::new();
         let mut node_names = StringInterner::new();
-        let root_id =
-            NodeId::new(arena.new_node(NodeData::new(node_names.get_or_intern(""), Vec::new())));
+        let root_id = NodeId::new(
+            arena.new_node(NodeData::new(node_names.get_or_intern(""), Vec::new())),
+            TreeId::new(),
+        );
 
         Self {
             arena,
             node_names,
             root_id,
+            attribute_pool: AttributePool::default(),
+            compact_storage: false,
+            unused_pool_slots: 0,
+            unused_string_slots: 0,
+            child_name_index: None,
+            journal: None,
+            removed_nodes: HashSet::new(),
         }
     }
 }
 
+/// A node collected by [`collect_subtree`].
+///
+/// This is a tuple of the node name symbol, the attributes, and the index of
+/// the parent node (or `None` for the subtree root).
+type CollectedNode = (NodeNameSym, Vec<AttributeValue>, Option<usize>);
+
+/// Collects the nodes in the subtree in preorder.
+///
+/// # Panics
+///
+/// Panics if the given node is the root node.
+fn collect_subtree(
+    source: NodeHandle<'_>,
+    mut name_sym: impl FnMut(NodeHandle<'_>) -> NodeNameSym,
+) -> Vec<CollectedNode> {
+    let tree = source.tree();
+    assert_ne!(
+        source.node_id(),
+        tree.root_id,
+        "Root node should not be copied"
+    );
+    let mut indices = HashMap::new();
+    source
+        .node_id()
+        .raw()
+        .descendants(&tree.arena)
+        .enumerate()
+        .map(|(index, id)| {
+            indices.insert(id, index);
+            let node = NodeId::new(id, tree.tree_id()).to_handle(tree);
+            let parent_index = if index == 0 {
+                None
+            } else {
+                let parent = node.parent().expect("Should never fail").node_id();
+                Some(indices[&parent.raw()])
+            };
+            let attributes = node.attribute_values().map(Cow::into_owned).collect();
+            (name_sym(node), attributes, parent_index)
+        })
+        .collect()
+}
+
 /// A simple wrapper for pretty-printing tree.
 struct DebugTree<'a> {
     /// Tree.
@@ -369,7 +1030,10 @@ impl fmt::Debug for DebugNodeHandle<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Node")
             .field("name", &self.node.name())
-            .field("attributes", &self.node.attributes())
+            .field(
+                "attributes",
+                &self.node.attribute_values().collect::<Vec<_>>(),
+            )
             .field("children", &DebugNodeHandleChildren { node: self.node })
             .finish()
     }
@@ -626,3 +1290,107 @@ impl DepthFirstTraverseSubtree {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tree_v7400;
+
+    #[test]
+    fn remove_and_compact() {
+        let mut tree = tree_v7400! {
+            Objects: {
+                Model: [1i64] { Keep: {} },
+                AnimationCurve: [2i64, vec![0.0f32; 16]] { KeyTime: {} },
+                AnimationCurve: [3i64, vec![0.0f32; 16]] {},
+            },
+            Detached: {},
+        };
+        let expected = tree_v7400! {
+            Objects: {
+                Model: [1i64] { Keep: {} },
+            },
+        };
+
+        let objects = tree.root().first_child().unwrap().node_id();
+        let curves = objects
+            .to_handle(&tree)
+            .children_by_name("AnimationCurve")
+            .map(|node| node.node_id())
+            .collect::<Vec<_>>();
+        for curve in curves.iter().copied() {
+            tree.remove_subtree(curve);
+        }
+        let detached = tree.root().last_child().unwrap().node_id();
+        tree.detach(detached);
+        assert!(tree.strict_eq(&expected));
+        assert!(!tree.contains_node(curves[0]));
+        assert_eq!(tree.arena.len(), 8);
+
+        let old_root = tree.root_id;
+        let map = tree.compact();
+        assert!(tree.strict_eq(&expected));
+        assert_eq!(tree.arena.len(), 4);
+        assert_eq!(map.len(), 4);
+        assert_eq!(map[&old_root], tree.root_id);
+        assert_eq!(map[&objects].to_handle(&tree).name(), "Objects");
+        assert!(!map.contains_key(&curves[0]));
+        assert!(!map.contains_key(&detached));
+        assert_eq!(tree.node_name_sym("AnimationCurve"), None);
+        assert_eq!(tree.node_name_sym("Detached"), None);
+        assert!(tree.node_name_sym("Keep").is_some());
+    }
+
+    #[test]
+    fn copy_subtrees() {
+        let template = tree_v7400! {
+            Definitions: {
+                ObjectType: ["Model"] {
+                    PropertyTemplate: ["FbxNode"] {},
+                },
+            },
+        };
+        let mut tree = tree_v7400! {
+            Definitions: {
+                ObjectType: ["Geometry"] {},
+            },
+        };
+
+        let definitions = tree.root().first_child().unwrap().node_id();
+        let source = template
+            .root()
+            .first_child()
+            .and_then(|node| node.first_child())
+            .unwrap();
+        let imported = tree.import_subtree(definitions, source);
+        assert!(imported.to_handle(&tree).strict_eq(&source));
+
+        // Copy a subtree into itself.
+        let cloned = tree.clone_subtree(imported, imported);
+        assert_eq!(
+            cloned.to_handle(&tree).parent().unwrap().node_id(),
+            imported
+        );
+        let expected = tree_v7400! {
+            Definitions: {
+                ObjectType: ["Geometry"] {},
+                ObjectType: ["Model"] {
+                    PropertyTemplate: ["FbxNode"] {},
+                    ObjectType: ["Model"] {
+                        PropertyTemplate: ["FbxNode"] {},
+                    },
+                },
+            },
+        };
+        assert!(tree.strict_eq(&expected));
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_root() {
+        let mut tree = Tree::default();
+        let root = tree.root_id;
+        tree.remove_subtree(root);
+    }
+}
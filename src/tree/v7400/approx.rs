@@ -0,0 +1,222 @@
+//! Approximate comparison of trees.
+
+use std::{borrow::Cow, collections::HashSet};
+
+use crate::{
+    low::v7400::{ApproxEqOptions, AttributeValue},
+    tree::v7400::NodeHandle,
+};
+
+/// Options for approximate comparison of trees.
+///
+/// The default value compares trees as strictly as
+/// [`Tree::strict_eq`][`crate::tree::v7400::Tree::strict_eq`] does.
+///
+/// # Examples
+///
+/// ```
+/// use fbxcel::low::v7400::FloatTolerance;
+/// use fbxcel::tree::v7400::TreeApproxEqOptions;
+/// use fbxcel::tree_v7400;
+///
+/// let left = tree_v7400! {
+///     FBXHeaderExtension: { CreationTime: ["2020-01-01"] {} },
+///     Objects: { Model: [1i64, "Cube", 0.1f64] {} },
+/// };
+/// let right = tree_v7400! {
+///     FBXHeaderExtension: { CreationTime: ["2022-12-31"] {} },
+///     Objects: { Model: [1i32, "Cube", 0.1f32] {} },
+/// };
+///
+/// let mut options = TreeApproxEqOptions::new();
+/// options.attributes.float_tolerance = FloatTolerance::Absolute(1e-6);
+/// options.attributes.ignore_float_width = true;
+/// options.attributes.ignore_integer_width = true;
+/// assert!(!left.approx_eq(&right, &options));
+///
+/// options.ignored_subtrees.insert("FBXHeaderExtension".to_owned());
+/// assert!(left.approx_eq(&right, &options));
+/// ```
+#[derive(Default, Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct TreeApproxEqOptions {
+    /// Options to compare attribute values.
+    pub attributes: ApproxEqOptions,
+    /// Names of nodes whose attributes are compared regardless of the order.
+    ///
+    /// Attributes of such nodes are equal if they can be paired one-to-one so
+    /// that each pair is approximately equal. The result does not depend on
+    /// the order of the attributes nor on which tree is on the left.
+    pub unordered_attributes: HashSet<String>,
+    /// Names of nodes to be ignored with their descendants.
+    ///
+    /// Such nodes are skipped on both sides, at any depth.
+    pub ignored_subtrees: HashSet<String>,
+}
+
+impl TreeApproxEqOptions {
+    /// Creates a new options with the default values.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compares the nodes and their descendants approximately.
+    pub(crate) fn nodes_eq(&self, left: NodeHandle<'_>, right: NodeHandle<'_>) -> bool {
+        if left.name() != right.name() {
+            return false;
+        }
+        let (left_attrs, right_attrs) = (left.attribute_values(), right.attribute_values());
+        let attrs_eq = if left_attrs.len() != right_attrs.len() {
+            false
+        } else if self.unordered_attributes.contains(left.name()) {
+            self.unordered_attrs_eq(
+                &left_attrs.collect::<Vec<_>>(),
+                &right_attrs.collect::<Vec<_>>(),
+            )
+        } else {
+            left_attrs
+                .zip(right_attrs)
+                .all(|(l, r)| l.approx_eq(&r, &self.attributes))
+        };
+        if !attrs_eq {
+            return false;
+        }
+
+        let mut left = left.children().filter(|child| !self.is_ignored(child));
+        let mut right = right.children().filter(|child| !self.is_ignored(child));
+        loop {
+            match (left.next(), right.next()) {
+                (Some(l), Some(r)) => {
+                    if !self.nodes_eq(l, r) {
+                        return false;
+                    }
+                }
+                (None, None) => return true,
+                _ => return false,
+            }
+        }
+    }
+
+    /// Returns whether the node is ignored.
+    fn is_ignored(&self, node: &NodeHandle<'_>) -> bool {
+        self.ignored_subtrees.contains(node.name())
+    }
+
+    /// Compares attributes regardless of the order.
+    fn unordered_attrs_eq(
+        &self,
+        left: &[Cow<'_, AttributeValue>],
+        right: &[Cow<'_, AttributeValue>],
+    ) -> bool {
+        if left.len() != right.len() {
+            return false;
+        }
+        // Find a perfect matching by augmenting paths.
+        // Greedy pairing is not enough, since the tolerance is not transitive.
+        // Attributes are few, so the quadratic number of comparisons is fine.
+        let mut pairs = vec![None; right.len()];
+        (0..left.len()).all(|l| {
+            let mut visited = vec![false; right.len()];
+            self.augment(left, right, l, &mut pairs, &mut visited)
+        })
+    }
+
+    /// Tries to pair the left attribute with a right attribute, re-pairing
+    /// already paired attributes if necessary.
+    ///
+    /// `pairs` maps right attribute indices to paired left attribute indices.
+    fn augment(
+        &self,
+        left: &[Cow<'_, AttributeValue>],
+        right: &[Cow<'_, AttributeValue>],
+        l: usize,
+        pairs: &mut [Option<usize>],
+        visited: &mut [bool],
+    ) -> bool {
+        for r in 0..right.len() {
+            if visited[r] || !left[l].approx_eq(&right[r], &self.attributes) {
+                continue;
+            }
+            visited[r] = true;
+            let repaired = match pairs[r] {
+                Some(other) => self.augment(left, right, other, pairs, visited),
+                None => true,
+            };
+            if repaired {
+                pairs[r] = Some(l);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{low::v7400::FloatTolerance, tree_v7400};
+
+    #[test]
+    fn default_is_strict() {
+        let options = TreeApproxEqOptions::new();
+        let left = tree_v7400! { A: [1i32, 0.5f64] { B: {} } };
+        let same = tree_v7400! { A: [1i32, 0.5f64] { B: {} } };
+        let wider = tree_v7400! { A: [1i64, 0.5f64] { B: {} } };
+        let fewer = tree_v7400! { A: [1i32, 0.5f64] {} };
+        assert!(left.approx_eq(&same, &options));
+        assert!(!left.approx_eq(&wider, &options));
+        assert!(!left.approx_eq(&fewer, &options));
+    }
+
+    #[test]
+    fn unordered_attributes() {
+        let left = tree_v7400! { A: { B: [1i32, "x", 2.0f64] {} } };
+        let right = tree_v7400! { A: { B: [2.0f64, 1i32, "x"] {} } };
+        let dup = tree_v7400! { A: { B: [1i32, 1i32, "x"] {} } };
+        let mut options = TreeApproxEqOptions::new();
+        assert!(!left.approx_eq(&right, &options));
+        options.unordered_attributes.insert("B".to_owned());
+        assert!(left.approx_eq(&right, &options));
+        assert!(!left.approx_eq(&dup, &options));
+        // Only the listed nodes are affected.
+        options.unordered_attributes.clear();
+        options.unordered_attributes.insert("A".to_owned());
+        assert!(!left.approx_eq(&right, &options));
+    }
+
+    #[test]
+    fn unordered_attributes_symmetric() {
+        let left = tree_v7400! { A: [1.05f64, 1.0f64] {} };
+        let right = tree_v7400! { A: [1.0f64, 1.1f64] {} };
+        let mut options = TreeApproxEqOptions::new();
+        options.unordered_attributes.insert("A".to_owned());
+        options.attributes.float_tolerance = FloatTolerance::Absolute(0.06);
+        // `1.05` is close to both `1.0` and `1.1`, but `1.0` is not close to
+        // `1.1`.
+        assert!(left.approx_eq(&right, &options));
+        assert!(right.approx_eq(&left, &options));
+
+        options.attributes.float_tolerance = FloatTolerance::Absolute(0.04);
+        assert!(!left.approx_eq(&right, &options));
+        assert!(!right.approx_eq(&left, &options));
+    }
+
+    #[test]
+    fn ignored_subtrees() {
+        let left = tree_v7400! {
+            A: { Ignored: [1i32] {}, B: [0.25f32] {} },
+            Ignored: {},
+        };
+        let right = tree_v7400! {
+            A: { B: [0.2500001f32] {}, Ignored: { C: {} } },
+        };
+        let mut options = TreeApproxEqOptions::new();
+        options.ignored_subtrees.insert("Ignored".to_owned());
+        assert!(!left.approx_eq(&right, &options));
+        options.attributes.float_tolerance = FloatTolerance::Ulps(4);
+        assert!(left.approx_eq(&right, &options));
+    }
+}
@@ -0,0 +1,277 @@
+//! Bulk mutation of trees.
+
+use crate::{
+    low::v7400::AttributeValue,
+    tree::v7400::{Change, NodeHandle, NodeHandleMut, NodeId, Tree},
+};
+
+impl Tree {
+    /// Retains only the nodes specified by the predicate.
+    ///
+    /// Nodes are visited in preorder (excluding the root node).
+    /// If the predicate returns `false` for a node, the node and its
+    /// descendants are removed (as [`remove_subtree`][`Self::remove_subtree`]
+    /// does), and the descendants are not visited.
+    ///
+    /// Returns the number of removed subtrees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fbxcel::tree_v7400;
+    ///
+    /// let mut tree = tree_v7400! {
+    ///     Takes: { Current: [""] {} },
+    ///     Objects: {
+    ///         Model: [1i64] {
+    ///             Properties70: {
+    ///                 P: ["Lcl Translation"] {},
+    ///                 P: ["UDP3DSMAX"] {},
+    ///             },
+    ///         },
+    ///     },
+    /// };
+    ///
+    /// let removed = tree.retain(|node| match node.name() {
+    ///     "Takes" => false,
+    ///     "P" => node.attributes()[0].get_string() != Some("UDP3DSMAX"),
+    ///     _ => true,
+    /// });
+    /// assert_eq!(removed, 2);
+    ///
+    /// let expected = tree_v7400! {
+    ///     Objects: {
+    ///         Model: [1i64] {
+    ///             Properties70: {
+    ///                 P: ["Lcl Translation"] {},
+    ///             },
+    ///         },
+    ///     },
+    /// };
+    /// assert!(tree.strict_eq(&expected));
+    /// ```
+    pub fn retain(&mut self, mut f: impl FnMut(NodeHandle<'_>) -> bool) -> usize {
+        let mut removed = Vec::new();
+        let mut stack = self.root().children().collect::<Vec<_>>();
+        stack.reverse();
+        while let Some(node) = stack.pop() {
+            if f(node) {
+                let len = stack.len();
+                stack.extend(node.children());
+                stack[len..].reverse();
+            } else {
+                removed.push(node.node_id());
+            }
+        }
+
+        let count = removed.len();
+        self.with_child_name_index_deferred(|tree| {
+            tree.begin_transaction();
+            for id in removed {
+                tree.remove_subtree(id);
+            }
+            tree.commit_transaction();
+        });
+        count
+    }
+
+    /// Calls the given function for each node with its mutable handle.
+    ///
+    /// Nodes are visited in preorder (excluding the root node).
+    /// The next node to visit is determined after the function returns, so
+    /// the function can rename the node, edit its attributes, and append
+    /// children to it. Appended children are also visited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fbxcel::tree_v7400;
+    ///
+    /// let mut tree = tree_v7400! {
+    ///     Objects: {
+    ///         Model: [1i64] {},
+    ///         Model: [2i64] {},
+    ///     },
+    /// };
+    ///
+    /// tree.for_each_mut(|mut node| {
+    ///     if node.name() == "Model" {
+    ///         node.append_new("Shading").append_attribute(true);
+    ///     }
+    /// });
+    ///
+    /// let expected = tree_v7400! {
+    ///     Objects: {
+    ///         Model: [1i64] { Shading: [true] {} },
+    ///         Model: [2i64] { Shading: [true] {} },
+    ///     },
+    /// };
+    /// assert!(tree.strict_eq(&expected));
+    /// ```
+    pub fn for_each_mut(&mut self, mut f: impl FnMut(NodeHandleMut<'_>)) {
+        let root = self.root_id;
+        let mut next = self.root().first_child().map(|node| node.node_id());
+        self.begin_transaction();
+        while let Some(current) = next {
+            f(NodeHandleMut::new(self, current));
+            next = self.next_in_preorder(current, root);
+        }
+        self.commit_transaction();
+    }
+
+    /// Returns the next node in preorder in the subtree.
+    fn next_in_preorder(&self, current: NodeId, subtree_root: NodeId) -> Option<NodeId> {
+        let node = current.to_handle(self);
+        if let Some(child) = node.first_child() {
+            return Some(child.node_id());
+        }
+        std::iter::once(node)
+            .chain(node.ancestors())
+            .take_while(|node| node.node_id() != subtree_root)
+            .find_map(|node| node.next_sibling())
+            .map(|node| node.node_id())
+    }
+
+    /// Calls the given function for each attribute of all nodes, and replaces
+    /// the attributes with the values it returns.
+    ///
+    /// The function is called with the node name, the attribute index, and
+    /// the attribute value, for nodes in preorder. It should return `None` to
+    /// keep the value.
+    ///
+    /// Only the attributes of the nodes with replaced values are modified,
+    /// so the other nodes are not affected in the compact storage mode (see
+    /// [`set_compact_storage`][`Self::set_compact_storage`]), and the values
+    /// are not cloned even if the change journal is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fbxcel::low::v7400::AttributeValue;
+    /// use fbxcel::tree_v7400;
+    ///
+    /// let mut tree = tree_v7400! {
+    ///     Vertices: [vec![0.5f64, 1.0]] {},
+    ///     Scale: [2.0f64] {},
+    /// };
+    ///
+    /// // Convert `f64` values to `f32`.
+    /// tree.map_attributes(|_name, _index, value| match value {
+    ///     AttributeValue::F64(v) => Some(AttributeValue::F32(*v as f32)),
+    ///     AttributeValue::ArrF64(v) => Some(AttributeValue::ArrF32(
+    ///         v.iter().map(|&v| v as f32).collect(),
+    ///     )),
+    ///     _ => None,
+    /// });
+    ///
+    /// let expected = tree_v7400! {
+    ///     Vertices: [vec![0.5f32, 1.0]] {},
+    ///     Scale: [2.0f32] {},
+    /// };
+    /// assert!(tree.strict_eq(&expected));
+    /// ```
+    pub fn map_attributes(
+        &mut self,
+        mut f: impl FnMut(&str, usize, &AttributeValue) -> Option<AttributeValue>,
+    ) {
+        let mut replaced = Vec::new();
+        for node in self.root().descendants() {
+            let name = node.name();
+            for (index, value) in node.attribute_values().enumerate() {
+                if let Some(new) = f(name, index, &value) {
+                    replaced.push((node.node_id(), index, new));
+                }
+            }
+        }
+
+        self.begin_transaction();
+        for (node, index, mut value) in replaced {
+            std::mem::swap(&mut self.node_attributes_mut(node)[index], &mut value);
+            self.record_with(|_| Change::SetAttribute { node, index, value });
+        }
+        self.commit_transaction();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tree_v7400;
+
+    #[test]
+    fn retain_and_walk() {
+        let mut tree = tree_v7400! {
+            A: [1i32] {
+                B: [2i32] { A: [3i32] {} },
+                C: [4i32] {},
+            },
+            B: [5i32] {},
+        };
+        let mut visited = Vec::new();
+        let removed = tree.retain(|node| {
+            visited.push(node.attributes()[0].get_i32().unwrap());
+            node.name() != "B"
+        });
+        assert_eq!(removed, 2);
+        assert_eq!(visited, [1, 2, 4, 5]);
+        assert!(tree.strict_eq(&tree_v7400! { A: [1i32] { C: [4i32] {} } }));
+
+        let mut visited = Vec::new();
+        tree.for_each_mut(|mut node| {
+            visited.push(node.name().to_owned());
+            if node.name() == "A" {
+                node.append_new("D");
+                node.rename("E");
+            }
+            node.append_attribute(0i64);
+        });
+        assert_eq!(visited, ["A", "C", "D"]);
+        let expected = tree_v7400! {
+            E: [1i32, 0i64] {
+                C: [4i32, 0i64] {},
+                D: [0i64] {},
+            },
+        };
+        assert!(tree.strict_eq(&expected));
+
+        let mut tree = Tree::default();
+        tree.for_each_mut(|_| panic!("Root node should not be visited"));
+        assert_eq!(tree.retain(|_| false), 0);
+    }
+
+    #[test]
+    fn map_only_replaced_attributes() {
+        let original = tree_v7400! {
+            P: ["Lcl Scaling", vec![1.0f64; 3]] {},
+            P: ["Lcl Translation", 2.0f64] {},
+        };
+        let mut tree = original.clone();
+        tree.set_compact_storage(true);
+        tree.set_journal_enabled(true);
+        let usage = tree.memory_usage();
+
+        tree.map_attributes(|_, _, _| None);
+        assert_eq!(tree.memory_usage(), usage);
+        assert!(!tree.can_undo());
+
+        tree.map_attributes(|_, _, value| match value {
+            AttributeValue::F64(v) => Some(AttributeValue::F32(*v as f32)),
+            _ => None,
+        });
+        let expected = tree_v7400! {
+            P: ["Lcl Scaling", vec![1.0f64; 3]] {},
+            P: ["Lcl Translation", 2.0f32] {},
+        };
+        assert!(tree.strict_eq(&expected));
+        // Only the node with the replaced value has its string values put back.
+        let interned = tree
+            .root()
+            .children()
+            .map(|node| node.node().get().attributes_storage().1)
+            .collect::<Vec<_>>();
+        assert_eq!(interned, [1, 0]);
+        assert!(tree.undo());
+        assert!(tree.strict_eq(&original));
+    }
+}
@@ -0,0 +1,372 @@
+//! Tree canonicalization.
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    low::v7400::AttributeValue,
+    tree::v7400::{Change, NodeId, Tree},
+};
+
+/// Options for tree canonicalization.
+///
+/// The default value does nothing.
+///
+/// # Examples
+///
+/// ```
+/// use fbxcel::tree::v7400::CanonicalizeOptions;
+/// use fbxcel::tree_v7400;
+///
+/// let mut tree = tree_v7400! {
+///     FBXHeaderExtension: {
+///         CreationTimeStamp: { Year: [2022i32] {} },
+///     },
+///     Connections: {
+///         C: ["OO", 3i64, 1i64] {},
+///         C: ["OO", 2i64, 1i64] {},
+///     },
+/// };
+///
+/// let mut options = CanonicalizeOptions::new();
+/// options.sort_children("Connections", 3);
+/// options.removed_subtrees.insert("CreationTimeStamp".to_owned());
+/// tree.canonicalize(&options);
+///
+/// let expected = tree_v7400! {
+///     FBXHeaderExtension: {},
+///     Connections: {
+///         C: ["OO", 2i64, 1i64] {},
+///         C: ["OO", 3i64, 1i64] {},
+///     },
+/// };
+/// assert!(tree.strict_eq(&expected));
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CanonicalizeOptions {
+    /// Parent node names whose children are sorted, and the number of leading
+    /// attributes used as the sort key.
+    ///
+    /// Children are sorted by the node name, and then by the key attributes.
+    /// The sort is stable, so children with the same key keep their order.
+    ///
+    /// Attribute values are ordered first by their types, and then by their
+    /// values (floating point numbers are totally ordered by
+    /// the IEEE 754 `totalOrder` predicate).
+    pub sorted_children: HashMap<String, usize>,
+    /// Names of nodes to be removed with their descendants.
+    ///
+    /// This is useful to remove nodes with volatile contents such as
+    /// timestamps.
+    pub removed_subtrees: HashSet<String>,
+    /// Whether to replace negative zeros in floating point attributes
+    /// (including arrays) with positive zeros.
+    pub normalize_negative_zero: bool,
+}
+
+impl CanonicalizeOptions {
+    /// Creates a new options with the default values.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sorts children of nodes with the given name, using `key_len` leading
+    /// attributes as the sort key.
+    ///
+    /// For example, `sort_children("Properties70", 1)` sorts properties by
+    /// their names, and `sort_children("Connections", 3)` sorts connections by
+    /// their types, sources, and destinations.
+    #[inline]
+    pub fn sort_children(&mut self, parent_name: &str, key_len: usize) -> &mut Self {
+        self.sorted_children.insert(parent_name.to_owned(), key_len);
+        self
+    }
+}
+
+impl Tree {
+    /// Canonicalizes the tree.
+    ///
+    /// Subtrees are removed first, then attribute values are normalized, and
+    /// then children are sorted.
+    ///
+    /// See [`CanonicalizeOptions`] for details.
+    pub fn canonicalize(&mut self, options: &CanonicalizeOptions) {
+        self.with_child_name_index_deferred(|tree| tree.canonicalize_impl(options));
+    }
+
+    /// Internal implementation of `canonicalize`.
+    fn canonicalize_impl(&mut self, options: &CanonicalizeOptions) {
+        self.begin_transaction();
+        if !options.removed_subtrees.is_empty() {
+            let removed = self
+                .descendant_ids()
+                .filter(|&id| options.removed_subtrees.contains(id.to_handle(self).name()))
+                .collect::<Vec<_>>();
+            for id in removed {
+                // Descendants of removed nodes are already removed (or marked
+                // as removed, if the journal is enabled).
+                if self.contains_node(id) {
+                    self.remove_subtree(id);
+                }
+            }
+        }
+
+        if options.normalize_negative_zero {
+            self.map_attributes(|_, _, value| normalize_negative_zero(value));
+        }
+
+        if !options.sorted_children.is_empty() {
+            let parents = self
+                .descendant_ids()
+                .filter_map(|id| {
+                    let key_len = options.sorted_children.get(id.to_handle(self).name())?;
+                    Some((id, *key_len))
+                })
+                .collect::<Vec<_>>();
+            for (parent, key_len) in parents {
+                self.sort_children(parent, key_len);
+            }
+        }
+        self.commit_transaction();
+    }
+
+    /// Returns an iterator of the IDs of the descendants of the root node.
+    fn descendant_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.root_id
+            .raw()
+            .descendants(&self.arena)
+            .skip(1)
+            .map(move |id| NodeId::new(id, self.tree_id()))
+    }
+
+    /// Sorts the children of the given node by the name and the leading
+    /// attributes.
+    ///
+    /// Does nothing if the children are already sorted.
+    fn sort_children(&mut self, parent: NodeId, key_len: usize) {
+        let mut keyed = parent
+            .to_handle(self)
+            .children()
+            .map(|child| {
+                let attrs = child.attribute_values().take(key_len).collect::<Vec<_>>();
+                (child.name(), attrs, child.node_id())
+            })
+            .collect::<Vec<_>>();
+        let children = keyed.iter().map(|(_, _, id)| *id).collect::<Vec<_>>();
+        keyed.sort_by(|(l_name, l_attrs, _), (r_name, r_attrs, _)| {
+            l_name
+                .cmp(r_name)
+                .then_with(|| cmp_slices(l_attrs, r_attrs, |l, r| cmp_attributes(l, r)))
+        });
+        if keyed.iter().map(|(_, _, id)| id).eq(&children) {
+            return;
+        }
+        let sorted = keyed
+            .into_iter()
+            .map(|(_, _, child)| child)
+            .collect::<Vec<_>>();
+        let old = self.reorder_children(parent, &sorted);
+        self.record_with(|_| Change::Reorder {
+            parent,
+            children: old,
+        });
+    }
+}
+
+/// Returns the value with negative zeros replaced by positive zeros.
+///
+/// Returns `None` if the value has no negative zeros.
+fn normalize_negative_zero(value: &AttributeValue) -> Option<AttributeValue> {
+    /// Returns `true` if the value is a negative zero.
+    fn is_neg_zero_f32(v: f32) -> bool {
+        v == 0.0 && v.is_sign_negative()
+    }
+    /// Returns `true` if the value is a negative zero.
+    fn is_neg_zero_f64(v: f64) -> bool {
+        v == 0.0 && v.is_sign_negative()
+    }
+
+    match value {
+        AttributeValue::F32(v) if is_neg_zero_f32(*v) => Some(AttributeValue::F32(0.0)),
+        AttributeValue::F64(v) if is_neg_zero_f64(*v) => Some(AttributeValue::F64(0.0)),
+        AttributeValue::ArrF32(arr) if arr.iter().any(|&v| is_neg_zero_f32(v)) => {
+            Some(AttributeValue::ArrF32(
+                arr.iter()
+                    .map(|&v| if is_neg_zero_f32(v) { 0.0 } else { v })
+                    .collect(),
+            ))
+        }
+        AttributeValue::ArrF64(arr) if arr.iter().any(|&v| is_neg_zero_f64(v)) => {
+            Some(AttributeValue::ArrF64(
+                arr.iter()
+                    .map(|&v| if is_neg_zero_f64(v) { 0.0 } else { v })
+                    .collect(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Compares attribute values in a total order.
+fn cmp_attributes(l: &AttributeValue, r: &AttributeValue) -> Ordering {
+    use AttributeValue::*;
+
+    match (l, r) {
+        (Bool(l), Bool(r)) => l.cmp(r),
+        (I16(l), I16(r)) => l.cmp(r),
+        (I32(l), I32(r)) => l.cmp(r),
+        (I64(l), I64(r)) => l.cmp(r),
+        (F32(l), F32(r)) => cmp_f32(l, r),
+        (F64(l), F64(r)) => cmp_f64(l, r),
+        (ArrBool(l), ArrBool(r)) => l.cmp(r),
+        (ArrI32(l), ArrI32(r)) => l.cmp(r),
+        (ArrI64(l), ArrI64(r)) => l.cmp(r),
+        (ArrF32(l), ArrF32(r)) => cmp_slices(l, r, cmp_f32),
+        (ArrF64(l), ArrF64(r)) => cmp_slices(l, r, cmp_f64),
+        (Binary(l), Binary(r)) => l.cmp(r),
+        (String(l), String(r)) => l.cmp(r),
+        _ => (l.type_() as u8).cmp(&(r.type_() as u8)),
+    }
+}
+
+/// Compares `f32` values by the IEEE 754 `totalOrder` predicate.
+fn cmp_f32(l: &f32, r: &f32) -> Ordering {
+    /// Maps the bits to the integer with the same order as the float value.
+    fn ordered(v: f32) -> i32 {
+        let bits = v.to_bits() as i32;
+        bits ^ (((bits >> 31) as u32) >> 1) as i32
+    }
+    ordered(*l).cmp(&ordered(*r))
+}
+
+/// Compares `f64` values by the IEEE 754 `totalOrder` predicate.
+fn cmp_f64(l: &f64, r: &f64) -> Ordering {
+    /// Maps the bits to the integer with the same order as the float value.
+    fn ordered(v: f64) -> i64 {
+        let bits = v.to_bits() as i64;
+        bits ^ (((bits >> 63) as u64) >> 1) as i64
+    }
+    ordered(*l).cmp(&ordered(*r))
+}
+
+/// Compares slices lexicographically.
+fn cmp_slices<T>(l: &[T], r: &[T], cmp: impl Fn(&T, &T) -> Ordering) -> Ordering {
+    l.iter()
+        .zip(r)
+        .map(|(l, r)| cmp(l, r))
+        .find(|ord| *ord != Ordering::Equal)
+        .unwrap_or_else(|| l.len().cmp(&r.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tree_v7400;
+
+    #[test]
+    fn sort_children() {
+        let mut tree = tree_v7400! {
+            Properties70: {
+                P: ["b", 2i32] {},
+                P: ["a", 1i32] {},
+                P: ["b", 1i32] {},
+                Extra: {},
+                P: [1i32] {},
+            },
+            Other: {
+                B: {},
+                A: {},
+            },
+        };
+        let mut options = CanonicalizeOptions::new();
+        options.sort_children("Properties70", 1);
+        tree.canonicalize(&options);
+
+        // Integers are ordered before strings; equal keys keep the order.
+        let expected = tree_v7400! {
+            Properties70: {
+                Extra: {},
+                P: [1i32] {},
+                P: ["a", 1i32] {},
+                P: ["b", 2i32] {},
+                P: ["b", 1i32] {},
+            },
+            Other: {
+                B: {},
+                A: {},
+            },
+        };
+        assert!(tree.strict_eq(&expected));
+
+        // Sorting is recorded only if the order changes.
+        let original = tree.clone();
+        tree.set_journal_enabled(true);
+        tree.canonicalize(&options);
+        assert!(!tree.can_undo());
+        let properties = tree.root().first_child().unwrap().node_id();
+        let extra = properties.to_handle(&tree).first_child().unwrap().node_id();
+        tree.append(extra, properties);
+        tree.canonicalize(&options);
+        assert!(tree.strict_eq(&original));
+        assert!(tree.undo());
+        assert_eq!(
+            properties.to_handle(&tree).last_child().unwrap().node_id(),
+            extra
+        );
+    }
+
+    #[test]
+    fn normalize_values() {
+        let mut tree = tree_v7400! {
+            A: [-0.0f32, -0.0f64, vec![-0.0f64, -1.0], -1i32] {
+                Time: ["2022-01-01"] {},
+            },
+        };
+        let mut options = CanonicalizeOptions::new();
+        options.normalize_negative_zero = true;
+        options.removed_subtrees.insert("Time".to_owned());
+        tree.canonicalize(&options);
+
+        let expected = tree_v7400! {
+            A: [0.0f32, 0.0f64, vec![0.0f64, -1.0], -1i32] {},
+        };
+        assert!(tree.strict_eq(&expected));
+    }
+
+    #[test]
+    fn remove_nested_subtrees_with_journal() {
+        let original = tree_v7400! {
+            A: {
+                Time: {
+                    Time: {},
+                },
+                B: {},
+            },
+        };
+        let mut tree = original.clone();
+        tree.set_journal_enabled(true);
+        let mut options = CanonicalizeOptions::new();
+        options.removed_subtrees.insert("Time".to_owned());
+        tree.canonicalize(&options);
+
+        assert!(tree.strict_eq(&tree_v7400! { A: { B: {} } }));
+        assert!(tree.undo());
+        assert!(tree.strict_eq(&original));
+    }
+
+    #[test]
+    fn float_order() {
+        let values = [f64::NEG_INFINITY, -1.0, -0.0, 0.0, 1.0, f64::INFINITY];
+        for (i, l) in values.iter().enumerate() {
+            for (j, r) in values.iter().enumerate() {
+                assert_eq!(cmp_f64(l, r), i.cmp(&j));
+            }
+        }
+    }
+}
@@ -0,0 +1,376 @@
+//! Tree edits with node ID checks.
+
+use crate::{
+    low::v7400::AttributeValue,
+    tree::v7400::{EditError, NodeId, Tree},
+};
+
+impl Tree {
+    /// Checks whether the node ID belongs to the tree and the node exists.
+    pub(crate) fn check_node(&self, node_id: NodeId) -> Result<(), EditError> {
+        if node_id.tree_id() != self.tree_id() {
+            return Err(EditError::ForeignNode(node_id));
+        }
+        if self.arena.get(node_id.raw()).is_none() || self.removed_nodes.contains(&node_id.raw()) {
+            return Err(EditError::RemovedNode(node_id));
+        }
+        Ok(())
+    }
+
+    /// Checks whether the node ID belongs to the tree and the node exists.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the check fails.
+    pub(crate) fn assert_node(&self, node_id: NodeId) {
+        if let Err(e) = self.check_node(node_id) {
+            panic!("Invalid node ID: {}", e);
+        }
+    }
+
+    /// Checks whether the node ID is valid and is not the root node.
+    fn check_non_root(&self, node_id: NodeId) -> Result<(), EditError> {
+        self.check_node(node_id)?;
+        if node_id == self.root_id {
+            return Err(EditError::RootNode);
+        }
+        Ok(())
+    }
+
+    /// Checks whether the node can be moved to the position relative to the
+    /// base node.
+    fn check_move(&self, node: NodeId, base: NodeId) -> Result<(), EditError> {
+        self.check_non_root(node)?;
+        self.check_node(base)?;
+        if base.raw().ancestors(&self.arena).any(|id| id == node.raw()) {
+            return Err(EditError::CyclicMove(node));
+        }
+        Ok(())
+    }
+
+    /// Detaches the node and appends it to the given parent node.
+    ///
+    /// This is a non-panicking version of [`append`][`Self::append`].
+    pub fn try_append(&mut self, new_last_child: NodeId, parent: NodeId) -> Result<(), EditError> {
+        self.check_move(new_last_child, parent)?;
+        self.append(new_last_child, parent);
+        Ok(())
+    }
+
+    /// Detaches the node and prepends it to the given parent node.
+    ///
+    /// This is a non-panicking version of [`prepend`][`Self::prepend`].
+    pub fn try_prepend(
+        &mut self,
+        new_first_child: NodeId,
+        parent: NodeId,
+    ) -> Result<(), EditError> {
+        self.check_move(new_first_child, parent)?;
+        self.prepend(new_first_child, parent);
+        Ok(())
+    }
+
+    /// Detaches the node and inserts it after the given base node.
+    ///
+    /// This is a non-panicking version of [`insert_after`][`Self::insert_after`].
+    pub fn try_insert_after(
+        &mut self,
+        new_next_sibling: NodeId,
+        prev_sibling: NodeId,
+    ) -> Result<(), EditError> {
+        self.check_non_root(prev_sibling)?;
+        self.check_move(new_next_sibling, prev_sibling)?;
+        self.insert_after(new_next_sibling, prev_sibling);
+        Ok(())
+    }
+
+    /// Detaches the node and inserts it before the given base node.
+    ///
+    /// This is a non-panicking version of
+    /// [`insert_before`][`Self::insert_before`].
+    pub fn try_insert_before(
+        &mut self,
+        new_prev_sibling: NodeId,
+        next_sibling: NodeId,
+    ) -> Result<(), EditError> {
+        self.check_non_root(next_sibling)?;
+        self.check_move(new_prev_sibling, next_sibling)?;
+        self.insert_before(new_prev_sibling, next_sibling);
+        Ok(())
+    }
+
+    /// Creates a new node and appends to the given parent node.
+    ///
+    /// This is a non-panicking version of [`append_new`][`Self::append_new`].
+    pub fn try_append_new(&mut self, parent: NodeId, name: &str) -> Result<NodeId, EditError> {
+        self.check_node(parent)?;
+        Ok(self.append_new(parent, name))
+    }
+
+    /// Creates a new node and prepends to the given parent node.
+    ///
+    /// This is a non-panicking version of [`prepend_new`][`Self::prepend_new`].
+    pub fn try_prepend_new(&mut self, parent: NodeId, name: &str) -> Result<NodeId, EditError> {
+        self.check_node(parent)?;
+        Ok(self.prepend_new(parent, name))
+    }
+
+    /// Creates a new node and inserts after the given sibling node.
+    ///
+    /// This is a non-panicking version of
+    /// [`insert_new_after`][`Self::insert_new_after`].
+    pub fn try_insert_new_after(
+        &mut self,
+        sibling: NodeId,
+        name: &str,
+    ) -> Result<NodeId, EditError> {
+        self.check_non_root(sibling)?;
+        Ok(self.insert_new_after(sibling, name))
+    }
+
+    /// Creates a new node and inserts before the given sibling node.
+    ///
+    /// This is a non-panicking version of
+    /// [`insert_new_before`][`Self::insert_new_before`].
+    pub fn try_insert_new_before(
+        &mut self,
+        sibling: NodeId,
+        name: &str,
+    ) -> Result<NodeId, EditError> {
+        self.check_non_root(sibling)?;
+        Ok(self.insert_new_before(sibling, name))
+    }
+
+    /// Detaches the subtree from the tree.
+    ///
+    /// This is a non-panicking version of [`detach`][`Self::detach`].
+    pub fn try_detach(&mut self, subtree_root: NodeId) -> Result<(), EditError> {
+        self.check_node(subtree_root)?;
+        self.detach(subtree_root);
+        Ok(())
+    }
+
+    /// Deep-copies the subtree in the tree, and appends it to the given parent
+    /// node.
+    ///
+    /// This is a non-panicking version of
+    /// [`clone_subtree`][`Self::clone_subtree`].
+    pub fn try_clone_subtree(
+        &mut self,
+        parent: NodeId,
+        source: NodeId,
+    ) -> Result<NodeId, EditError> {
+        self.check_node(parent)?;
+        self.check_non_root(source)?;
+        Ok(self.clone_subtree(parent, source))
+    }
+
+    /// Removes the subtree from the tree.
+    ///
+    /// This is a non-panicking version of
+    /// [`remove_subtree`][`Self::remove_subtree`].
+    pub fn try_remove_subtree(&mut self, subtree_root: NodeId) -> Result<(), EditError> {
+        self.check_non_root(subtree_root)?;
+        self.remove_subtree(subtree_root);
+        Ok(())
+    }
+
+    /// Appends the attribute to the node.
+    ///
+    /// This is a non-panicking version of
+    /// [`append_attribute`][`Self::append_attribute`].
+    pub fn try_append_attribute(
+        &mut self,
+        node_id: NodeId,
+        v: impl Into<AttributeValue>,
+    ) -> Result<(), EditError> {
+        self.check_non_root(node_id)?;
+        self.append_attribute(node_id, v);
+        Ok(())
+    }
+
+    /// Returns a mutable reference to the node attribute at the given index.
+    ///
+    /// This is a non-panicking version of
+    /// [`get_attribute_mut`][`Self::get_attribute_mut`].
+    pub fn try_get_attribute_mut(
+        &mut self,
+        node_id: NodeId,
+        i: usize,
+    ) -> Result<Option<&mut AttributeValue>, EditError> {
+        self.check_node(node_id)?;
+        Ok(self.get_attribute_mut(node_id, i))
+    }
+
+    /// Takes all attributes as a `Vec`.
+    ///
+    /// This is a non-panicking version of
+    /// [`take_attributes_vec`][`Self::take_attributes_vec`].
+    pub fn try_take_attributes_vec(
+        &mut self,
+        node_id: NodeId,
+    ) -> Result<Vec<AttributeValue>, EditError> {
+        self.check_node(node_id)?;
+        Ok(self.take_attributes_vec(node_id))
+    }
+
+    /// Sets the given `Vec` of attribute values as the node attributes.
+    ///
+    /// This is a non-panicking version of
+    /// [`set_attributes_vec`][`Self::set_attributes_vec`].
+    pub fn try_set_attributes_vec(
+        &mut self,
+        node_id: NodeId,
+        new: Vec<AttributeValue>,
+    ) -> Result<(), EditError> {
+        self.check_non_root(node_id)?;
+        self.set_attributes_vec(node_id, new);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        low::v7400::AttributeValue,
+        tree::v7400::{EditError, Tree},
+        tree_v7400,
+    };
+
+    #[test]
+    fn foreign_and_removed_nodes() {
+        let mut tree = tree_v7400! {
+            Node0: [1i32] {},
+            Node1: {},
+        };
+        let other = tree_v7400! {
+            Node0: [1i32] {},
+        };
+        let node0 = tree.root().first_child().unwrap().node_id();
+        let foreign = other.root().first_child().unwrap().node_id();
+
+        // Same structure, but different trees.
+        assert_ne!(node0, foreign);
+        assert!(tree.get_node_mut(foreign).is_none());
+        assert_eq!(
+            tree.try_get_attribute_mut(foreign, 0),
+            Err(EditError::ForeignNode(foreign))
+        );
+        assert_eq!(
+            tree.try_append(foreign, tree.root().node_id()),
+            Err(EditError::ForeignNode(foreign))
+        );
+
+        // Clones share the identity.
+        let mut cloned = tree.clone();
+        assert_eq!(
+            cloned.try_get_attribute_mut(node0, 0),
+            Ok(Some(&mut AttributeValue::I32(1)))
+        );
+
+        tree.remove_subtree(node0);
+        assert_eq!(tree.try_detach(node0), Err(EditError::RemovedNode(node0)));
+        assert_eq!(
+            tree.try_append_new(node0, "Node0_0"),
+            Err(EditError::RemovedNode(node0))
+        );
+
+        // Compaction changes the identity.
+        let node1 = tree.root().first_child().unwrap().node_id();
+        let map = tree.compact();
+        assert_eq!(tree.check_node(node1), Err(EditError::ForeignNode(node1)));
+        assert_eq!(tree.check_node(map[&node1]), Ok(()));
+    }
+
+    #[test]
+    fn invalid_moves() {
+        let mut tree = tree_v7400! {
+            Node0: {
+                Node0_0: {},
+            },
+        };
+        let root = tree.root().node_id();
+        let node0 = tree.root().first_child().unwrap().node_id();
+        let node0_0 = node0.to_handle(&tree).first_child().unwrap().node_id();
+
+        assert_eq!(
+            tree.try_append(node0, node0),
+            Err(EditError::CyclicMove(node0))
+        );
+        assert_eq!(
+            tree.try_prepend(node0, node0_0),
+            Err(EditError::CyclicMove(node0))
+        );
+        assert_eq!(tree.try_append(root, node0), Err(EditError::RootNode));
+        assert_eq!(
+            tree.try_insert_after(node0_0, root),
+            Err(EditError::RootNode)
+        );
+        assert_eq!(
+            tree.try_insert_new_before(root, "Node"),
+            Err(EditError::RootNode)
+        );
+        assert_eq!(tree.try_remove_subtree(root), Err(EditError::RootNode));
+        assert_eq!(
+            tree.try_append_attribute(root, 1i32),
+            Err(EditError::RootNode)
+        );
+
+        tree.try_insert_after(node0_0, node0).unwrap();
+        tree.try_set_attributes_vec(node0_0, vec![AttributeValue::Bool(true)])
+            .unwrap();
+        let expected = tree_v7400! {
+            Node0: {},
+            Node0_0: [true] {},
+        };
+        assert!(tree.strict_eq(&expected));
+    }
+
+    #[test]
+    fn insert_before_and_after() {
+        let mut tree = tree_v7400! {
+            Node0: {},
+            Node1: {},
+            Node2: {},
+        };
+        let root = tree.root();
+        let node0 = root.first_child().unwrap().node_id();
+        let node2 = root.last_child().unwrap().node_id();
+        let original = tree.clone();
+        tree.set_journal_enabled(true);
+
+        tree.try_insert_before(node2, node0).unwrap();
+        let expected = tree_v7400! {
+            Node2: {},
+            Node0: {},
+            Node1: {},
+        };
+        assert!(tree.strict_eq(&expected));
+
+        tree.try_insert_after(node2, node0).unwrap();
+        let expected = tree_v7400! {
+            Node0: {},
+            Node2: {},
+            Node1: {},
+        };
+        assert!(tree.strict_eq(&expected));
+
+        assert!(tree.undo());
+        assert!(tree.undo());
+        assert!(tree.strict_eq(&original));
+
+        assert_eq!(
+            tree.try_insert_before(node0, tree.root().node_id()),
+            Err(EditError::RootNode)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn append_foreign_node() {
+        let mut tree = tree_v7400! { Node0: {} };
+        let other = Tree::default();
+        let root = tree.root().node_id();
+        tree.append(other.root().node_id(), root);
+    }
+}
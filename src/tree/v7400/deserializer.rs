@@ -0,0 +1,956 @@
+//! Serde deserializer of nodes.
+
+use serde::de::{
+    self,
+    value::{BorrowedStrDeserializer, SeqDeserializer},
+    DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, Unexpected,
+    VariantAccess, Visitor,
+};
+
+use crate::{
+    low::v7400::{attribute_variant_name, AttributeValue, ATTRIBUTE_VALUE_NAME},
+    tree::v7400::{DeserializeError, NodeHandle},
+};
+
+/// Field name for node attributes.
+const ATTRIBUTES_FIELD: &str = "$attributes";
+
+/// Implement `deserialize_*` methods by forwarding to the deserializer
+/// returned by the given method.
+macro_rules! forward_deserialize {
+    ($target:ident => $($method:ident($($arg:ident: $ty:ty),*);)*) => {$(
+        #[inline]
+        fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'a>,
+        {
+            self.$target()?.$method($($arg,)* visitor)
+        }
+    )*};
+}
+
+/// Serde deserializer of a node.
+///
+/// Nodes are mapped to the serde data model as below.
+///
+/// * Structs and maps are deserialized from the child nodes.
+///     + Field names are node names.
+///     + If the field type is a sequence (such as `Vec<T>`), all children with
+///       the field name are deserialized as elements. Otherwise, the first
+///       child with the field name is deserialized.
+///     + To deserialize the elements of an array attribute of a child, use a
+///       newtype struct (such as `struct Vertices(Vec<f64>)`) as the field
+///       type.
+///     + The node attributes are available as `$attributes` field of structs
+///       (use `#[serde(rename = "$attributes")]`).
+///     + `Option<T>` fields are `None` if no child has the field name.
+/// * `Option<T>` of an existing node is always `Some`.
+/// * Tuples and tuple structs are deserialized from the node attributes.
+/// * Sequences are deserialized from the node attributes.
+///     + If the node has a single array or binary attribute, the elements of
+///       the attribute are deserialized instead.
+/// * Primitives, strings, and unit enum variants are deserialized from the
+///   single attribute of the node.
+///     + If the node does not have exactly one attribute,
+///       [`DeserializeError::AttributeCount`] is returned.
+///     + Integers and floating point values are converted to the target types
+///       if the values fit.
+/// * [`AttributeValue`] is deserialized from the single attribute with the
+///   type preserved (requires `serde` feature).
+///
+/// To deserialize nodes from a parser, load the node into a tree by
+/// [`Loader::load_subtree`][`crate::tree::v7400::Loader::load_subtree`] first.
+///
+/// # Examples
+///
+/// ```
+/// # use fbxcel::tree_v7400;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct GlobalSettings {
+///     #[serde(rename = "Version")]
+///     version: i32,
+///     #[serde(rename = "Properties70")]
+///     properties: Properties70,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Properties70 {
+///     #[serde(rename = "P", default)]
+///     props: Vec<(String, String, String, String, f64)>,
+/// }
+///
+/// let tree = tree_v7400! {
+///     GlobalSettings: {
+///         Version: [1000i32] {},
+///         Properties70: {
+///             P: "UpAxis", "int", "Integer", "", 1i32;
+///             P: "UnitScaleFactor", "double", "Number", "", 2.54f64;
+///         },
+///     },
+/// };
+/// let node = tree.root().first_child_by_name("GlobalSettings").unwrap();
+/// let settings: GlobalSettings = node.deserialize()?;
+/// assert_eq!(settings.version, 1000);
+/// assert_eq!(settings.properties.props[0].0, "UpAxis");
+/// assert_eq!(settings.properties.props[1].4, 2.54);
+/// # Ok::<_, fbxcel::tree::v7400::DeserializeError>(())
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct NodeDeserializer<'a> {
+    /// Node.
+    node: NodeHandle<'a>,
+}
+
+impl<'a> NodeDeserializer<'a> {
+    /// Creates a new `NodeDeserializer`.
+    #[inline]
+    #[must_use]
+    pub fn new(node: NodeHandle<'a>) -> Self {
+        Self { node }
+    }
+
+    /// Returns the deserializer of the node attributes.
+    #[inline]
+    fn attributes(self) -> Result<AttributesDeserializer<'a>, DeserializeError> {
+        Ok(AttributesDeserializer::new(self.node))
+    }
+
+    /// Returns the map access to the child nodes.
+    fn map_access(self, with_attributes: bool) -> NodeMapAccess<'a> {
+        let mut names: Vec<&'a str> = Vec::new();
+        for child in self.node.children() {
+            let name = child.name();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        let attributes = Some(self.node.attributes()).filter(|_| with_attributes);
+        NodeMapAccess {
+            node: self.node,
+            attributes,
+            names: names.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'a> Deserializer<'a> for NodeDeserializer<'a> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        if self.node.children().next().is_some() {
+            let with_attributes = !self.node.attributes().is_empty();
+            visitor.visit_map(self.map_access(with_attributes))
+        } else {
+            self.attributes()?.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        visitor.visit_map(self.map_access(false))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        visitor.visit_map(self.map_access(fields.contains(&ATTRIBUTES_FIELD)))
+    }
+
+    forward_deserialize! { attributes =>
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl<'a> IntoDeserializer<'a, DeserializeError> for NodeDeserializer<'a> {
+    type Deserializer = Self;
+
+    #[inline]
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// Value of a map entry.
+#[derive(Debug, Clone, Copy)]
+enum MapValue<'a> {
+    /// Node attributes.
+    Attributes(AttributesDeserializer<'a>),
+    /// Child nodes with the same name.
+    Children(ChildrenDeserializer<'a>),
+}
+
+/// Map access to the child nodes.
+#[derive(Debug, Clone)]
+struct NodeMapAccess<'a> {
+    /// Parent node.
+    node: NodeHandle<'a>,
+    /// Node attributes, if not yet visited.
+    attributes: Option<&'a [AttributeValue]>,
+    /// Child node names not yet visited.
+    names: std::vec::IntoIter<&'a str>,
+    /// Value of the current entry.
+    value: Option<MapValue<'a>>,
+}
+
+impl<'a> MapAccess<'a> for NodeMapAccess<'a> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'a>,
+    {
+        let key = if self.attributes.take().is_some() {
+            self.value = Some(MapValue::Attributes(AttributesDeserializer::new(self.node)));
+            ATTRIBUTES_FIELD
+        } else if let Some(name) = self.names.next() {
+            self.value = Some(MapValue::Children(ChildrenDeserializer {
+                parent: self.node,
+                name,
+            }));
+            name
+        } else {
+            return Ok(None);
+        };
+        seed.deserialize(BorrowedStrDeserializer::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'a>,
+    {
+        match self.value.take() {
+            Some(MapValue::Attributes(de)) => seed.deserialize(de),
+            Some(MapValue::Children(de)) => seed.deserialize(de),
+            None => Err(de::Error::custom("Map value is requested before the key")),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.names.len() + usize::from(self.attributes.is_some()))
+    }
+}
+
+/// Deserializer of child nodes with the same name.
+#[derive(Debug, Clone, Copy)]
+struct ChildrenDeserializer<'a> {
+    /// Parent node.
+    parent: NodeHandle<'a>,
+    /// Child node name.
+    name: &'a str,
+}
+
+impl<'a> ChildrenDeserializer<'a> {
+    /// Returns the deserializer of the first child.
+    fn first_child(self) -> Result<NodeDeserializer<'a>, DeserializeError> {
+        self.parent
+            .first_child_by_name(self.name)
+            .map(NodeDeserializer::new)
+            .ok_or_else(|| de::Error::custom(format_args!("No `{}` node found", self.name)))
+    }
+}
+
+impl<'a> Deserializer<'a> for ChildrenDeserializer<'a> {
+    type Error = DeserializeError;
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        SeqDeserializer::new(
+            self.parent
+                .children_by_name(self.name)
+                .map(NodeDeserializer::new),
+        )
+        .deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        match self.parent.first_child_by_name(self.name) {
+            Some(child) => visitor.visit_some(NodeDeserializer::new(child)),
+            None => visitor.visit_none(),
+        }
+    }
+
+    forward_deserialize! { first_child =>
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Sequence deserializer of node attributes.
+type AttributesSeqDeserializer<'a> = SeqDeserializer<
+    std::iter::Map<
+        std::slice::Iter<'a, AttributeValue>,
+        fn(&'a AttributeValue) -> AttributeDeserializer<'a>,
+    >,
+    DeserializeError,
+>;
+
+/// Deserializer of node attributes.
+#[derive(Debug, Clone, Copy)]
+struct AttributesDeserializer<'a> {
+    /// Node.
+    node: NodeHandle<'a>,
+}
+
+impl<'a> AttributesDeserializer<'a> {
+    /// Creates a new `AttributesDeserializer`.
+    #[inline]
+    #[must_use]
+    fn new(node: NodeHandle<'a>) -> Self {
+        Self { node }
+    }
+
+    /// Returns the deserializer of the single attribute.
+    fn single(self) -> Result<AttributeDeserializer<'a>, DeserializeError> {
+        match self.node.attributes() {
+            [attr] => Ok(AttributeDeserializer(attr)),
+            attrs => Err(DeserializeError::AttributeCount {
+                node: self.node.name().to_owned(),
+                count: attrs.len(),
+            }),
+        }
+    }
+
+    /// Returns the sequence deserializer of the attributes.
+    #[must_use]
+    fn seq(self) -> AttributesSeqDeserializer<'a> {
+        SeqDeserializer::new(self.node.attributes().iter().map(AttributeDeserializer))
+    }
+}
+
+impl<'a> Deserializer<'a> for AttributesDeserializer<'a> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        match self.node.attributes() {
+            [] => visitor.visit_unit(),
+            [attr] => AttributeDeserializer(attr).deserialize_any(visitor),
+            _ => self.seq().deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        match self.node.attributes() {
+            [attr @ AttributeValue::ArrBool(_)]
+            | [attr @ AttributeValue::ArrI32(_)]
+            | [attr @ AttributeValue::ArrI64(_)]
+            | [attr @ AttributeValue::ArrF32(_)]
+            | [attr @ AttributeValue::ArrF64(_)]
+            | [attr @ AttributeValue::Binary(_)] => {
+                AttributeDeserializer(attr).deserialize_seq(visitor)
+            }
+            _ => self.seq().deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        self.seq().deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        self.seq().deserialize_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_deserialize! { single =>
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+    }
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Deserializer of an attribute.
+#[derive(Debug, Clone, Copy)]
+struct AttributeDeserializer<'a>(&'a AttributeValue);
+
+impl<'a> AttributeDeserializer<'a> {
+    /// Returns the attribute value as `Unexpected`.
+    #[must_use]
+    fn unexpected(&self) -> Unexpected<'a> {
+        match self.0 {
+            AttributeValue::Bool(v) => Unexpected::Bool(*v),
+            AttributeValue::I16(v) => Unexpected::Signed(i64::from(*v)),
+            AttributeValue::I32(v) => Unexpected::Signed(i64::from(*v)),
+            AttributeValue::I64(v) => Unexpected::Signed(*v),
+            AttributeValue::F32(v) => Unexpected::Float(f64::from(*v)),
+            AttributeValue::F64(v) => Unexpected::Float(*v),
+            AttributeValue::ArrBool(_)
+            | AttributeValue::ArrI32(_)
+            | AttributeValue::ArrI64(_)
+            | AttributeValue::ArrF32(_)
+            | AttributeValue::ArrF64(_) => Unexpected::Seq,
+            AttributeValue::String(v) => Unexpected::Str(v),
+            AttributeValue::Binary(v) => Unexpected::Bytes(v),
+        }
+    }
+}
+
+impl<'a> Deserializer<'a> for AttributeDeserializer<'a> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        match self.0 {
+            AttributeValue::Bool(v) => visitor.visit_bool(*v),
+            AttributeValue::I16(v) => visitor.visit_i16(*v),
+            AttributeValue::I32(v) => visitor.visit_i32(*v),
+            AttributeValue::I64(v) => visitor.visit_i64(*v),
+            AttributeValue::F32(v) => visitor.visit_f32(*v),
+            AttributeValue::F64(v) => visitor.visit_f64(*v),
+            AttributeValue::ArrBool(v) => {
+                SeqDeserializer::new(v.iter().copied()).deserialize_any(visitor)
+            }
+            AttributeValue::ArrI32(v) => {
+                SeqDeserializer::new(v.iter().copied()).deserialize_any(visitor)
+            }
+            AttributeValue::ArrI64(v) => {
+                SeqDeserializer::new(v.iter().copied()).deserialize_any(visitor)
+            }
+            AttributeValue::ArrF32(v) => {
+                SeqDeserializer::new(v.iter().copied()).deserialize_any(visitor)
+            }
+            AttributeValue::ArrF64(v) => {
+                SeqDeserializer::new(v.iter().copied()).deserialize_any(visitor)
+            }
+            AttributeValue::String(v) => visitor.visit_borrowed_str(v),
+            AttributeValue::Binary(v) => visitor.visit_borrowed_bytes(v),
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        match self.0 {
+            AttributeValue::Binary(v) => {
+                SeqDeserializer::new(v.iter().copied()).deserialize_any(visitor)
+            }
+            AttributeValue::ArrBool(_)
+            | AttributeValue::ArrI32(_)
+            | AttributeValue::ArrI64(_)
+            | AttributeValue::ArrF32(_)
+            | AttributeValue::ArrF64(_) => self.deserialize_any(visitor),
+            _ => Err(de::Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        if name == ATTRIBUTE_VALUE_NAME {
+            return visitor.visit_enum(self);
+        }
+        match self.0 {
+            AttributeValue::String(v) => visitor.visit_enum(v.as_str().into_deserializer()),
+            _ => Err(de::Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        <W: Visitor<'a>>
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple tuple_struct map struct
+        identifier ignored_any
+    }
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl<'a> IntoDeserializer<'a, DeserializeError> for AttributeDeserializer<'a> {
+    type Deserializer = Self;
+
+    #[inline]
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'a> EnumAccess<'a> for AttributeDeserializer<'a> {
+    type Error = DeserializeError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'a>,
+    {
+        let variant =
+            seed.deserialize(BorrowedStrDeserializer::new(attribute_variant_name(self.0)))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a> VariantAccess<'a> for AttributeDeserializer<'a> {
+    type Error = DeserializeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Err(de::Error::invalid_type(
+            Unexpected::NewtypeVariant,
+            &"unit variant",
+        ))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'a>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        Err(de::Error::invalid_type(
+            Unexpected::NewtypeVariant,
+            &"tuple variant",
+        ))
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'a>,
+    {
+        Err(de::Error::invalid_type(
+            Unexpected::NewtypeVariant,
+            &"struct variant",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::{de::IgnoredAny, Deserialize};
+
+    use crate::{
+        low::v7400::AttributeValue,
+        tree::v7400::{DeserializeError, Tree},
+        tree_v7400,
+    };
+
+    fn gentree() -> Tree {
+        tree_v7400! {
+            Settings: [1i32, "name"] {
+                Version: [1000i32] {},
+                Scale: [2.5f64] {},
+                Flag: [true] {},
+                Axis: ["Y"] {},
+                Indices: [vec![0i32, 1, 2]] {},
+                Data: [vec![1u8, 2, 3]] {},
+                Item: ["a", 1i64] {},
+                Item: ["b", 2i64] {},
+                Empty: {},
+            },
+        }
+    }
+
+    #[test]
+    fn structs() {
+        #[derive(Debug, PartialEq, Eq, Deserialize)]
+        enum Axis {
+            X,
+            Y,
+            Z,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Indices(Vec<u32>);
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Settings {
+            #[serde(rename = "$attributes")]
+            attributes: (i32, String),
+            #[serde(rename = "Version")]
+            version: u16,
+            #[serde(rename = "Scale")]
+            scale: f64,
+            #[serde(rename = "Flag")]
+            flag: bool,
+            #[serde(rename = "Axis")]
+            axis: Axis,
+            #[serde(rename = "Indices")]
+            indices: Indices,
+            #[serde(rename = "Data", with = "serde_bytes_vec")]
+            data: Vec<u8>,
+            #[serde(rename = "Item")]
+            items: Vec<(String, i64)>,
+            #[serde(rename = "Empty")]
+            empty: (),
+            #[serde(rename = "Missing")]
+            missing: Option<i32>,
+        }
+
+        /// Deserializes a byte buffer.
+        mod serde_bytes_vec {
+            use serde::{de, Deserializer};
+
+            pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                struct BytesVisitor;
+                impl<'de> de::Visitor<'de> for BytesVisitor {
+                    type Value = Vec<u8>;
+                    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        f.write_str("bytes")
+                    }
+                    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                        Ok(v.to_vec())
+                    }
+                }
+                deserializer.deserialize_bytes(BytesVisitor)
+            }
+        }
+
+        let tree = gentree();
+        let node = tree.root().first_child_by_name("Settings").unwrap();
+        let settings: Settings = node.deserialize().unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                attributes: (1, "name".to_owned()),
+                version: 1000,
+                scale: 2.5,
+                flag: true,
+                axis: Axis::Y,
+                indices: Indices(vec![0, 1, 2]),
+                data: vec![1, 2, 3],
+                items: vec![("a".to_owned(), 1), ("b".to_owned(), 2)],
+                empty: (),
+                missing: None,
+            }
+        );
+    }
+
+    #[test]
+    fn attribute_values_and_maps() {
+        let tree = gentree();
+        let node = tree.root().first_child_by_name("Settings").unwrap();
+
+        let version: AttributeValue = node
+            .first_child_by_name("Version")
+            .unwrap()
+            .deserialize()
+            .unwrap();
+        assert_eq!(version, AttributeValue::I32(1000));
+        let indices: AttributeValue = node
+            .first_child_by_name("Indices")
+            .unwrap()
+            .deserialize()
+            .unwrap();
+        assert_eq!(indices, AttributeValue::ArrI32(vec![0, 1, 2]));
+
+        // Sequences of children with the same name.
+        let map: BTreeMap<String, Vec<IgnoredAny>> = node.deserialize().unwrap();
+        assert_eq!(map.len(), 8);
+        assert_eq!(map["Item"].len(), 2);
+        assert_eq!(map["Version"].len(), 1);
+
+        let items: Vec<(String, AttributeValue)> = node
+            .children_by_name("Item")
+            .map(|item| item.deserialize())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            items,
+            [
+                ("a".to_owned(), AttributeValue::I64(1)),
+                ("b".to_owned(), AttributeValue::I64(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn user_enum_named_attribute_value() {
+        #[derive(Debug, PartialEq, Eq, Deserialize)]
+        enum AttributeValue {
+            X,
+            Y,
+        }
+
+        let tree = gentree();
+        let node = tree.root().first_child_by_name("Settings").unwrap();
+        let axis: AttributeValue = node
+            .first_child_by_name("Axis")
+            .unwrap()
+            .deserialize()
+            .unwrap();
+        assert_eq!(axis, AttributeValue::Y);
+    }
+
+    #[test]
+    fn options() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Model {
+            #[serde(rename = "$attributes")]
+            attributes: (i64,),
+            #[serde(rename = "Version")]
+            version: i32,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Objects {
+            #[serde(rename = "Model")]
+            models: Vec<Option<Model>>,
+        }
+
+        let tree = tree_v7400! {
+            Objects: {
+                Model: [1i64] {
+                    Version: [2i32] {},
+                },
+                Model: [3i64] {
+                    Version: [4i32] {},
+                },
+            },
+        };
+        let objects = tree.root().first_child_by_name("Objects").unwrap();
+        let model = objects.first_child_by_name("Model").unwrap();
+
+        assert_eq!(
+            model.deserialize::<Option<Model>>().unwrap(),
+            Some(Model {
+                attributes: (1,),
+                version: 2,
+            })
+        );
+        assert_eq!(
+            objects.deserialize::<Objects>().unwrap(),
+            Objects {
+                models: vec![
+                    Some(Model {
+                        attributes: (1,),
+                        version: 2,
+                    }),
+                    Some(Model {
+                        attributes: (3,),
+                        version: 4,
+                    }),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let tree = gentree();
+        let node = tree.root().first_child_by_name("Settings").unwrap();
+
+        assert_eq!(
+            node.deserialize::<i32>(),
+            Err(DeserializeError::AttributeCount {
+                node: "Settings".to_owned(),
+                count: 2,
+            })
+        );
+        let version = node.first_child_by_name("Version").unwrap();
+        assert!(version.deserialize::<i8>().is_err());
+        assert!(version.deserialize::<String>().is_err());
+        assert!(node
+            .first_child_by_name("Empty")
+            .unwrap()
+            .deserialize::<bool>()
+            .is_err());
+    }
+}
@@ -0,0 +1,762 @@
+//! Structural diff between trees.
+//!
+//! # Examples
+//!
+//! ```
+//! use fbxcel::tree::v7400::{diff::ChangeKind, DiffOptions};
+//! use fbxcel::tree_v7400;
+//!
+//! let old = tree_v7400! {
+//!     Objects: {
+//!         Model: [1i64, "Cube"] {},
+//!         Model: [2i64, "Light"] {},
+//!     },
+//! };
+//! let new = tree_v7400! {
+//!     Objects: {
+//!         Model: [2i64, "Lamp"] {},
+//!         Model: [3i64, "Camera"] {},
+//!     },
+//! };
+//!
+//! let diff = old.diff(&new, &DiffOptions::by_name_and_id());
+//! let kinds = diff.changes().iter().map(|c| c.kind()).collect::<Vec<_>>();
+//! assert!(matches!(kinds[..], [
+//!     ChangeKind::NodeRemoved,
+//!     ChangeKind::AttributeValueChanged { index: 1, .. },
+//!     ChangeKind::NodeAdded,
+//! ]));
+//! println!("{}", diff);
+//! ```
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    fmt,
+    ops::Range,
+};
+
+use crate::{
+    low::v7400::{AttributeType, AttributeValue},
+    tree::v7400::{NodeHandle, NodeId},
+};
+
+/// How to pair children of the nodes being compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ChildMatching {
+    /// Pairs children at the same position.
+    Position,
+    /// Pairs children by the node name and the first attribute.
+    ///
+    /// This is useful for FBX objects, whose first attribute is the object ID.
+    /// Children with the same key are paired in order.
+    /// Remaining children with the same `i64` first attribute are then paired
+    /// as renamed nodes.
+    NameAndFirstAttribute,
+}
+
+impl Default for ChildMatching {
+    #[inline]
+    fn default() -> Self {
+        Self::Position
+    }
+}
+
+/// Diff options.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct DiffOptions {
+    /// How to pair children.
+    pub child_matching: ChildMatching,
+}
+
+impl DiffOptions {
+    /// Creates a new default options, which pairs children by position.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new options which pairs children by name and the first
+    /// attribute.
+    #[inline]
+    #[must_use]
+    pub fn by_name_and_id() -> Self {
+        Self {
+            child_matching: ChildMatching::NameAndFirstAttribute,
+        }
+    }
+}
+
+/// Kind of a change.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ChangeKind {
+    /// The node (and its descendants) is added.
+    NodeAdded,
+    /// The node (and its descendants) is removed.
+    NodeRemoved,
+    /// The node is renamed.
+    NodeRenamed {
+        /// Old name.
+        old: String,
+        /// New name.
+        new: String,
+    },
+    /// An attribute is added.
+    AttributeAdded {
+        /// Attribute index.
+        index: usize,
+        /// Added value.
+        value: AttributeValue,
+    },
+    /// An attribute is removed.
+    AttributeRemoved {
+        /// Attribute index.
+        index: usize,
+        /// Removed value.
+        value: AttributeValue,
+    },
+    /// The type of an attribute is changed.
+    AttributeTypeChanged {
+        /// Attribute index.
+        index: usize,
+        /// Old type.
+        old: AttributeType,
+        /// New type.
+        new: AttributeType,
+    },
+    /// The value of a single value or string attribute is changed.
+    AttributeValueChanged {
+        /// Attribute index.
+        index: usize,
+        /// Old value.
+        old: AttributeValue,
+        /// New value.
+        new: AttributeValue,
+    },
+    /// Elements of an array or binary attribute are changed.
+    ArrayChanged {
+        /// Attribute index.
+        index: usize,
+        /// Ranges of changed element indices.
+        ///
+        /// If the length is changed, the last range covers the elements only
+        /// one of the arrays has.
+        ranges: Vec<Range<usize>>,
+        /// Old length.
+        old_len: usize,
+        /// New length.
+        new_len: usize,
+    },
+}
+
+/// A change between trees.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// Node in the old tree.
+    old_node: Option<NodeId>,
+    /// Node in the new tree.
+    new_node: Option<NodeId>,
+    /// Node path.
+    node_path: Vec<(usize, String)>,
+    /// Change kind.
+    kind: ChangeKind,
+}
+
+impl Change {
+    /// Returns the node in the old tree.
+    ///
+    /// Returns `None` for [`ChangeKind::NodeAdded`].
+    #[inline]
+    #[must_use]
+    pub fn old_node(&self) -> Option<NodeId> {
+        self.old_node
+    }
+
+    /// Returns the node in the new tree.
+    ///
+    /// Returns `None` for [`ChangeKind::NodeRemoved`].
+    #[inline]
+    #[must_use]
+    pub fn new_node(&self) -> Option<NodeId> {
+        self.new_node
+    }
+
+    /// Returns the node path.
+    ///
+    /// This is a vector of pairs of node indices in siblings (i.e. the number
+    /// of preceding siblings) and node names.
+    /// The path is in the old tree, except for [`ChangeKind::NodeAdded`],
+    /// whose path is in the new tree.
+    #[inline]
+    #[must_use]
+    pub fn node_path(&self) -> &[(usize, String)] {
+        &self.node_path
+    }
+
+    /// Returns the change kind.
+    #[inline]
+    #[must_use]
+    pub fn kind(&self) -> &ChangeKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = match self.kind {
+            ChangeKind::NodeAdded | ChangeKind::AttributeAdded { .. } => '+',
+            ChangeKind::NodeRemoved | ChangeKind::AttributeRemoved { .. } => '-',
+            _ => '~',
+        };
+        write!(f, "{} ", mark)?;
+        if self.node_path.is_empty() {
+            f.write_str("/")?;
+        }
+        for (index, name) in &self.node_path {
+            write!(f, "/{}[{}]", name, index)?;
+        }
+        f.write_str(": ")?;
+        match &self.kind {
+            ChangeKind::NodeAdded => f.write_str("node added"),
+            ChangeKind::NodeRemoved => f.write_str("node removed"),
+            ChangeKind::NodeRenamed { old, new } => write!(f, "renamed {:?} -> {:?}", old, new),
+            ChangeKind::AttributeAdded { index, value } => {
+                write!(f, "attribute {} added: {:?}", index, value)
+            }
+            ChangeKind::AttributeRemoved { index, value } => {
+                write!(f, "attribute {} removed: {:?}", index, value)
+            }
+            ChangeKind::AttributeTypeChanged { index, old, new } => {
+                write!(
+                    f,
+                    "attribute {} type changed: {:?} -> {:?}",
+                    index, old, new
+                )
+            }
+            ChangeKind::AttributeValueChanged { index, old, new } => {
+                write!(
+                    f,
+                    "attribute {} value changed: {:?} -> {:?}",
+                    index, old, new
+                )
+            }
+            ChangeKind::ArrayChanged {
+                index,
+                ranges,
+                old_len,
+                new_len,
+            } => {
+                write!(f, "attribute {} elements changed at ", index)?;
+                for (i, range) in ranges.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{:?}", range)?;
+                }
+                write!(f, " (length {} -> {})", old_len, new_len)
+            }
+        }
+    }
+}
+
+/// Structural diff between trees.
+///
+/// `Display` implementation prints a readable report, one change per line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeDiff {
+    /// Changes.
+    changes: Vec<Change>,
+}
+
+impl TreeDiff {
+    /// Computes the diff between the given nodes and their descendants.
+    ///
+    /// Node names and attributes are compared strictly, i.e. `f32` and `f64`
+    /// values are compared bitwise.
+    #[must_use]
+    pub fn new(old: NodeHandle<'_>, new: NodeHandle<'_>, options: &DiffOptions) -> Self {
+        let mut differ = Differ {
+            options,
+            changes: Vec::new(),
+            old_path: Vec::new(),
+            new_path: Vec::new(),
+        };
+        differ.diff_nodes(old, new);
+
+        Self {
+            changes: differ.changes,
+        }
+    }
+
+    /// Returns the changes.
+    #[inline]
+    #[must_use]
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Returns true if there are no changes.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for TreeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.changes
+            .iter()
+            .try_for_each(|change| writeln!(f, "{}", change))
+    }
+}
+
+/// Diff state.
+struct Differ<'o> {
+    /// Options.
+    options: &'o DiffOptions,
+    /// Changes found.
+    changes: Vec<Change>,
+    /// Path of the current node in the old tree.
+    old_path: Vec<(usize, String)>,
+    /// Path of the current node in the new tree.
+    new_path: Vec<(usize, String)>,
+}
+
+impl Differ<'_> {
+    /// Records a change at the current path.
+    ///
+    /// The path in the new tree is used for added nodes, and the path in the
+    /// old tree is used for other changes.
+    fn push(&mut self, old: Option<NodeHandle<'_>>, new: Option<NodeHandle<'_>>, kind: ChangeKind) {
+        let node_path = match kind {
+            ChangeKind::NodeAdded => self.new_path.clone(),
+            _ => self.old_path.clone(),
+        };
+        self.changes.push(Change {
+            old_node: old.map(|v| v.node_id()),
+            new_node: new.map(|v| v.node_id()),
+            node_path,
+            kind,
+        });
+    }
+
+    /// Compares the paired nodes.
+    fn diff_nodes(&mut self, old: NodeHandle<'_>, new: NodeHandle<'_>) {
+        if old.name() != new.name() {
+            self.push(
+                Some(old),
+                Some(new),
+                ChangeKind::NodeRenamed {
+                    old: old.name().to_owned(),
+                    new: new.name().to_owned(),
+                },
+            );
+        }
+        self.diff_attributes(old, new);
+        self.diff_children(old, new);
+    }
+
+    /// Compares attributes of the paired nodes.
+    fn diff_attributes(&mut self, old: NodeHandle<'_>, new: NodeHandle<'_>) {
+        let old_attrs = old.attribute_values().collect::<Vec<_>>();
+        let new_attrs = new.attribute_values().collect::<Vec<_>>();
+        for index in 0..old_attrs.len().max(new_attrs.len()) {
+            let kind = match (old_attrs.get(index), new_attrs.get(index)) {
+                (Some(o), Some(n)) => match diff_attribute(index, o, n) {
+                    Some(kind) => kind,
+                    None => continue,
+                },
+                (Some(o), None) => ChangeKind::AttributeRemoved {
+                    index,
+                    value: o.clone().into_owned(),
+                },
+                (None, Some(n)) => ChangeKind::AttributeAdded {
+                    index,
+                    value: n.clone().into_owned(),
+                },
+                (None, None) => unreachable!("Index should be less than the longer length"),
+            };
+            self.push(Some(old), Some(new), kind);
+        }
+    }
+
+    /// Compares children of the paired nodes.
+    fn diff_children(&mut self, old: NodeHandle<'_>, new: NodeHandle<'_>) {
+        let old_children = old.children().collect::<Vec<_>>();
+        let new_children = new.children().collect::<Vec<_>>();
+        let pairs = match self.options.child_matching {
+            ChildMatching::Position => pair_by_position(old_children.len(), new_children.len()),
+            ChildMatching::NameAndFirstAttribute => pair_by_key(&old_children, &new_children),
+        };
+
+        let mut new_paired = vec![false; new_children.len()];
+        for (old_index, new_index) in pairs.into_iter().enumerate() {
+            let old_child = old_children[old_index];
+            self.old_path.push((old_index, old_child.name().to_owned()));
+            match new_index {
+                Some(new_index) => {
+                    new_paired[new_index] = true;
+                    let new_child = new_children[new_index];
+                    self.new_path.push((new_index, new_child.name().to_owned()));
+                    self.diff_nodes(old_child, new_child);
+                    self.new_path.pop();
+                }
+                None => self.push(Some(old_child), None, ChangeKind::NodeRemoved),
+            }
+            self.old_path.pop();
+        }
+        for (new_index, new_child) in new_children.into_iter().enumerate() {
+            if new_paired[new_index] {
+                continue;
+            }
+            self.new_path.push((new_index, new_child.name().to_owned()));
+            self.push(None, Some(new_child), ChangeKind::NodeAdded);
+            self.new_path.pop();
+        }
+    }
+}
+
+/// Pairs children by position.
+///
+/// Returns the indices of the paired new children for each old child.
+fn pair_by_position(old_len: usize, new_len: usize) -> Vec<Option<usize>> {
+    (0..old_len)
+        .map(|i| if i < new_len { Some(i) } else { None })
+        .collect()
+}
+
+/// Pairs children by the node name and the first attribute.
+///
+/// Returns the indices of the paired new children for each old child.
+fn pair_by_key(old: &[NodeHandle<'_>], new: &[NodeHandle<'_>]) -> Vec<Option<usize>> {
+    let mut pairs = vec![None; old.len()];
+
+    // Pair by name and first attribute.
+    let mut candidates: HashMap<_, VecDeque<usize>> = HashMap::new();
+    for (i, node) in new.iter().enumerate() {
+        candidates.entry(node_key(node)).or_default().push_back(i);
+    }
+    for (pair, node) in pairs.iter_mut().zip(old) {
+        *pair = candidates
+            .get_mut(&node_key(node))
+            .and_then(VecDeque::pop_front);
+    }
+
+    // Pair remaining nodes by `i64` first attribute (object ID).
+    let mut new_paired = vec![false; new.len()];
+    pairs.iter().flatten().for_each(|&i| new_paired[i] = true);
+    let mut candidates: HashMap<i64, VecDeque<usize>> = HashMap::new();
+    for (i, node) in new.iter().enumerate() {
+        if let (false, Some(id)) = (new_paired[i], object_id(node)) {
+            candidates.entry(id).or_default().push_back(i);
+        }
+    }
+    for (pair, node) in pairs.iter_mut().zip(old) {
+        if pair.is_none() {
+            if let Some(id) = object_id(node) {
+                *pair = candidates.get_mut(&id).and_then(VecDeque::pop_front);
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Returns the `i64` first attribute of the node.
+fn object_id(node: &NodeHandle<'_>) -> Option<i64> {
+    node.attribute_values().next().and_then(|v| v.get_i64())
+}
+
+/// Returns the key to pair the node.
+fn node_key<'a>(node: &NodeHandle<'a>) -> (&'a str, Option<AttributeKey<'a>>) {
+    let key = node.attribute_values().next().map(|v| match v {
+        Cow::Borrowed(v) => attribute_key(v),
+        Cow::Owned(v) => attribute_key(&v).into_owned(),
+    });
+    (node.name(), key)
+}
+
+/// Hashable representation of an attribute value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum AttributeKey<'a> {
+    /// Single `bool`.
+    Bool(bool),
+    /// Single integer.
+    Int(AttributeType, i64),
+    /// Single floating point number, as bits.
+    Float(AttributeType, u64),
+    /// String.
+    String(Cow<'a, str>),
+    /// Binary.
+    Binary(Cow<'a, [u8]>),
+    /// Array.
+    ///
+    /// Array values are not used as keys, since it would be costly.
+    Array(AttributeType),
+}
+
+/// Returns the hashable representation of the attribute value.
+fn attribute_key(v: &AttributeValue) -> AttributeKey<'_> {
+    match v {
+        AttributeValue::Bool(v) => AttributeKey::Bool(*v),
+        AttributeValue::I16(v) => AttributeKey::Int(AttributeType::I16, i64::from(*v)),
+        AttributeValue::I32(v) => AttributeKey::Int(AttributeType::I32, i64::from(*v)),
+        AttributeValue::I64(v) => AttributeKey::Int(AttributeType::I64, *v),
+        AttributeValue::F32(v) => AttributeKey::Float(AttributeType::F32, u64::from(v.to_bits())),
+        AttributeValue::F64(v) => AttributeKey::Float(AttributeType::F64, v.to_bits()),
+        AttributeValue::String(v) => AttributeKey::String(Cow::Borrowed(v)),
+        AttributeValue::Binary(v) => AttributeKey::Binary(Cow::Borrowed(v)),
+        v => AttributeKey::Array(v.type_()),
+    }
+}
+
+impl AttributeKey<'_> {
+    /// Returns the key owning the string or the binary.
+    fn into_owned(self) -> AttributeKey<'static> {
+        match self {
+            Self::Bool(v) => AttributeKey::Bool(v),
+            Self::Int(ty, v) => AttributeKey::Int(ty, v),
+            Self::Float(ty, v) => AttributeKey::Float(ty, v),
+            Self::String(v) => AttributeKey::String(Cow::Owned(v.into_owned())),
+            Self::Binary(v) => AttributeKey::Binary(Cow::Owned(v.into_owned())),
+            Self::Array(ty) => AttributeKey::Array(ty),
+        }
+    }
+}
+
+/// Compares the attributes at the same index.
+fn diff_attribute(index: usize, old: &AttributeValue, new: &AttributeValue) -> Option<ChangeKind> {
+    if old.type_() != new.type_() {
+        return Some(ChangeKind::AttributeTypeChanged {
+            index,
+            old: old.type_(),
+            new: new.type_(),
+        });
+    }
+
+    /// Creates `ArrayChanged` if the arrays differ.
+    fn array_changed<T>(
+        index: usize,
+        old: &[T],
+        new: &[T],
+        eq: impl Fn(&T, &T) -> bool,
+    ) -> Option<ChangeKind> {
+        let ranges = diff_ranges(old, new, eq);
+        if ranges.is_empty() {
+            return None;
+        }
+        Some(ChangeKind::ArrayChanged {
+            index,
+            ranges,
+            old_len: old.len(),
+            new_len: new.len(),
+        })
+    }
+
+    match (old, new) {
+        (AttributeValue::ArrBool(o), AttributeValue::ArrBool(n)) => {
+            array_changed(index, o, n, PartialEq::eq)
+        }
+        (AttributeValue::ArrI32(o), AttributeValue::ArrI32(n)) => {
+            array_changed(index, o, n, PartialEq::eq)
+        }
+        (AttributeValue::ArrI64(o), AttributeValue::ArrI64(n)) => {
+            array_changed(index, o, n, PartialEq::eq)
+        }
+        (AttributeValue::ArrF32(o), AttributeValue::ArrF32(n)) => {
+            array_changed(index, o, n, |l, r| l.to_bits() == r.to_bits())
+        }
+        (AttributeValue::ArrF64(o), AttributeValue::ArrF64(n)) => {
+            array_changed(index, o, n, |l, r| l.to_bits() == r.to_bits())
+        }
+        (AttributeValue::Binary(o), AttributeValue::Binary(n)) => {
+            array_changed(index, o, n, PartialEq::eq)
+        }
+        (o, n) if o.strict_eq(n) => None,
+        (o, n) => Some(ChangeKind::AttributeValueChanged {
+            index,
+            old: o.clone(),
+            new: n.clone(),
+        }),
+    }
+}
+
+/// Returns ranges of the differing elements.
+///
+/// If the lengths differ, the elements only the longer slice has are reported
+/// as the last range.
+fn diff_ranges<T>(old: &[T], new: &[T], eq: impl Fn(&T, &T) -> bool) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut push = |i: usize| match ranges.last_mut() {
+        Some(last) if last.end == i => last.end = i + 1,
+        _ => ranges.push(i..(i + 1)),
+    };
+    old.iter()
+        .zip(new)
+        .enumerate()
+        .filter(|(_, (o, n))| !eq(o, n))
+        .for_each(|(i, _)| push(i));
+    (old.len().min(new.len())..old.len().max(new.len())).for_each(push);
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tree_v7400;
+
+    #[test]
+    fn positional() {
+        let old = tree_v7400! {
+            A: [1i32, "x", vec![1.0f32, 2.0, 3.0, 4.0]] {
+                B: {},
+                C: [true] {},
+            },
+        };
+        let new = tree_v7400! {
+            A: [1i64, "y", vec![1.0f32, 0.0, 0.0, 4.0, 5.0], 7i16] {
+                D: {},
+            },
+        };
+
+        let diff = old.diff(&new, &DiffOptions::new());
+        let changes = diff
+            .changes()
+            .iter()
+            .map(|c| (c.node_path().to_vec(), c.kind().clone()))
+            .collect::<Vec<_>>();
+        let a = vec![(0, "A".to_owned())];
+        assert_eq!(
+            changes,
+            [
+                (
+                    a.clone(),
+                    ChangeKind::AttributeTypeChanged {
+                        index: 0,
+                        old: AttributeType::I32,
+                        new: AttributeType::I64,
+                    }
+                ),
+                (
+                    a.clone(),
+                    ChangeKind::AttributeValueChanged {
+                        index: 1,
+                        old: "x".into(),
+                        new: "y".into(),
+                    }
+                ),
+                (
+                    a.clone(),
+                    ChangeKind::ArrayChanged {
+                        index: 2,
+                        ranges: vec![1..3, 4..5],
+                        old_len: 4,
+                        new_len: 5,
+                    }
+                ),
+                (
+                    a.clone(),
+                    ChangeKind::AttributeAdded {
+                        index: 3,
+                        value: 7i16.into(),
+                    }
+                ),
+                (
+                    vec![(0, "A".into()), (0, "B".into())],
+                    ChangeKind::NodeRenamed {
+                        old: "B".into(),
+                        new: "D".into(),
+                    }
+                ),
+                (
+                    vec![(0, "A".into()), (1, "C".into())],
+                    ChangeKind::NodeRemoved
+                ),
+            ]
+        );
+        assert_eq!(
+            diff.to_string().lines().last(),
+            Some("- /A[0]/C[1]: node removed")
+        );
+        assert!(old.diff(&old.clone(), &DiffOptions::new()).is_empty());
+    }
+
+    #[test]
+    fn by_name_and_id() {
+        let old = tree_v7400! {
+            Objects: {
+                Model: [1i64] {},
+                Model: [2i64] {},
+                Geometry: [3i64] {},
+            },
+        };
+        let new = tree_v7400! {
+            Objects: {
+                Geometry: [4i64] {},
+                Model: [2i64] {},
+                Mesh: [3i64] {},
+                Model: [1i64] {},
+            },
+        };
+
+        let diff = old.diff(&new, &DiffOptions::by_name_and_id());
+        let changes = diff
+            .changes()
+            .iter()
+            .map(|c| (c.node_path().last().cloned(), c.kind().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                (
+                    Some((2, "Geometry".into())),
+                    ChangeKind::NodeRenamed {
+                        old: "Geometry".into(),
+                        new: "Mesh".into(),
+                    }
+                ),
+                (Some((0, "Geometry".into())), ChangeKind::NodeAdded),
+            ]
+        );
+    }
+
+    #[test]
+    fn added_node_path_in_new_tree() {
+        let old = tree_v7400! {
+            Objects: {
+                Model: [1i64] {},
+                Model: [2i64] {},
+            },
+        };
+        let new = tree_v7400! {
+            Objects: {
+                Model: [2i64] {
+                    Properties70: {},
+                },
+                Model: [1i64] {},
+            },
+        };
+
+        let diff = old.diff(&new, &DiffOptions::by_name_and_id());
+        assert_eq!(diff.changes().len(), 1);
+        let change = &diff.changes()[0];
+        assert_eq!(change.kind(), &ChangeKind::NodeAdded);
+        assert_eq!(
+            change.node_path(),
+            [
+                (0, "Objects".to_owned()),
+                (0, "Model".to_owned()),
+                (0, "Properties70".to_owned()),
+            ]
+        );
+        let added = change.new_node().unwrap().to_handle(&new);
+        assert_eq!(added.parent().unwrap().attributes(), [2i64.into()]);
+    }
+}
@@ -2,7 +2,7 @@
 
 use std::{error, fmt};
 
-use crate::pull_parser::Error as ParserError;
+use crate::{pull_parser::Error as ParserError, tree::v7400::NodeId};
 
 /// FBX data tree load error.
 #[derive(Debug)]
@@ -41,3 +41,82 @@ impl From<ParserError> for LoadError {
         LoadError::Parser(e)
     }
 }
+
+/// FBX data tree edit error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EditError {
+    /// The node ID belongs to another tree.
+    ForeignNode(NodeId),
+    /// The node has been removed from the tree.
+    RemovedNode(NodeId),
+    /// The operation is not allowed for the root node.
+    RootNode,
+    /// The node cannot be moved to itself or its descendants.
+    CyclicMove(NodeId),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::ForeignNode(id) => {
+                write!(f, "The node ID belongs to another tree: node_id={:?}", id)
+            }
+            EditError::RemovedNode(id) => {
+                write!(f, "The node is removed from the tree: node_id={:?}", id)
+            }
+            EditError::RootNode => f.write_str("The operation is not allowed for the root node"),
+            EditError::CyclicMove(id) => write!(
+                f,
+                "The node cannot be moved to itself or its descendants: node_id={:?}",
+                id
+            ),
+        }
+    }
+}
+
+impl error::Error for EditError {}
+
+/// Error on deserialization of nodes by
+/// [`NodeDeserializer`][`crate::tree::v7400::NodeDeserializer`].
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DeserializeError {
+    /// The node has no attribute or multiple attributes, where a single
+    /// attribute is expected.
+    AttributeCount {
+        /// Node name.
+        node: String,
+        /// Actual number of attributes.
+        count: usize,
+    },
+    /// Error reported by the deserialized type.
+    Custom(String),
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeserializeError::AttributeCount { node, count } => write!(
+                f,
+                "Expected a single attribute, but node {:?} has {} attributes",
+                node, count
+            ),
+            DeserializeError::Custom(msg) => f.write_str(msg),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl error::Error for DeserializeError {}
+
+#[cfg(feature = "serde")]
+impl serde::de::Error for DeserializeError {
+    #[inline]
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeserializeError::Custom(msg.to_string())
+    }
+}
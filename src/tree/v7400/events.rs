@@ -0,0 +1,424 @@
+//! Pull-parser-style events replayed from a tree.
+//!
+//! [`TreeEvents`] walks a tree (or a subtree) and yields [`TreeEvent`]s
+//! equivalent to [`pull_parser::v7400::Event`][`crate::pull_parser::v7400::Event`].
+//! Node attributes are served through the same
+//! [`LoadAttribute`] loaders as the pull parser, so attribute
+//! handling code can be shared between files and trees.
+//!
+//! # Examples
+//!
+//! ```
+//! use fbxcel::pull_parser::v7400::attribute::loaders::DirectLoader;
+//! use fbxcel::tree::v7400::TreeEvent;
+//! use fbxcel::tree_v7400;
+//!
+//! let tree = tree_v7400! {
+//!     Node0: [1i32, "foo"] {
+//!         Node0_0: {},
+//!     },
+//! };
+//!
+//! let mut names = Vec::new();
+//! for event in tree.events() {
+//!     match event {
+//!         TreeEvent::StartNode(start) => {
+//!             names.push(start.name().to_owned());
+//!             let attrs = start
+//!                 .attributes()
+//!                 .into_iter(std::iter::repeat(DirectLoader))
+//!                 .collect::<Result<Vec<_>, _>>()?;
+//!             println!("{:?}", attrs);
+//!         }
+//!         TreeEvent::EndNode => names.push("/".to_owned()),
+//!         TreeEvent::EndTree => names.push("(end)".to_owned()),
+//!     }
+//! }
+//! assert_eq!(names, ["Node0", "Node0_0", "/", "/", "(end)"]);
+//! # Ok::<_, fbxcel::pull_parser::Error>(())
+//! ```
+
+use std::iter::FusedIterator;
+
+use crate::{
+    low::v7400::AttributeValue,
+    pull_parser::{v7400::LoadAttribute, Result},
+    tree::v7400::{
+        AttributeValues, DepthFirstTraverseSubtree, DepthFirstTraversed, NodeHandle, NodeId, Tree,
+    },
+};
+
+/// Event replayed from a tree.
+#[derive(Debug, Clone, Copy)]
+pub enum TreeEvent<'a> {
+    /// Start of a node.
+    StartNode(TreeStartNode<'a>),
+    /// End of a node.
+    EndNode,
+    /// End of the tree (or the subtree).
+    ///
+    /// This corresponds to `EndFbx` event of the pull parser, but trees have
+    /// no FBX footer.
+    EndTree,
+}
+
+/// Node start event replayed from a tree.
+#[derive(Debug, Clone, Copy)]
+pub struct TreeStartNode<'a> {
+    /// Node.
+    node: NodeHandle<'a>,
+}
+
+impl<'a> TreeStartNode<'a> {
+    /// Returns the node name.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &'a str {
+        self.node.name()
+    }
+
+    /// Returns the node handle.
+    #[inline]
+    #[must_use]
+    pub fn node(&self) -> NodeHandle<'a> {
+        self.node
+    }
+
+    /// Returns node attributes reader.
+    #[inline]
+    #[must_use]
+    pub fn attributes(self) -> TreeAttributes<'a> {
+        TreeAttributes {
+            total_count: self.node.attribute_values().len(),
+            iter: self.node.attribute_values(),
+        }
+    }
+}
+
+/// Node attributes reader over a tree node.
+///
+/// This provides the same methods as
+/// [`pull_parser::v7400::Attributes`][`crate::pull_parser::v7400::Attributes`].
+#[derive(Debug, Clone)]
+pub struct TreeAttributes<'a> {
+    /// Number of all attributes.
+    total_count: usize,
+    /// Rest attributes.
+    iter: AttributeValues<'a>,
+}
+
+impl<'a> TreeAttributes<'a> {
+    /// Returns the total number of attributes.
+    #[inline]
+    #[must_use]
+    pub fn total_count(&self) -> u64 {
+        self.total_count as u64
+    }
+
+    /// Returns the rest number of attributes.
+    #[inline]
+    #[must_use]
+    pub fn rest_count(&self) -> u64 {
+        self.iter.len() as u64
+    }
+
+    /// Lets loader load the next node attribute.
+    pub fn load_next<V>(&mut self, loader: V) -> Result<Option<V::Output>>
+    where
+        V: LoadAttribute,
+    {
+        self.iter
+            .next()
+            .map(|value| load_value(&value, loader))
+            .transpose()
+    }
+
+    /// Lets loader load the next node attribute.
+    ///
+    /// Binaries and strings are always served through
+    /// `V::load_{binary,string}_buffered`, so this is same as
+    /// [`load_next`][`Self::load_next`].
+    #[inline]
+    pub fn load_next_buffered<V>(&mut self, loader: V) -> Result<Option<V::Output>>
+    where
+        V: LoadAttribute,
+    {
+        self.load_next(loader)
+    }
+
+    /// Creates an iterator emitting attribute values.
+    #[inline]
+    pub fn iter<V, I>(&mut self, loaders: I) -> TreeAttributesIter<'_, 'a, I::IntoIter>
+    where
+        V: LoadAttribute,
+        I: IntoIterator<Item = V>,
+    {
+        TreeAttributesIter {
+            attributes: self,
+            loaders: loaders.into_iter(),
+        }
+    }
+
+    /// Creates an iterator emitting attribute values.
+    #[inline]
+    pub fn into_iter<V, I>(self, loaders: I) -> TreeAttributesIntoIter<'a, I::IntoIter>
+    where
+        V: LoadAttribute,
+        I: IntoIterator<Item = V>,
+    {
+        TreeAttributesIntoIter {
+            iter: self.iter,
+            loaders: loaders.into_iter(),
+        }
+    }
+}
+
+/// Lets the loader load the attribute value.
+fn load_value<V: LoadAttribute>(value: &AttributeValue, loader: V) -> Result<V::Output> {
+    match value {
+        AttributeValue::Bool(v) => loader.load_bool(*v),
+        AttributeValue::I16(v) => loader.load_i16(*v),
+        AttributeValue::I32(v) => loader.load_i32(*v),
+        AttributeValue::I64(v) => loader.load_i64(*v),
+        AttributeValue::F32(v) => loader.load_f32(*v),
+        AttributeValue::F64(v) => loader.load_f64(*v),
+        AttributeValue::ArrBool(v) => loader.load_seq_bool(v.iter().copied().map(Ok), v.len()),
+        AttributeValue::ArrI32(v) => loader.load_seq_i32(v.iter().copied().map(Ok), v.len()),
+        AttributeValue::ArrI64(v) => loader.load_seq_i64(v.iter().copied().map(Ok), v.len()),
+        AttributeValue::ArrF32(v) => loader.load_seq_f32(v.iter().copied().map(Ok), v.len()),
+        AttributeValue::ArrF64(v) => loader.load_seq_f64(v.iter().copied().map(Ok), v.len()),
+        AttributeValue::Binary(v) => loader.load_binary_buffered(&v[..], v.len() as u64),
+        AttributeValue::String(v) => loader.load_string_buffered(v.as_bytes(), v.len() as u64),
+    }
+}
+
+/// Node attributes iterator borrowing [`TreeAttributes`].
+#[derive(Debug)]
+pub struct TreeAttributesIter<'b, 'a, I> {
+    /// Attributes.
+    attributes: &'b mut TreeAttributes<'a>,
+    /// Loaders.
+    loaders: I,
+}
+
+impl<I, V> Iterator for TreeAttributesIter<'_, '_, I>
+where
+    I: Iterator<Item = V>,
+    V: LoadAttribute,
+{
+    type Item = Result<V::Output>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.attributes.iter.len() == 0 {
+            return None;
+        }
+        let loader = self.loaders.next()?;
+        self.attributes.load_next(loader).transpose()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        size_hint(self.attributes.iter.len(), &self.loaders)
+    }
+}
+
+/// Node attributes iterator owning [`TreeAttributes`].
+#[derive(Debug)]
+pub struct TreeAttributesIntoIter<'a, I> {
+    /// Rest attributes.
+    iter: AttributeValues<'a>,
+    /// Loaders.
+    loaders: I,
+}
+
+impl<I, V> Iterator for TreeAttributesIntoIter<'_, I>
+where
+    I: Iterator<Item = V>,
+    V: LoadAttribute,
+{
+    type Item = Result<V::Output>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.iter.len() == 0 {
+            return None;
+        }
+        let loader = self.loaders.next()?;
+        self.iter.next().map(|value| load_value(&value, loader))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        size_hint(self.iter.len(), &self.loaders)
+    }
+}
+
+/// Creates size hint from the number of the rest attributes and loaders.
+fn size_hint<V>(rest: usize, loaders: &impl Iterator<Item = V>) -> (usize, Option<usize>) {
+    let (loaders_min, loaders_max) = loaders.size_hint();
+    let min = rest.min(loaders_min);
+    let max = loaders_max.map_or(rest, |v| rest.min(v));
+
+    (min, Some(max))
+}
+
+/// Iterator of events replayed from a tree.
+///
+/// This is created by [`Tree::events`] or [`NodeHandle::events`].
+/// After [`TreeEvent::EndTree`] is emitted, this returns `None`.
+#[derive(Debug, Clone)]
+pub struct TreeEvents<'a> {
+    /// Tree.
+    tree: &'a Tree,
+    /// Depth-first traversal.
+    traverse: DepthFirstTraverseSubtree,
+    /// Root node of the tree, if events for it should be skipped.
+    skipped_root: Option<NodeId>,
+    /// Whether `EndTree` is emitted.
+    finished: bool,
+}
+
+impl<'a> TreeEvents<'a> {
+    /// Creates a new iterator.
+    ///
+    /// If the given node is the root node of the tree, events for the root
+    /// node itself are not emitted.
+    #[must_use]
+    fn new(node: NodeHandle<'a>) -> Self {
+        let tree = node.tree();
+        Self {
+            tree,
+            traverse: node.node_id().traverse_depth_first(),
+            skipped_root: Some(tree.root_id).filter(|&root| root == node.node_id()),
+            finished: false,
+        }
+    }
+}
+
+impl<'a> Iterator for TreeEvents<'a> {
+    type Item = TreeEvent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        loop {
+            let event = match self.traverse.next_forward(self.tree) {
+                Some(v) => v,
+                None => {
+                    self.finished = true;
+                    return Some(TreeEvent::EndTree);
+                }
+            };
+            if Some(event.node_id()) == self.skipped_root {
+                continue;
+            }
+            return Some(match event {
+                DepthFirstTraversed::Open(id) => TreeEvent::StartNode(TreeStartNode {
+                    node: id.to_handle(self.tree),
+                }),
+                DepthFirstTraversed::Close(_) => TreeEvent::EndNode,
+            });
+        }
+    }
+}
+
+impl FusedIterator for TreeEvents<'_> {}
+
+impl Tree {
+    /// Returns an iterator of pull-parser-style events replayed from the tree.
+    ///
+    /// See [`events`][`crate::tree::v7400::events`] module documentation for
+    /// details.
+    #[inline]
+    #[must_use]
+    pub fn events(&self) -> TreeEvents<'_> {
+        TreeEvents::new(self.root())
+    }
+}
+
+impl<'a> NodeHandle<'a> {
+    /// Returns an iterator of pull-parser-style events replayed from the
+    /// node and its descendants.
+    ///
+    /// If the node is the root node of the tree, this is same as
+    /// [`Tree::events`].
+    /// Otherwise, events for the node itself are also emitted.
+    #[inline]
+    #[must_use]
+    pub fn events(&self) -> TreeEvents<'a> {
+        TreeEvents::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        pull_parser::v7400::attribute::loaders::{DirectLoader, PrimitiveLoader},
+        tree_v7400,
+    };
+
+    /// Rebuilds a tree from the events.
+    fn rebuild(events: TreeEvents<'_>) -> Result<Tree> {
+        let mut tree = Tree::default();
+        let mut open = vec![tree.root().node_id()];
+        for event in events {
+            match event {
+                TreeEvent::StartNode(start) => {
+                    let parent = *open.last().unwrap();
+                    let node = tree.append_new(parent, start.name());
+                    let mut attrs = start.attributes();
+                    assert_eq!(attrs.total_count(), attrs.rest_count());
+                    let values = attrs
+                        .iter(std::iter::repeat(DirectLoader))
+                        .collect::<Result<Vec<_>>>()?;
+                    assert_eq!(attrs.rest_count(), 0);
+                    tree.set_attributes_vec(node, values);
+                    open.push(node);
+                }
+                TreeEvent::EndNode => {
+                    open.pop().unwrap();
+                }
+                TreeEvent::EndTree => assert_eq!(open.len(), 1),
+            }
+        }
+        Ok(tree)
+    }
+
+    #[test]
+    fn replay() -> Result<()> {
+        let tree = tree_v7400! {
+            A: [true, 1i16, 2i32, 3i64, 4.0f32, 5.0f64] {
+                B: [vec![true], vec![1i32], vec![2i64], vec![3.0f32], vec![4.0f64]] {},
+                C: [&b"bin"[..], "str"] {},
+            },
+            D: {},
+        };
+        assert!(rebuild(tree.events())?.strict_eq(&tree));
+
+        let a = tree.root().first_child().unwrap();
+        let subtree = rebuild(a.events())?;
+        assert!(subtree.root().first_child().unwrap().strict_eq(&a));
+        assert_eq!(subtree.root().children().count(), 1);
+
+        let mut events = tree.events();
+        assert_eq!(events.by_ref().count(), 9);
+        assert!(events.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn typed_loaders() {
+        let tree = tree_v7400! { A: [42i32, "foo"] {} };
+        let start = match tree.events().next() {
+            Some(TreeEvent::StartNode(start)) => start,
+            v => panic!("Unexpected event: {:?}", v),
+        };
+        let mut attrs = start.attributes();
+        assert_eq!(
+            attrs.load_next(PrimitiveLoader::<i32>::default()).unwrap(),
+            Some(42)
+        );
+        assert!(attrs.load_next(PrimitiveLoader::<i32>::default()).is_err());
+        assert!(attrs.load_next(DirectLoader).unwrap().is_none());
+    }
+}
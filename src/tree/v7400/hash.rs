@@ -0,0 +1,315 @@
+//! Content hashing of subtrees.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    low::v7400::AttributeValue,
+    tree::v7400::{NodeHandle, NodeId, Tree},
+};
+
+/// Content hash of a subtree.
+///
+/// The hash is computed from node names, attributes, and children, and does
+/// not depend on the arena layout, the array encoding in the source file, the
+/// platform, or the process.
+/// Floating point numbers are hashed bitwise, so trees with the same content
+/// hash are (almost certainly) equal by
+/// [`strict_eq`][`NodeHandle::strict_eq`].
+///
+/// The hash is 128-bit FNV-1a over a Merkle-style serialization.
+/// The algorithm is not cryptographically secure, and it is changed only with
+/// a breaking release of this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ContentHash(u128);
+
+impl ContentHash {
+    /// Returns the hash value as an integer.
+    #[inline]
+    #[must_use]
+    pub fn as_u128(self) -> u128 {
+        self.0
+    }
+}
+
+impl fmt::Display for ContentHash {
+    /// Writes the hash as 32 lowercase hexadecimal digits.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+/// 128-bit FNV-1a hasher.
+struct Fnv1a128(u128);
+
+impl Fnv1a128 {
+    /// Offset basis.
+    const OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    /// Prime.
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+    /// Creates a new hasher.
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    /// Writes the bytes.
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u128::from(byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Writes the length.
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    /// Writes the attribute value.
+    fn write_attribute(&mut self, value: &AttributeValue) {
+        /// Writes the array elements.
+        macro_rules! write_arr {
+            ($arr:expr, |$v:ident| $bytes:expr) => {{
+                self.write_len($arr.len());
+                for &$v in $arr.iter() {
+                    self.write(&$bytes);
+                }
+            }};
+        }
+
+        self.write(&[value.type_().type_code()]);
+        match value {
+            AttributeValue::Bool(v) => self.write(&[u8::from(*v)]),
+            AttributeValue::I16(v) => self.write(&v.to_le_bytes()),
+            AttributeValue::I32(v) => self.write(&v.to_le_bytes()),
+            AttributeValue::I64(v) => self.write(&v.to_le_bytes()),
+            AttributeValue::F32(v) => self.write(&v.to_bits().to_le_bytes()),
+            AttributeValue::F64(v) => self.write(&v.to_bits().to_le_bytes()),
+            AttributeValue::ArrBool(arr) => write_arr!(arr, |v| [u8::from(v)]),
+            AttributeValue::ArrI32(arr) => write_arr!(arr, |v| v.to_le_bytes()),
+            AttributeValue::ArrI64(arr) => write_arr!(arr, |v| v.to_le_bytes()),
+            AttributeValue::ArrF32(arr) => write_arr!(arr, |v| v.to_bits().to_le_bytes()),
+            AttributeValue::ArrF64(arr) => write_arr!(arr, |v| v.to_bits().to_le_bytes()),
+            AttributeValue::Binary(v) => {
+                self.write_len(v.len());
+                self.write(v);
+            }
+            AttributeValue::String(v) => {
+                self.write_len(v.len());
+                self.write(v.as_bytes());
+            }
+        }
+    }
+
+    /// Returns the hash.
+    fn finish(&self) -> ContentHash {
+        ContentHash(self.0)
+    }
+}
+
+/// Computes the content hash of the subtree, and calls `f` for every node in
+/// the subtree in postorder.
+fn hash_subtree(node: NodeHandle<'_>, f: &mut impl FnMut(NodeId, ContentHash)) -> ContentHash {
+    let mut hasher = Fnv1a128::new();
+    let name = node.name();
+    hasher.write_len(name.len());
+    hasher.write(name.as_bytes());
+    let attributes = node.attribute_values();
+    hasher.write_len(attributes.len());
+    for attr in attributes {
+        hasher.write_attribute(&attr);
+    }
+    let mut num_children = 0;
+    for child in node.children() {
+        hasher.write(&hash_subtree(child, f).as_u128().to_le_bytes());
+        num_children += 1;
+    }
+    hasher.write_len(num_children);
+
+    let hash = hasher.finish();
+    f(node.node_id(), hash);
+    hash
+}
+
+impl NodeHandle<'_> {
+    /// Returns the content hash of the node and its descendants.
+    ///
+    /// See [`ContentHash`] for details.
+    #[must_use]
+    pub fn content_hash(&self) -> ContentHash {
+        hash_subtree(*self, &mut |_, _| {})
+    }
+}
+
+impl Tree {
+    /// Returns the content hash of the tree.
+    ///
+    /// See [`ContentHash`] for details.
+    #[inline]
+    #[must_use]
+    pub fn content_hash(&self) -> ContentHash {
+        self.root().content_hash()
+    }
+
+    /// Returns groups of duplicate subtrees in the tree.
+    ///
+    /// Each group has two or more node IDs of subtrees equal by
+    /// [`strict_eq`][`NodeHandle::strict_eq`], in preorder.
+    /// Groups are also sorted in preorder of their first nodes.
+    ///
+    /// Groups implied by larger duplicates are omitted, i.e. a group is not
+    /// returned if its nodes are children of distinct nodes in another group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fbxcel::tree_v7400;
+    ///
+    /// let tree = tree_v7400! {
+    ///     Objects: {
+    ///         Geometry: [1i64] { Vertices: [vec![0.0f64; 3]] {} },
+    ///         Geometry: [2i64] { Vertices: [vec![0.0f64; 3]] {} },
+    ///         Material: [3i64] {},
+    ///     },
+    /// };
+    ///
+    /// let groups = tree.duplicate_subtrees();
+    /// assert_eq!(groups.len(), 1);
+    /// assert!(groups[0].iter().all(|id| id.to_handle(&tree).name() == "Vertices"));
+    /// ```
+    #[must_use]
+    pub fn duplicate_subtrees(&self) -> Vec<Vec<NodeId>> {
+        let mut postorder = Vec::new();
+        hash_subtree(self.root(), &mut |id, hash| postorder.push((id, hash)));
+        postorder.pop();
+
+        // Group by hashes, and then by strict equality to reject collisions.
+        let mut by_hash: HashMap<ContentHash, Vec<Vec<NodeId>>> = HashMap::new();
+        for (id, hash) in postorder.into_iter().rev() {
+            let candidates = by_hash.entry(hash).or_default();
+            let node = id.to_handle(self);
+            match candidates
+                .iter_mut()
+                .find(|group| group[0].to_handle(self).strict_eq(&node))
+            {
+                Some(group) => group.push(id),
+                None => candidates.push(vec![id]),
+            }
+        }
+
+        // The traversal above is in reversed postorder; reorder to preorder.
+        let mut groups: Vec<Vec<NodeId>> = by_hash
+            .into_values()
+            .flatten()
+            .filter(|group| group.len() > 1)
+            .collect();
+        let preorder: HashMap<NodeId, usize> = self
+            .root_id
+            .raw()
+            .descendants(&self.arena)
+            .enumerate()
+            .map(|(i, id)| (NodeId::new(id, self.tree_id()), i))
+            .collect();
+        for group in &mut groups {
+            group.sort_by_key(|id| preorder[id]);
+        }
+        groups.sort_by_key(|group| preorder[&group[0]]);
+
+        let group_of: HashMap<NodeId, usize> = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(i, group)| group.iter().map(move |id| (*id, i)))
+            .collect();
+        let is_implied = |group: &[NodeId]| {
+            let parents = group
+                .iter()
+                .map(|id| {
+                    id.to_handle(self)
+                        .parent()
+                        .expect("Should never fail: non-root nodes have parents")
+                        .node_id()
+                })
+                .collect::<Vec<_>>();
+            let parent_group = match group_of.get(&parents[0]) {
+                Some(i) => *i,
+                None => return false,
+            };
+            let mut distinct = parents.clone();
+            distinct.sort_by_key(|id| preorder[id]);
+            distinct.dedup();
+            distinct.len() == parents.len()
+                && parents
+                    .iter()
+                    .all(|parent| group_of.get(parent) == Some(&parent_group))
+        };
+        groups.retain(|group| !is_implied(group));
+
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tree_v7400;
+
+    #[test]
+    fn hash_is_content_based() {
+        let tree = tree_v7400! {
+            A: [1i32, vec![0.5f64]] { B: ["x"] {} },
+            A: [1i32, vec![0.5f64]] { B: ["x"] {} },
+            A: [1i64, vec![0.5f64]] { B: ["x"] {} },
+            A: [1i32, vec![-0.5f64]] { B: ["x"] {} },
+            A: [1i32, vec![0.5f64]] { B: ["x"] {}, B: ["x"] {} },
+        };
+        let hashes = tree
+            .root()
+            .children()
+            .map(|node| node.content_hash())
+            .collect::<Vec<_>>();
+        assert_eq!(hashes[0], hashes[1]);
+        for i in 1..hashes.len() {
+            for j in (i + 1)..hashes.len() {
+                assert_ne!(hashes[i], hashes[j]);
+            }
+        }
+
+        // Independent of the arena layout.
+        let mut other = Tree::default();
+        let root = other.root().node_id();
+        let b = other.create_node("B");
+        other.append_attribute(b, "x");
+        for node in tree.root().children() {
+            other.import_subtree(root, node);
+        }
+        other.remove_subtree(b);
+        assert_eq!(other.content_hash(), tree.content_hash());
+
+        // Stable across platforms and processes.
+        assert_eq!(
+            Tree::default().content_hash().to_string(),
+            "c5975d646bc2ca7ec51f289603d0f8ad"
+        );
+    }
+
+    #[test]
+    fn duplicates() {
+        let tree = tree_v7400! {
+            G: [1i32] { V: [vec![1.0f64]] {}, N: {} },
+            G: [1i32] { V: [vec![1.0f64]] {}, N: {} },
+            H: { N: {}, N: {} },
+        };
+        let groups = tree
+            .duplicate_subtrees()
+            .into_iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|id| id.to_handle(&tree).name())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(groups, [vec!["G", "G"], vec!["N", "N", "N", "N"]]);
+    }
+}
@@ -0,0 +1,694 @@
+//! Change journal of the tree.
+
+use std::collections::HashSet;
+
+use crate::{
+    low::v7400::AttributeValue,
+    tree::v7400::{NodeId, NodeNameSym, Tree},
+};
+
+/// Position of a node in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Position {
+    /// The node has no parent.
+    Detached,
+    /// The node is the first child of the given node.
+    FirstChild(NodeId),
+    /// The node is the next sibling of the given node.
+    After(NodeId),
+}
+
+impl Position {
+    /// Returns the current position of the node.
+    #[must_use]
+    pub(crate) fn current(tree: &Tree, node: NodeId) -> Self {
+        let raw = tree.node(node);
+        match (raw.parent(), raw.previous_sibling()) {
+            (None, _) => Position::Detached,
+            (Some(_), Some(prev)) => Position::After(NodeId::new(prev, tree.tree_id())),
+            (Some(parent), None) => Position::FirstChild(NodeId::new(parent, tree.tree_id())),
+        }
+    }
+}
+
+/// Change of the tree.
+///
+/// Journal records the changes reverting edits, and applying a change returns
+/// the change reverting it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Change {
+    /// Moves the node to the position.
+    Move {
+        /// Node.
+        node: NodeId,
+        /// Destination.
+        to: Position,
+    },
+    /// Removes the subtree, keeping the nodes in the arena.
+    Remove {
+        /// Subtree root.
+        node: NodeId,
+    },
+    /// Restores the removed subtree to the position.
+    Restore {
+        /// Subtree root.
+        node: NodeId,
+        /// Destination.
+        to: Position,
+    },
+    /// Reorders the children of the node.
+    Reorder {
+        /// Parent node.
+        parent: NodeId,
+        /// Children in the new order.
+        children: Vec<NodeId>,
+    },
+    /// Sets the node name.
+    Rename {
+        /// Node.
+        node: NodeId,
+        /// Node name symbol.
+        name_sym: NodeNameSym,
+    },
+    /// Replaces all attributes of the node.
+    SetAttributes {
+        /// Node.
+        node: NodeId,
+        /// Attributes.
+        attributes: Vec<AttributeValue>,
+    },
+    /// Replaces the attribute of the node.
+    SetAttribute {
+        /// Node.
+        node: NodeId,
+        /// Attribute index.
+        index: usize,
+        /// Attribute value.
+        value: AttributeValue,
+    },
+    /// Inserts the attribute to the node.
+    InsertAttribute {
+        /// Node.
+        node: NodeId,
+        /// Attribute index.
+        index: usize,
+        /// Attribute value.
+        value: AttributeValue,
+    },
+    /// Removes the attribute from the node.
+    RemoveAttribute {
+        /// Node.
+        node: NodeId,
+        /// Attribute index.
+        index: usize,
+    },
+}
+
+impl Change {
+    /// Creates a change moving the node back to the current position.
+    #[must_use]
+    pub(crate) fn move_back(tree: &Tree, node: NodeId) -> Self {
+        Change::Move {
+            node,
+            to: Position::current(tree, node),
+        }
+    }
+
+    /// Applies the change to the tree, and returns the change reverting it.
+    fn apply(self, tree: &mut Tree) -> Self {
+        match self {
+            Change::Move { node, to } => {
+                let reverse = Change::move_back(tree, node);
+                tree.unlink_from_child_name_index(node);
+                node.raw().detach(&mut tree.arena);
+                match to {
+                    Position::Detached => {}
+                    Position::FirstChild(parent) => {
+                        parent.raw().prepend(node.raw(), &mut tree.arena)
+                    }
+                    Position::After(prev) => prev.raw().insert_after(node.raw(), &mut tree.arena),
+                }
+                tree.link_to_child_name_index(node);
+                reverse
+            }
+            Change::Remove { node } => {
+                let to = Position::current(tree, node);
+                tree.mark_subtree_removed(node);
+                Change::Restore { node, to }
+            }
+            Change::Restore { node, to } => {
+                tree.unmark_subtree_removed(node);
+                Change::Move { node, to }.apply(tree);
+                Change::Remove { node }
+            }
+            Change::Reorder { parent, children } => {
+                let old = tree.reorder_children(parent, &children);
+                Change::Reorder {
+                    parent,
+                    children: old,
+                }
+            }
+            Change::Rename { node, name_sym } => {
+                tree.unlink_from_child_name_index(node);
+                let data = tree
+                    .arena
+                    .get_mut(node.raw())
+                    .expect("Should never fail: journaled nodes should exist")
+                    .get_mut();
+                let old = data.name_sym();
+                data.set_name_sym(name_sym);
+                tree.link_to_child_name_index(node);
+                Change::Rename {
+                    node,
+                    name_sym: old,
+                }
+            }
+            Change::SetAttributes {
+                node,
+                mut attributes,
+            } => {
+                std::mem::swap(tree.node_attributes_vec_mut(node), &mut attributes);
+                Change::SetAttributes { node, attributes }
+            }
+            Change::SetAttribute {
+                node,
+                index,
+                mut value,
+            } => {
+                std::mem::swap(&mut tree.node_attributes_mut(node)[index], &mut value);
+                Change::SetAttribute { node, index, value }
+            }
+            Change::InsertAttribute { node, index, value } => {
+                tree.node_attributes_vec_mut(node).insert(index, value);
+                Change::RemoveAttribute { node, index }
+            }
+            Change::RemoveAttribute { node, index } => {
+                let value = tree.node_attributes_vec_mut(node).remove(index);
+                Change::InsertAttribute { node, index, value }
+            }
+        }
+    }
+}
+
+/// Change journal.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Journal {
+    /// Changes reverting edits in the open transactions.
+    pending: Vec<Change>,
+    /// Start indices of the open transactions in `pending`.
+    transactions: Vec<usize>,
+    /// Undo stack.
+    undo: Vec<Vec<Change>>,
+    /// Redo stack.
+    redo: Vec<Vec<Change>>,
+}
+
+impl Journal {
+    /// Discards the redo history, and returns whether it was not empty.
+    fn clear_redo(&mut self) -> bool {
+        let cleared = !self.redo.is_empty();
+        self.redo.clear();
+        cleared
+    }
+}
+
+impl Tree {
+    /// Returns whether the change journal is enabled.
+    ///
+    /// See [`set_journal_enabled`][`Self::set_journal_enabled`] for details.
+    #[inline]
+    #[must_use]
+    pub fn is_journal_enabled(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Enables or disables the change journal.
+    ///
+    /// While the journal is enabled, edits made through the methods of `Tree`
+    /// and [`NodeHandleMut`][`crate::tree::v7400::NodeHandleMut`] are recorded
+    /// as changes reverting them, and can be undone by
+    /// [`undo`][`Self::undo`] and redone by [`redo`][`Self::redo`].
+    /// Edits are grouped by transactions (see
+    /// [`begin_transaction`][`Self::begin_transaction`]), and an edit outside
+    /// of transactions is a group by itself.
+    ///
+    /// Note that while the journal is enabled:
+    ///
+    /// * [`remove_subtree`][`Self::remove_subtree`] keeps the nodes in the
+    ///   arena until the removal leaves the history, so that they can be
+    ///   restored,
+    /// * undoing the creation of nodes removes them in the same way, and they
+    ///   are dropped from the arena when the undone creation leaves the
+    ///   history (for example, when new edits discard the redo history),
+    /// * [`get_attribute_mut`][`Self::get_attribute_mut`],
+    ///   [`take_attributes_vec`][`Self::take_attributes_vec`], and
+    ///   [`NodeHandleMut::attributes_mut`][`crate::tree::v7400::NodeHandleMut::attributes_mut`]
+    ///   clone the old values, and
+    /// * [`compact`][`Self::compact`] clears the history.
+    ///
+    /// Disabling the journal drops the history.
+    pub fn set_journal_enabled(&mut self, enabled: bool) {
+        if enabled != self.is_journal_enabled() {
+            self.journal = if enabled {
+                Some(Journal::default())
+            } else {
+                None
+            };
+            self.free_unrestorable_nodes();
+        }
+    }
+
+    /// Removes the nodes marked as removed from the arena, if no changes in
+    /// the journal can restore them.
+    fn free_unrestorable_nodes(&mut self) {
+        if self.removed_nodes.is_empty() {
+            return;
+        }
+        let restorable = self
+            .journal
+            .iter()
+            .flat_map(|journal| {
+                journal
+                    .pending
+                    .iter()
+                    .chain(journal.undo.iter().flatten())
+                    .chain(journal.redo.iter().flatten())
+            })
+            .filter_map(|change| match change {
+                Change::Restore { node, .. } => Some(*node),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        self.free_removed_nodes(&restorable);
+    }
+
+    /// Records the change created by the given function, if the journal is
+    /// enabled.
+    ///
+    /// The function should return the change reverting the edit to record.
+    pub(crate) fn record_with(&mut self, f: impl FnOnce(&mut Tree) -> Change) {
+        if self.journal.is_none() {
+            return;
+        }
+        let change = f(self);
+        let journal = self.journal.as_mut().expect("Should never fail");
+        if journal.transactions.is_empty() {
+            journal.undo.push(vec![change]);
+            if journal.clear_redo() {
+                self.free_unrestorable_nodes();
+            }
+        } else {
+            journal.pending.push(change);
+        }
+    }
+
+    /// Begins a transaction.
+    ///
+    /// Edits until the corresponding
+    /// [`commit_transaction`][`Self::commit_transaction`] are undone and
+    /// redone at once, or reverted by
+    /// [`rollback_transaction`][`Self::rollback_transaction`].
+    ///
+    /// Transactions can be nested. Edits in a nested transaction belong to
+    /// the outermost transaction when committed.
+    ///
+    /// Does nothing if the journal is disabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fbxcel::tree_v7400;
+    ///
+    /// let mut tree = tree_v7400! { Objects: {} };
+    /// tree.set_journal_enabled(true);
+    /// let objects = tree.root().first_child().expect("should exist").node_id();
+    ///
+    /// tree.begin_transaction();
+    /// let model = tree.append_new(objects, "Model");
+    /// tree.append_attribute(model, 42i64);
+    /// tree.commit_transaction();
+    ///
+    /// let edited = tree_v7400! { Objects: { Model: [42i64] {} } };
+    /// assert!(tree.strict_eq(&edited));
+    ///
+    /// assert!(tree.undo());
+    /// assert!(tree.strict_eq(&tree_v7400! { Objects: {} }));
+    /// assert!(tree.redo());
+    /// assert!(tree.strict_eq(&edited));
+    /// ```
+    pub fn begin_transaction(&mut self) {
+        if let Some(journal) = &mut self.journal {
+            journal.transactions.push(journal.pending.len());
+        }
+    }
+
+    /// Commits the innermost transaction.
+    ///
+    /// Does nothing if the journal is disabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the journal is enabled and there are no open transactions.
+    pub fn commit_transaction(&mut self) {
+        if let Some(journal) = &mut self.journal {
+            journal
+                .transactions
+                .pop()
+                .expect("There should be an open transaction to commit");
+            if journal.transactions.is_empty() && !journal.pending.is_empty() {
+                journal.undo.push(std::mem::take(&mut journal.pending));
+                if journal.clear_redo() {
+                    self.free_unrestorable_nodes();
+                }
+            }
+        }
+    }
+
+    /// Reverts the edits in the innermost transaction, and closes it.
+    ///
+    /// Does nothing if the journal is disabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the journal is enabled and there are no open transactions.
+    pub fn rollback_transaction(&mut self) {
+        if let Some(mut journal) = self.journal.take() {
+            let start = journal
+                .transactions
+                .pop()
+                .expect("There should be an open transaction to roll back");
+            let changes = journal.pending.split_off(start);
+            self.apply_changes(changes);
+            self.journal = Some(journal);
+            // Nodes created in the transaction are no longer restorable.
+            self.free_unrestorable_nodes();
+        }
+    }
+
+    /// Returns whether there are edits to undo.
+    #[inline]
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        self.journal
+            .as_ref()
+            .map_or(false, |journal| !journal.undo.is_empty())
+    }
+
+    /// Returns whether there are undone edits to redo.
+    #[inline]
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        self.journal
+            .as_ref()
+            .map_or(false, |journal| !journal.redo.is_empty())
+    }
+
+    /// Undoes the last group of edits.
+    ///
+    /// Returns `false` if there are no edits to undo.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are open transactions.
+    pub fn undo(&mut self) -> bool {
+        self.undo_or_redo(true)
+    }
+
+    /// Redoes the last undone group of edits.
+    ///
+    /// Returns `false` if there are no edits to redo.
+    /// Undone edits are discarded when new edits are recorded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are open transactions.
+    pub fn redo(&mut self) -> bool {
+        self.undo_or_redo(false)
+    }
+
+    /// Drops the undo and redo history.
+    ///
+    /// Open transactions are kept.
+    pub fn clear_history(&mut self) {
+        if let Some(journal) = &mut self.journal {
+            journal.undo.clear();
+            journal.redo.clear();
+        }
+        self.free_unrestorable_nodes();
+    }
+
+    /// Applies the group of changes on the top of the undo or redo stack, and
+    /// pushes the reverting changes to the other stack.
+    fn undo_or_redo(&mut self, undo: bool) -> bool {
+        let mut journal = match self.journal.take() {
+            Some(journal) => journal,
+            None => return false,
+        };
+        assert!(
+            journal.transactions.is_empty(),
+            "Undo and redo are not allowed while a transaction is open"
+        );
+        let (from, to) = if undo {
+            (&mut journal.undo, &mut journal.redo)
+        } else {
+            (&mut journal.redo, &mut journal.undo)
+        };
+        let changes = from.pop();
+        let applied = changes.is_some();
+        if let Some(changes) = changes {
+            to.push(self.apply_changes(changes));
+        }
+        self.journal = Some(journal);
+        applied
+    }
+
+    /// Applies the changes in the reverse order, and returns the changes
+    /// reverting them.
+    ///
+    /// The returned changes should also be applied in the reverse order.
+    fn apply_changes(&mut self, changes: Vec<Change>) -> Vec<Change> {
+        let is_group = changes.len() > 1;
+        let apply = |tree: &mut Self| {
+            changes
+                .into_iter()
+                .rev()
+                .map(|change| change.apply(tree))
+                .collect()
+        };
+        if is_group {
+            self.with_child_name_index_deferred(apply)
+        } else {
+            apply(self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        low::v7400::AttributeValue,
+        tree::v7400::{EditError, Tree},
+        tree_v7400,
+    };
+
+    #[test]
+    fn undo_and_redo() {
+        let original = tree_v7400! {
+            Node0: [1i32, 2i32] {
+                Node0_0: {},
+                Node0_1: ["a"] {},
+            },
+            Node1: {},
+        };
+        let mut tree = original.clone();
+        tree.set_journal_enabled(true);
+        let node0 = tree.root().first_child().unwrap().node_id();
+        let node0_0 = node0.to_handle(&tree).first_child().unwrap().node_id();
+        let node0_1 = node0.to_handle(&tree).last_child().unwrap().node_id();
+        let node1 = tree.root().last_child().unwrap().node_id();
+
+        tree.append(node0_0, node1);
+        tree.detach(node0_1);
+        *tree.get_attribute_mut(node0, 0).unwrap() = AttributeValue::I32(3);
+        tree.append_attribute(node1, "b");
+        tree.remove_subtree(node0);
+        let taken = tree.take_attributes_vec(node1);
+        tree.set_attributes_vec(node0_0, taken);
+        {
+            let mut node = node0_0.to_handle_mut(&mut tree);
+            node.rename("Renamed");
+            node.insert_attribute(0, true);
+            node.remove_attribute(1);
+            node.append_new("Node_new").append_attribute(4i64);
+        }
+        let edited = tree.clone();
+        assert!(tree.strict_eq(&tree_v7400! {
+            Node1: {
+                Renamed: [true] {
+                    Node_new: [4i64] {},
+                },
+            },
+        }));
+
+        let mut steps = 0;
+        while tree.undo() {
+            steps += 1;
+        }
+        assert_eq!(steps, 12);
+        assert!(tree.strict_eq(&original));
+        assert!(!tree.can_undo());
+
+        while tree.redo() {}
+        assert!(tree.strict_eq(&edited));
+        assert!(!tree.can_redo());
+
+        // New edits discard the redo history.
+        assert!(tree.undo());
+        tree.append_new(node1, "Node2");
+        assert!(!tree.can_redo());
+    }
+
+    #[test]
+    fn removed_subtree() {
+        let original = tree_v7400! {
+            Node0: { Node0_0: {} },
+            Node1: {},
+        };
+        let mut tree = original.clone();
+        tree.set_journal_enabled(true);
+        let node0 = tree.root().first_child().unwrap().node_id();
+        let node0_0 = node0.to_handle(&tree).first_child().unwrap().node_id();
+        let removed_slots = |tree: &Tree| tree.arena.iter().filter(|v| v.is_removed()).count();
+
+        tree.remove_subtree(node0);
+        assert!(!tree.contains_node(node0));
+        assert!(!tree.contains_node(node0_0));
+        assert_eq!(
+            tree.try_detach(node0_0),
+            Err(EditError::RemovedNode(node0_0))
+        );
+        assert_eq!(removed_slots(&tree), 0);
+
+        assert!(tree.undo());
+        assert!(tree.strict_eq(&original));
+        assert!(tree.contains_node(node0_0));
+        assert!(tree.redo());
+        assert!(!tree.contains_node(node0));
+
+        // Removals in open transactions can still be rolled back.
+        tree.begin_transaction();
+        let node1 = tree.root().first_child().unwrap().node_id();
+        tree.remove_subtree(node1);
+        tree.clear_history();
+        assert_eq!(removed_slots(&tree), 2);
+        tree.rollback_transaction();
+        assert!(tree.contains_node(node1));
+
+        tree.set_journal_enabled(false);
+        assert!(tree.removed_nodes.is_empty());
+        assert_eq!(removed_slots(&tree), 2);
+    }
+
+    #[test]
+    fn undone_creation() {
+        let mut tree = tree_v7400! { Node0: {} };
+        tree.set_journal_enabled(true);
+        let node0 = tree.root().first_child().unwrap().node_id();
+        let live_nodes = |tree: &Tree| tree.memory_usage().total().nodes;
+        assert_eq!(live_nodes(&tree), 2);
+
+        tree.begin_transaction();
+        let new = tree.append_new(node0, "Node0_0");
+        tree.append_new(new, "Node0_0_0");
+        tree.commit_transaction();
+        assert_eq!(live_nodes(&tree), 4);
+        assert!(tree.undo());
+        assert!(!tree.contains_node(new));
+        assert!(tree.redo());
+        assert!(tree.contains_node(new));
+
+        // Undone creations are freed when new edits discard them.
+        assert!(tree.undo());
+        tree.append_new(node0, "Node0_1");
+        assert!(tree.removed_nodes.is_empty());
+        assert_eq!(live_nodes(&tree), 3);
+
+        // Nodes created in rolled back transactions are freed.
+        tree.begin_transaction();
+        tree.clone_subtree(node0, node0);
+        assert_eq!(live_nodes(&tree), 5);
+        tree.rollback_transaction();
+        assert!(tree.removed_nodes.is_empty());
+        assert_eq!(live_nodes(&tree), 3);
+
+        // Undone creations are freed when the history is dropped.
+        tree.append_new(node0, "Node0_2");
+        assert!(tree.undo());
+        tree.clear_history();
+        assert!(tree.removed_nodes.is_empty());
+        assert_eq!(live_nodes(&tree), 3);
+        tree.append_new(node0, "Node0_2");
+        assert!(tree.undo());
+        tree.set_journal_enabled(false);
+        assert!(tree.removed_nodes.is_empty());
+        assert_eq!(live_nodes(&tree), 3);
+    }
+
+    #[test]
+    fn transactions() {
+        let original = tree_v7400! {
+            Node0: { Node0_0: {} },
+            Node1: [1i32] {},
+        };
+        let mut tree = original.clone();
+        tree.set_journal_enabled(true);
+        let node0 = tree.root().first_child().unwrap().node_id();
+        let node1 = tree.root().last_child().unwrap().node_id();
+
+        tree.begin_transaction();
+        tree.append_new(node0, "Node0_1");
+        tree.begin_transaction();
+        tree.append_attribute(node1, 2i32);
+        tree.rollback_transaction();
+        tree.prepend(node1, node0);
+        tree.commit_transaction();
+        let edited = tree_v7400! {
+            Node0: {
+                Node1: [1i32] {},
+                Node0_0: {},
+                Node0_1: {},
+            },
+        };
+        assert!(tree.strict_eq(&edited));
+
+        assert!(tree.undo());
+        assert!(!tree.can_undo());
+        assert!(tree.strict_eq(&original));
+        assert!(tree.redo());
+        assert!(tree.strict_eq(&edited));
+
+        // Bulk edits are recorded as a group.
+        tree.canonicalize(crate::tree::v7400::CanonicalizeOptions::new().sort_children("Node0", 0));
+        tree.retain(|node| node.name() != "Node0_0");
+        tree.map_attributes(|_, _, _| Some(AttributeValue::I64(0)));
+        assert!(tree.undo());
+        assert!(tree.undo());
+        assert!(tree.undo());
+        assert!(tree.strict_eq(&edited));
+
+        // The history is dropped when the journal is disabled.
+        tree.set_journal_enabled(false);
+        assert!(!tree.can_redo());
+        tree.begin_transaction();
+        tree.commit_transaction();
+    }
+
+    #[test]
+    #[should_panic]
+    fn undo_in_transaction() {
+        let mut tree = tree_v7400! {};
+        tree.set_journal_enabled(true);
+        tree.begin_transaction();
+        tree.undo();
+    }
+}
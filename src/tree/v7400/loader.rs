@@ -12,7 +12,7 @@ use crate::{
         v7400::{attribute::loaders::DirectLoader, Event, Parser, StartNode},
         Error as ParserError,
     },
-    tree::v7400::{LoadError, NodeData, NodeId, NodeNameSym, Tree},
+    tree::v7400::{AttributePool, LoadError, NodeData, NodeNameSym, Tree},
 };
 
 /// FBX data tree loader.
@@ -23,7 +23,15 @@ pub struct Loader {
     /// Node name interner.
     node_names: StringInterner<DefaultBackend<NodeNameSym>>,
     /// (Implicit) root node ID.
-    root_id: NodeId,
+    ///
+    /// The identity of the tree is not decided here but when the tree is
+    /// created, so that trees loaded by clones of a loader have different
+    /// identities.
+    root_id: indextree::NodeId,
+    /// Attribute pool used in the compact storage mode.
+    attribute_pool: AttributePool,
+    /// Whether to load the tree in the compact storage mode.
+    compact_storage: bool,
 }
 
 impl Loader {
@@ -34,6 +42,18 @@ impl Loader {
         Self::default()
     }
 
+    /// Sets whether to load the tree in the compact storage mode.
+    ///
+    /// The loaded tree is equivalent to the one converted by
+    /// [`Tree::set_compact_storage`] after loading, but attributes are stored
+    /// in the compact form from the beginning.
+    #[inline]
+    #[must_use]
+    pub fn compact_storage(mut self, enabled: bool) -> Self {
+        self.compact_storage = enabled;
+        self
+    }
+
     /// Loads a tree from the given parser, and returns the tree and FBX footer.
     ///
     /// The given parser should be brand-new, i.e. it should not have emited any
@@ -94,16 +114,87 @@ impl Loader {
         );
 
         debug!("Successfully loaded FBX data tree");
-        let tree = Tree::new(self.arena, self.node_names, self.root_id);
+        self.attribute_pool.shrink_to_fit();
+        let tree = Tree::new(
+            self.arena,
+            self.node_names,
+            self.root_id,
+            self.attribute_pool,
+            self.compact_storage,
+        );
         Ok((tree, footer))
     }
 
+    /// Loads a subtree from the given node start event, and returns the tree
+    /// containing the node as the only top-level node.
+    ///
+    /// The node attributes should not have been read, and this is ensured by
+    /// [`StartNode::attributes()`] consuming the event.
+    /// On success, the parser is positioned right after the end of the node,
+    /// so the caller can continue reading events from the parser.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use fbxcel::pull_parser::v7400::{Event, Parser};
+    /// use fbxcel::tree::v7400::{Loader, Tree};
+    ///
+    /// # fn f<R: std::io::Read>(parser: &mut Parser<R>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut geometries: Vec<Tree> = Vec::new();
+    /// loop {
+    ///     match parser.next_event()? {
+    ///         Event::StartNode(start) if start.name() == "Geometry" => {
+    ///             geometries.push(Loader::new().load_subtree(start)?);
+    ///         }
+    ///         Event::EndFbx(_) => break,
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_subtree<R: io::Read>(mut self, start: StartNode<'_, R>) -> Result<Tree, LoadError> {
+        debug!("Loading FBX data subtree from a parser");
+
+        let parser = start.into_parser();
+        let subtree_root = self.add_node(self.root_id, StartNode::new(&mut *parser))?;
+        let mut open_nodes = vec![subtree_root];
+        while let Some(&parent) = open_nodes.last() {
+            match parser.next_event()? {
+                Event::StartNode(start) => {
+                    trace!("Got `Event::StartNode(name={:?})`", start.name());
+                    let current = self.add_node(parent, start)?;
+                    open_nodes.push(current);
+                }
+                Event::EndNode => {
+                    trace!("Got `Event::EndNode`");
+                    open_nodes.pop();
+                }
+                Event::EndFbx(_) => {
+                    // This should not happen unless the parser is in a bad state.
+                    error!("Got `Event::EndFbx(_)` before the end of the subtree");
+                    return Err(LoadError::BadParser);
+                }
+            }
+        }
+
+        debug!("Successfully loaded FBX data subtree");
+        self.attribute_pool.shrink_to_fit();
+        Ok(Tree::new(
+            self.arena,
+            self.node_names,
+            self.root_id,
+            self.attribute_pool,
+            self.compact_storage,
+        ))
+    }
+
     /// Creates and adds a new node to the tree.
     fn add_node<R: io::Read>(
         &mut self,
-        parent: NodeId,
+        parent: indextree::NodeId,
         start: StartNode<'_, R>,
-    ) -> Result<NodeId, LoadError> {
+    ) -> Result<indextree::NodeId, LoadError> {
         trace!(
             "Adding a new child name={:?} to the parent {:?}",
             start.name(),
@@ -117,12 +208,17 @@ impl Loader {
                 .attributes()
                 .into_iter(std::iter::repeat(DirectLoader))
                 .collect::<Result<Vec<_>, _>>()?;
+            let data = if self.compact_storage {
+                NodeData::with_pooled_attributes(name_sym, attributes, &mut self.attribute_pool)
+            } else {
+                NodeData::new(name_sym, attributes)
+            };
 
-            NodeId::new(self.arena.new_node(NodeData::new(name_sym, attributes)))
+            self.arena.new_node(data)
         };
 
         // Set the parent.
-        parent.raw().append(current.raw(), &mut self.arena);
+        parent.append(current, &mut self.arena);
 
         trace!(
             "Successfully added a new child {:?} to the parent {:?}",
@@ -141,12 +237,14 @@ impl Default for Loader {
         let root_id = {
             // Use empty string as dummy node name.
             let empty_sym = node_names.get_or_intern("");
-            NodeId::new(arena.new_node(NodeData::new(empty_sym, Vec::new())))
+            arena.new_node(NodeData::new(empty_sym, Vec::new()))
         };
         Self {
             arena,
             node_names,
             root_id,
+            attribute_pool: AttributePool::default(),
+            compact_storage: false,
         }
     }
 }
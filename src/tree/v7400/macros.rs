@@ -31,6 +31,66 @@
 ///     Node3: ["hello", "world", 1.234f32, &b"BINARY"[..]] {}
 /// };
 /// ```
+///
+/// Node names can be string literals, or expressions in parens.
+/// The expressions should implement `AsRef<str>`.
+///
+/// ```
+/// # use fbxcel::tree_v7400;
+/// let suffix = "Computed";
+/// let tree = tree_v7400! {
+///     "Node with spaces": {}
+///     (format!("Node{}", suffix)): [42i32] {}
+/// };
+/// ```
+///
+/// `for` loops and `if` conditionals generate children of the current node.
+/// Note that the iterator and the condition should be enclosed by parens.
+///
+/// ```
+/// # use fbxcel::tree_v7400;
+/// let include_extra = true;
+/// let tree = tree_v7400! {
+///     Objects: {
+///         for (i, name) in (["Cube", "Sphere"].iter().enumerate()) {
+///             Model: [i as i64, *name] {}
+///         }
+///         if (include_extra) {
+///             Extra: {}
+///         } else {
+///             NoExtra: {}
+///         }
+///     }
+/// };
+/// ```
+///
+/// `P` nodes in `Properties70` can be written in a shorthand form similar to
+/// ASCII FBX, terminated by a semicolon.
+/// The property name should be a literal, or an expression in parens.
+///
+/// ```
+/// # use fbxcel::tree_v7400;
+/// let tree = tree_v7400! {
+///     Properties70: {
+///         P: "UpAxis", "int", "Integer", "", 1i32;
+///         // Same as above.
+///         P: ["UpAxis", "int", "Integer", "", 1i32] {}
+///     }
+/// };
+/// ```
+///
+/// Array attributes in brackets can have encoding annotations, which are
+/// accepted for compatibility with [`write_v7400_binary!`] and ignored,
+/// since the tree does not keep array encodings.
+///
+/// ```
+/// # use fbxcel::tree_v7400;
+/// let tree = tree_v7400! {
+///     Vertices: [vec![0.0f64; 9] => Zlib] {}
+/// };
+/// ```
+///
+/// [`write_v7400_binary!`]: macro.write_v7400_binary.html
 #[cfg_attr(docsrs, doc(cfg(feature = "tree")))]
 #[macro_export]
 macro_rules! tree_v7400 {
@@ -40,46 +100,118 @@ macro_rules! tree_v7400 {
     };
 
     (@__node, $tree:ident, $parent:ident,
-        $name:ident: {
+        for $pat:pat in ($iter:expr) {
+            $($body:tt)*
+        }
+        $($rest:tt)*
+    ) => {{
+        for $pat in $iter {
+            tree_v7400! { @__node, $tree, $parent, $($body)* }
+        }
+        tree_v7400! { @__node, $tree, $parent, $($rest)* }
+    }};
+    (@__node, $tree:ident, $parent:ident,
+        if ($cond:expr) {
+            $($body:tt)*
+        } else {
+            $($else_body:tt)*
+        }
+        $($rest:tt)*
+    ) => {{
+        if $cond {
+            tree_v7400! { @__node, $tree, $parent, $($body)* }
+        } else {
+            tree_v7400! { @__node, $tree, $parent, $($else_body)* }
+        }
+        tree_v7400! { @__node, $tree, $parent, $($rest)* }
+    }};
+    (@__node, $tree:ident, $parent:ident,
+        if ($cond:expr) {
+            $($body:tt)*
+        }
+        $($rest:tt)*
+    ) => {{
+        if $cond {
+            tree_v7400! { @__node, $tree, $parent, $($body)* }
+        }
+        tree_v7400! { @__node, $tree, $parent, $($rest)* }
+    }};
+
+    (@__node, $tree:ident, $parent:ident,
+        P: $name:literal $(, $attr:expr)* $(,)?;
+        $($rest:tt)*
+    ) => {
+        tree_v7400! { @__named, $tree, $parent, ("P"), [$name $(, $attr)*] {} $($rest)* }
+    };
+    (@__node, $tree:ident, $parent:ident,
+        P: ($name:expr) $(, $attr:expr)* $(,)?;
+        $($rest:tt)*
+    ) => {
+        tree_v7400! { @__named, $tree, $parent, ("P"), [$name $(, $attr)*] {} $($rest)* }
+    };
+
+    (@__node, $tree:ident, $parent:ident, $name:ident: $($rest:tt)*) => {
+        tree_v7400! { @__named, $tree, $parent, (stringify!($name)), $($rest)* }
+    };
+    (@__node, $tree:ident, $parent:ident, $name:literal: $($rest:tt)*) => {
+        tree_v7400! { @__named, $tree, $parent, ($name), $($rest)* }
+    };
+    (@__node, $tree:ident, $parent:ident, ($name:expr): $($rest:tt)*) => {
+        tree_v7400! { @__named, $tree, $parent, ($name), $($rest)* }
+    };
+
+    (@__named, $tree:ident, $parent:ident, ($name:expr),
+        {
             $($subtree:tt)*
         }
         $($rest:tt)*
     ) => {{
         {
-            let _node = $tree.append_new($parent, stringify!($name));
+            let _node = $tree.append_new($parent, ::std::convert::AsRef::<str>::as_ref(&$name));
             tree_v7400! { @__node, $tree, _node, $($subtree)* }
         }
         tree_v7400! { @__node, $tree, $parent, $($rest)* }
     }};
-    (@__node, $tree:ident, $parent:ident,
-        $name:ident: [$($attr:expr),* $(,)?] {
+    (@__named, $tree:ident, $parent:ident, ($name:expr),
+        [$($attr:expr $(=> $encoding:expr)?),* $(,)?] {
             $($subtree:tt)*
         }
         $($rest:tt)*
     ) => {{
         {
-            let _node = $tree.append_new($parent, stringify!($name));
+            let _node = $tree.append_new($parent, ::std::convert::AsRef::<str>::as_ref(&$name));
             $(
                 $tree.append_attribute(_node, $attr);
+                $(
+                    // The tree does not keep encodings, but check the type.
+                    let _ = tree_v7400!(@__encoding, $encoding);
+                )?
             )*
             tree_v7400! { @__node, $tree, _node, $($subtree)* }
         }
         tree_v7400! { @__node, $tree, $parent, $($rest)* }
     }};
-    (@__node, $tree:ident, $parent:ident,
-        $name:ident: ($attrs:expr) {
+    (@__named, $tree:ident, $parent:ident, ($name:expr),
+        ($attrs:expr) {
             $($subtree:tt)*
         }
         $($rest:tt)*
     ) => {{
         {
-            let _node = $tree.append_new($parent, stringify!($name));
+            let _node = $tree.append_new($parent, ::std::convert::AsRef::<str>::as_ref(&$name));
             $attrs.into_iter().for_each(|attr: $crate::low::v7400::AttributeValue| $tree.append_attribute(_node, attr));
             tree_v7400! { @__node, $tree, _node, $($subtree)* }
         }
         tree_v7400! { @__node, $tree, $parent, $($rest)* }
     }};
 
+    (@__encoding, $encoding:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::low::v7400::ArrayAttributeEncoding::*;
+        let encoding: $crate::low::v7400::ArrayAttributeEncoding = $encoding;
+        encoding
+    }};
+
     ($($rest:tt)*) => {
         {
             #[allow(unused_mut)]
@@ -93,7 +225,7 @@ macro_rules! tree_v7400 {
 
 #[cfg(test)]
 mod tests {
-    use crate::tree::v7400::Tree;
+    use crate::{low::v7400::AttributeValue, tree::v7400::Tree};
 
     #[test]
     fn empty_trees_eq() {
@@ -160,8 +292,8 @@ mod tests {
                 Node2: [true, 42i16, 42i32, 42i64, 1.414f32, 1.234f64] {
                     Node2_0: (vec![vec![true, false].into(), vec![0i32, 42i32].into()]) {},
                     Node2_1: [
-                        vec![std::f32::NAN, std::f32::INFINITY],
-                        vec![std::f64::NAN, std::f64::INFINITY]
+                        vec![f32::NAN, f32::INFINITY],
+                        vec![f64::NAN, f64::INFINITY]
                     ] {},
                 },
             }
@@ -210,4 +342,61 @@ mod tests {
 
         assert!(tree_manual.strict_eq(&tree_macro));
     }
+
+    #[test]
+    fn extended_syntax() {
+        let tree_manual = {
+            let mut tree = Tree::default();
+            let root = tree.root().node_id();
+            tree.append_new(root, "Node with spaces");
+            let objects = tree.append_new(root, "Objects");
+            for i in 0..3i64 {
+                let model = tree.append_new(objects, &format!("Model{}", i));
+                tree.append_attribute(model, i);
+                if i % 2 == 0 {
+                    tree.append_new(model, "Even");
+                } else {
+                    tree.append_new(model, "Odd");
+                }
+            }
+            let props = tree.append_new(root, "Properties70");
+            for (name, ty, label, value) in [
+                ("UpAxis", "int", "Integer", AttributeValue::I32(1)),
+                ("Scale", "double", "Number", AttributeValue::F64(2.0)),
+            ] {
+                let p = tree.append_new(props, "P");
+                tree.set_attributes_vec(
+                    p,
+                    vec![name.into(), ty.into(), label.into(), "".into(), value],
+                );
+            }
+            let vertices = tree.append_new(root, "Vertices");
+            tree.append_attribute(vertices, vec![0.0f64; 3]);
+            tree.append_attribute(vertices, vec![1i32, 2]);
+            tree
+        };
+
+        let prop_name = "Scale";
+        let tree_macro = tree_v7400! {
+            "Node with spaces": {},
+            Objects: {
+                for i in (0..3i64) {
+                    (format!("Model{}", i)): [i] {
+                        if (i % 2 == 0) {
+                            Even: {}
+                        } else {
+                            Odd: {}
+                        }
+                    }
+                }
+            },
+            Properties70: {
+                P: "UpAxis", "int", "Integer", "", 1i32;
+                P: (prop_name), "double", "Number", "", 2.0f64,;
+            },
+            Vertices: [vec![0.0f64; 3] => Zlib, vec![1i32, 2]] {},
+        };
+
+        assert!(tree_manual.strict_eq(&tree_macro));
+    }
 }
@@ -0,0 +1,462 @@
+//! Memory usage report.
+
+use std::{collections::BTreeMap, fmt, mem};
+
+use crate::{
+    low::v7400::AttributeValue,
+    tree::v7400::{node::StringSym, NodeData, Tree},
+};
+
+/// Estimated memory usage of nodes.
+///
+/// Sizes are estimated from lengths and capacities of the buffers, and do not
+/// include the overhead of the memory allocator.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct NodeMemoryUsage {
+    /// Number of nodes.
+    pub nodes: usize,
+    /// Size of the node structures in bytes.
+    pub node_bytes: usize,
+    /// Number of attributes.
+    pub attributes: usize,
+    /// Size of the attributes in bytes, including heap buffers of arrays,
+    /// binaries, and strings.
+    ///
+    /// Interned string values are counted as the symbols referring to the
+    /// string table, and attributes cached on access are also counted.
+    pub attribute_bytes: usize,
+}
+
+impl NodeMemoryUsage {
+    /// Returns the total size in bytes.
+    #[inline]
+    #[must_use]
+    pub fn total_bytes(&self) -> usize {
+        self.node_bytes + self.attribute_bytes
+    }
+
+    /// Adds the other usage to this.
+    fn add(&mut self, other: &Self) {
+        self.nodes += other.nodes;
+        self.node_bytes += other.node_bytes;
+        self.attributes += other.attributes;
+        self.attribute_bytes += other.attribute_bytes;
+    }
+}
+
+/// Estimated memory usage of a tree.
+///
+/// Nodes detached from the tree are also counted, since they are still in
+/// the tree storage.
+///
+/// The `Display` implementation prints a table of usages by node names,
+/// sorted by the total size in descending order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Usage of all nodes.
+    total: NodeMemoryUsage,
+    /// Usage by node names.
+    by_name: BTreeMap<String, NodeMemoryUsage>,
+    /// Size of the node name interner.
+    node_names_bytes: usize,
+    /// Size of the string table of the attribute pool.
+    attribute_strings_bytes: usize,
+    /// Size of the unused storage.
+    unused_bytes: usize,
+}
+
+impl MemoryUsage {
+    /// Returns the usage of all nodes.
+    #[inline]
+    #[must_use]
+    pub fn total(&self) -> &NodeMemoryUsage {
+        &self.total
+    }
+
+    /// Returns the usages by node names.
+    #[inline]
+    #[must_use]
+    pub fn by_name(&self) -> &BTreeMap<String, NodeMemoryUsage> {
+        &self.by_name
+    }
+
+    /// Returns the size of node names in bytes.
+    #[inline]
+    #[must_use]
+    pub fn node_names_bytes(&self) -> usize {
+        self.node_names_bytes
+    }
+
+    /// Returns the size of the string values interned in the compact storage
+    /// mode in bytes.
+    ///
+    /// See [`Tree::set_compact_storage`] for details.
+    #[inline]
+    #[must_use]
+    pub fn attribute_strings_bytes(&self) -> usize {
+        self.attribute_strings_bytes
+    }
+
+    /// Returns the size of the unused storage in bytes.
+    ///
+    /// This includes slots of removed nodes, and unused slots and capacity of
+    /// the attribute pool.
+    /// [`Tree::compact`] releases them.
+    #[inline]
+    #[must_use]
+    pub fn unused_bytes(&self) -> usize {
+        self.unused_bytes
+    }
+
+    /// Returns the total size in bytes.
+    #[inline]
+    #[must_use]
+    pub fn total_bytes(&self) -> usize {
+        self.total.total_bytes()
+            + self.node_names_bytes
+            + self.attribute_strings_bytes
+            + self.unused_bytes
+    }
+}
+
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = self.by_name.iter().collect::<Vec<_>>();
+        names.sort_by(|(l_name, l), (r_name, r)| {
+            r.total_bytes()
+                .cmp(&l.total_bytes())
+                .then_with(|| l_name.cmp(r_name))
+        });
+
+        writeln!(
+            f,
+            "{:<32} {:>10} {:>12} {:>14}",
+            "node name", "nodes", "attributes", "bytes"
+        )?;
+        for (name, usage) in names {
+            writeln!(
+                f,
+                "{:<32} {:>10} {:>12} {:>14}",
+                name,
+                usage.nodes,
+                usage.attributes,
+                usage.total_bytes()
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<32} {:>10} {:>12} {:>14}",
+            "(total)",
+            self.total.nodes,
+            self.total.attributes,
+            self.total.total_bytes()
+        )?;
+        writeln!(f, "node names: {} bytes", self.node_names_bytes)?;
+        writeln!(
+            f,
+            "attribute strings: {} bytes",
+            self.attribute_strings_bytes
+        )?;
+        writeln!(f, "unused: {} bytes", self.unused_bytes)?;
+        write!(f, "total: {} bytes", self.total_bytes())
+    }
+}
+
+/// Returns the size of the heap buffer of the attribute value.
+fn heap_bytes(v: &AttributeValue) -> usize {
+    /// Returns the size of the buffer of the `Vec`.
+    fn vec_bytes<T>(v: &Vec<T>) -> usize {
+        v.capacity() * mem::size_of::<T>()
+    }
+
+    match v {
+        AttributeValue::ArrBool(v) => vec_bytes(v),
+        AttributeValue::ArrI32(v) => vec_bytes(v),
+        AttributeValue::ArrI64(v) => vec_bytes(v),
+        AttributeValue::ArrF32(v) => vec_bytes(v),
+        AttributeValue::ArrF64(v) => vec_bytes(v),
+        AttributeValue::Binary(v) => vec_bytes(v),
+        AttributeValue::String(v) => v.capacity(),
+        _ => 0,
+    }
+}
+
+impl Tree {
+    /// Returns the estimated memory usage of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fbxcel::tree_v7400;
+    ///
+    /// let mut tree = tree_v7400! {
+    ///     Properties70: {
+    ///         P: ["Lcl Translation", "Lcl Translation", "", "A", 0.0f64, 0.0f64, 0.0f64] {},
+    ///         P: ["Lcl Rotation", "Lcl Rotation", "", "A", 0.0f64, 0.0f64, 0.0f64] {},
+    ///     },
+    /// };
+    ///
+    /// let usage = tree.memory_usage();
+    /// assert_eq!(usage.by_name()["P"].nodes, 2);
+    /// assert_eq!(usage.by_name()["P"].attributes, 14);
+    ///
+    /// tree.set_compact_storage(true);
+    /// assert!(tree.memory_usage().total_bytes() < usage.total_bytes());
+    /// println!("{}", tree.memory_usage());
+    /// ```
+    #[must_use]
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let node_size = mem::size_of::<indextree::Node<NodeData>>();
+        let attr_size = mem::size_of::<AttributeValue>();
+        let sym_size = mem::size_of::<StringSym>();
+
+        let mut by_name: BTreeMap<String, NodeMemoryUsage> = BTreeMap::new();
+        let mut removed_nodes = 0;
+        let mut pooled_attributes = 0;
+        let mut interned_strings = 0;
+        for node in self.arena.iter() {
+            if node.is_removed() {
+                removed_nodes += 1;
+                continue;
+            }
+            let data = node.get();
+            let (attributes, strings, cache) = data.stored_attributes(&self.attribute_pool);
+            let (pooled, _, owned_capacity) = data.attributes_storage();
+            pooled_attributes += pooled;
+            interned_strings += strings;
+            let cache_bytes = cache.map_or(0, |cache| {
+                mem::size_of_val(cache) + cache.iter().map(heap_bytes).sum::<usize>()
+            });
+            let usage = NodeMemoryUsage {
+                nodes: 1,
+                node_bytes: node_size,
+                attributes: attributes.len(),
+                attribute_bytes: (pooled + owned_capacity) * attr_size
+                    + strings * sym_size
+                    + attributes.iter().map(heap_bytes).sum::<usize>()
+                    + cache_bytes,
+            };
+            let name = self.resolve_node_name(data.name_sym());
+            match by_name.get_mut(name) {
+                Some(entry) => entry.add(&usage),
+                None => {
+                    by_name.insert(name.to_owned(), usage);
+                }
+            }
+        }
+
+        let mut total = NodeMemoryUsage::default();
+        by_name.values().for_each(|usage| total.add(usage));
+        let node_names_bytes = self
+            .node_names
+            .into_iter()
+            .map(|(_, name)| name.len())
+            .sum();
+        let (values_capacity, strings_capacity) = self.attribute_pool.capacities();
+        let unused_bytes = removed_nodes * node_size
+            + (values_capacity - pooled_attributes) * attr_size
+            + (strings_capacity - interned_strings) * sym_size;
+
+        MemoryUsage {
+            total,
+            by_name,
+            node_names_bytes,
+            attribute_strings_bytes: self.attribute_pool.strings_bytes(),
+            unused_bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use crate::{low::v7400::AttributeValue, tree_v7400};
+
+    #[test]
+    fn compact_storage() {
+        let mut tree = tree_v7400! {
+            A: [1i32, "foo", vec![0.0f32; 4]] {
+                B: [2i32] {},
+                B: [3i32] {},
+            },
+        };
+        let original = tree.clone();
+        let usage = tree.memory_usage();
+        assert_eq!(usage.total().nodes, 4);
+        assert_eq!(usage.total().attributes, 5);
+        assert_eq!(usage.by_name()["B"].nodes, 2);
+        assert_eq!(usage.unused_bytes(), 0);
+
+        tree.set_compact_storage(true);
+        assert!(tree.is_compact_storage());
+        assert!(tree.strict_eq(&original));
+        let compact = tree.memory_usage();
+        assert_eq!(compact.total().attributes, 5);
+        assert!(compact.total().attribute_bytes < usage.total().attribute_bytes);
+
+        // Editing moves attributes out of the pool.
+        let b = tree.root().first_child().unwrap().first_child().unwrap();
+        let b = b.node_id();
+        tree.append_attribute(b, 4i64);
+        assert!(tree.memory_usage().unused_bytes() > 0);
+        tree.set_compact_storage(true);
+        assert_eq!(tree.memory_usage().unused_bytes(), 0);
+
+        tree.set_compact_storage(false);
+        assert!(!tree.is_compact_storage());
+        tree.set_attributes_vec(b, vec![2i32.into()]);
+        assert!(tree.strict_eq(&original));
+
+        let map = {
+            tree.set_compact_storage(true);
+            tree.remove_subtree(b);
+            tree.compact()
+        };
+        assert!(tree.is_compact_storage());
+        assert_eq!(map.len(), 3);
+        assert_eq!(tree.memory_usage().unused_bytes(), 0);
+        assert_eq!(tree.memory_usage().by_name()["A"].attributes, 3);
+    }
+
+    #[test]
+    fn interned_strings() {
+        let mut tree = tree_v7400! {
+            Properties70: {
+                for i in (0..100) {
+                    P: (format!("Prop{}", i)), "KString", "", "", "value";
+                }
+            },
+        };
+        let original = tree.clone();
+        let usage = tree.memory_usage();
+
+        tree.set_compact_storage(true);
+        let compact = tree.memory_usage();
+        assert_eq!(
+            compact.by_name()["P"].attributes,
+            usage.by_name()["P"].attributes
+        );
+        // Each distinct string is stored once.
+        let props_bytes = (0..100).map(|i| format!("Prop{}", i).len()).sum::<usize>();
+        assert_eq!(
+            compact.attribute_strings_bytes(),
+            props_bytes + "KString".len() + "value".len()
+        );
+        // Nodes refer to the string table instead of having string buffers.
+        let string_buffers = props_bytes + 100 * ("KString".len() + "value".len());
+        assert!(
+            compact.by_name()["P"].attribute_bytes + string_buffers
+                <= usage.by_name()["P"].attribute_bytes
+        );
+
+        // Attributes with string values are cached on access.
+        let p0 = tree.root().first_child().unwrap().first_child().unwrap();
+        assert_eq!(p0.attributes()[0], AttributeValue::String("Prop0".into()));
+        let p0 = p0.node_id();
+        let accessed = tree.memory_usage();
+        assert!(accessed.by_name()["P"].attribute_bytes > compact.by_name()["P"].attribute_bytes);
+        assert!(tree.strict_eq(&original));
+
+        // Editing puts string values back into the pool.
+        *tree.get_attribute_mut(p0, 1).unwrap() = "KDouble".into();
+        assert_eq!(
+            tree.root()
+                .first_child()
+                .unwrap()
+                .first_child()
+                .unwrap()
+                .attributes()[..2],
+            ["Prop0".into(), "KDouble".into()]
+        );
+        tree.set_compact_storage(false);
+        *tree.get_attribute_mut(p0, 1).unwrap() = "KString".into();
+        assert!(tree.strict_eq(&original));
+        assert_eq!(tree.memory_usage().attribute_strings_bytes(), 0);
+    }
+
+    #[test]
+    fn reclaim_pool_slots() {
+        let attr_size = mem::size_of::<AttributeValue>();
+        let mut tree = tree_v7400! {
+            for i in (0..10i32) {
+                Node: [i] {},
+            }
+        };
+        tree.set_compact_storage(true);
+        let nodes = tree
+            .root()
+            .children()
+            .map(|node| node.node_id())
+            .collect::<Vec<_>>();
+
+        // Slots are reclaimed once more than half of the pool is unused.
+        for &node in &nodes[..5] {
+            tree.append_attribute(node, true);
+        }
+        assert_eq!(tree.memory_usage().unused_bytes(), 5 * attr_size);
+        tree.append_attribute(nodes[5], true);
+        assert_eq!(tree.memory_usage().unused_bytes(), 0);
+
+        let expected = tree_v7400! {
+            for i in (0..10i32) {
+                if (i < 6) {
+                    Node: [i, true] {},
+                } else {
+                    Node: [i] {},
+                }
+            }
+        };
+        assert!(tree.strict_eq(&expected));
+
+        // Slots of removed nodes are also reclaimed.
+        tree.remove_subtree(nodes[6]);
+        tree.remove_subtree(nodes[7]);
+        tree.remove_subtree(nodes[8]);
+        let node_size = mem::size_of::<indextree::Node<crate::tree::v7400::NodeData>>();
+        assert_eq!(tree.memory_usage().unused_bytes(), 3 * node_size);
+        assert_eq!(
+            tree.root()
+                .last_child()
+                .map(|node| node.attributes().to_vec()),
+            Some(vec![AttributeValue::I32(9)])
+        );
+    }
+
+    #[test]
+    fn reclaim_string_slots() {
+        let sym_size = mem::size_of::<super::StringSym>();
+        let mut tree = tree_v7400! {
+            for i in (0..4i32) {
+                Node: [i, "foo"] {},
+            }
+        };
+        tree.set_compact_storage(true);
+        let nodes = tree
+            .root()
+            .children()
+            .map(|node| node.node_id())
+            .collect::<Vec<_>>();
+
+        // Editing puts string values back into the pool, and the string symbol
+        // slots are reclaimed once more than half of them are unused.
+        for &node in &nodes[..2] {
+            *tree.get_attribute_mut(node, 0).unwrap() = 0i32.into();
+        }
+        assert_eq!(tree.memory_usage().unused_bytes(), 2 * sym_size);
+        *tree.get_attribute_mut(nodes[2], 0).unwrap() = 0i32.into();
+        assert_eq!(tree.memory_usage().unused_bytes(), 0);
+
+        let expected = tree_v7400! {
+            for i in (0..4i32) {
+                if (i < 3) {
+                    Node: [0i32, "foo"] {},
+                } else {
+                    Node: [i, "foo"] {},
+                }
+            }
+        };
+        assert!(tree.strict_eq(&expected));
+    }
+}
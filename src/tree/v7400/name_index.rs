@@ -0,0 +1,455 @@
+//! Index of children by names.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::tree::v7400::{NodeId, NodeNameSym, Tree};
+
+/// Index of children by parent nodes and names.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ChildNameIndex {
+    /// Children by parent nodes and names, in the order of siblings.
+    children: HashMap<(NodeId, NodeNameSym), Vec<NodeId>>,
+    /// Entries to be rebuilt, if the updates are deferred.
+    stale: Option<HashSet<(NodeId, NodeNameSym)>>,
+}
+
+impl ChildNameIndex {
+    /// Builds the index of all nodes in the tree.
+    fn build(tree: &Tree) -> Self {
+        let mut children: HashMap<_, Vec<_>> = HashMap::new();
+        for node in tree.arena.iter().filter(|node| !node.is_removed()) {
+            let parent = match node.parent() {
+                Some(parent) => NodeId::new(parent, tree.tree_id()),
+                None => continue,
+            };
+            let id = tree
+                .arena
+                .get_node_id(node)
+                .expect("Should never fail: the node is in the arena");
+            children
+                .entry((parent, node.get().name_sym()))
+                .or_default()
+                .push(NodeId::new(id, tree.tree_id()));
+        }
+        // Nodes in the arena are not in the order of siblings.
+        for (&(parent, _), ids) in children.iter_mut() {
+            if ids.len() > 1 {
+                let order: HashMap<_, _> = parent
+                    .raw()
+                    .children(&tree.arena)
+                    .enumerate()
+                    .map(|(i, id)| (id, i))
+                    .collect();
+                ids.sort_by_key(|id| order[&id.raw()]);
+            }
+        }
+
+        Self {
+            children,
+            stale: None,
+        }
+    }
+
+    /// Returns the children of the parent with the given name.
+    #[must_use]
+    pub(crate) fn get(&self, parent: NodeId, name_sym: NodeNameSym) -> &[NodeId] {
+        self.children
+            .get(&(parent, name_sym))
+            .map_or(&[], |ids| ids.as_slice())
+    }
+
+    /// Adds the node to the entry of its current parent.
+    ///
+    /// The node should not be in the index.
+    fn link(&mut self, tree: &Tree, node: NodeId) {
+        let raw = tree.arena[node.raw()].parent().map(|parent| {
+            let name_sym = tree.arena[node.raw()].get().name_sym();
+            (NodeId::new(parent, tree.tree_id()), name_sym)
+        });
+        let key = match raw {
+            Some(key) => key,
+            None => return,
+        };
+        if let Some(stale) = &mut self.stale {
+            stale.insert(key);
+            return;
+        }
+        let ids = self.children.entry(key).or_default();
+        // Fast path for appended nodes.
+        if tree.arena[node.raw()].next_sibling().is_none() {
+            ids.push(node);
+            return;
+        }
+        // Find the nearest preceding sibling with the same name.
+        let pos = node
+            .raw()
+            .preceding_siblings(&tree.arena)
+            .skip(1)
+            .find(|&id| tree.arena[id].get().name_sym() == key.1)
+            .map_or(0, |prev| {
+                ids.iter()
+                    .rposition(|id| id.raw() == prev)
+                    .expect("Should never fail: the sibling should be in the index")
+                    + 1
+            });
+        ids.insert(pos, node);
+    }
+
+    /// Removes the node from the entry of its current parent.
+    fn unlink(&mut self, tree: &Tree, node: NodeId) {
+        let parent = match tree.arena[node.raw()].parent() {
+            Some(parent) => NodeId::new(parent, tree.tree_id()),
+            None => return,
+        };
+        let key = (parent, tree.arena[node.raw()].get().name_sym());
+        if let Some(stale) = &mut self.stale {
+            stale.insert(key);
+            return;
+        }
+        if let Some(ids) = self.children.get_mut(&key) {
+            if let Some(pos) = ids.iter().rposition(|&id| id == node) {
+                ids.remove(pos);
+            }
+            if ids.is_empty() {
+                self.children.remove(&key);
+            }
+        }
+    }
+
+    /// Returns whether the updates are deferred.
+    #[inline]
+    #[must_use]
+    fn is_deferred(&self) -> bool {
+        self.stale.is_some()
+    }
+
+    /// Rebuilds the entries updated while the updates are deferred, and stops
+    /// deferring the updates.
+    ///
+    /// This takes time proportional to the number of children of the parents
+    /// of the updated entries.
+    fn rebuild_stale(&mut self, tree: &Tree) {
+        let stale = match self.stale.take() {
+            Some(stale) => stale,
+            None => return,
+        };
+        let mut stale_names: HashMap<NodeId, HashSet<NodeNameSym>> = HashMap::new();
+        for (parent, name_sym) in stale {
+            self.children.remove(&(parent, name_sym));
+            stale_names.entry(parent).or_default().insert(name_sym);
+        }
+        for (parent, names) in stale_names {
+            // Entries of the parents removed from the arena are already
+            // forgotten.
+            if tree
+                .arena
+                .get(parent.raw())
+                .map_or(true, |node| node.is_removed())
+            {
+                continue;
+            }
+            for id in parent.raw().children(&tree.arena) {
+                let name_sym = tree.arena[id].get().name_sym();
+                if names.contains(&name_sym) {
+                    self.children
+                        .entry((parent, name_sym))
+                        .or_default()
+                        .push(NodeId::new(id, tree.tree_id()));
+                }
+            }
+        }
+    }
+
+    /// Removes the entries of the parents in the subtree.
+    fn forget_subtree(&mut self, tree: &Tree, subtree_root: NodeId) {
+        for id in subtree_root.raw().descendants(&tree.arena).skip(1) {
+            let node = &tree.arena[id];
+            let parent = node
+                .parent()
+                .expect("Should never fail: descendants have parents");
+            self.children
+                .remove(&(NodeId::new(parent, tree.tree_id()), node.get().name_sym()));
+        }
+    }
+}
+
+impl Tree {
+    /// Builds the index of children by names.
+    ///
+    /// While the index is available,
+    /// [`NodeHandle::children_by_name`][`crate::tree::v7400::NodeHandle::children_by_name`]
+    /// and
+    /// [`NodeHandle::first_child_by_name`][`crate::tree::v7400::NodeHandle::first_child_by_name`]
+    /// take time proportional to the number of matched children, instead of
+    /// all children.
+    ///
+    /// The index is updated by the methods creating, moving, removing, and
+    /// renaming nodes (including undo and redo), so it is kept available
+    /// until [`clear_child_name_index`][`Self::clear_child_name_index`] is
+    /// called.
+    /// Appending a node takes amortized constant time, but other updates take
+    /// time proportional to the number of siblings, since the position among
+    /// the siblings with the same name is searched.
+    /// Bulk edits ([`canonicalize`][`Self::canonicalize`],
+    /// [`retain`][`Self::retain`], and undoing or redoing a group of edits)
+    /// instead rebuild the entries of each affected parent once at the end,
+    /// which takes time proportional to the number of its children.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fbxcel::tree_v7400;
+    ///
+    /// let mut tree = tree_v7400! {
+    ///     Connections: {
+    ///         C: ["OO", 2i64, 1i64] {},
+    ///         C: ["OO", 3i64, 1i64] {},
+    ///     },
+    /// };
+    ///
+    /// tree.build_child_name_index();
+    /// assert!(tree.has_child_name_index());
+    /// let connections = tree.root().first_child_by_name("Connections").unwrap();
+    /// assert_eq!(connections.children_by_name("C").count(), 2);
+    ///
+    /// let connections = connections.node_id();
+    /// tree.append_new(connections, "C");
+    /// assert!(tree.has_child_name_index());
+    /// assert_eq!(connections.to_handle(&tree).children_by_name("C").count(), 3);
+    /// ```
+    pub fn build_child_name_index(&mut self) {
+        self.child_name_index = Some(ChildNameIndex::build(self));
+    }
+
+    /// Drops the index of children by names.
+    #[inline]
+    pub fn clear_child_name_index(&mut self) {
+        self.child_name_index = None;
+    }
+
+    /// Returns whether the index of children by names is available.
+    #[inline]
+    #[must_use]
+    pub fn has_child_name_index(&self) -> bool {
+        self.child_name_index.is_some()
+    }
+
+    /// Returns the index of children by names if available.
+    #[inline]
+    #[must_use]
+    pub(crate) fn child_name_index(&self) -> Option<&ChildNameIndex> {
+        self.child_name_index
+            .as_ref()
+            .filter(|index| !index.is_deferred())
+    }
+
+    /// Calls the given function deferring the updates of the index of
+    /// children by names, and rebuilds the updated entries afterwards.
+    ///
+    /// This should be used by bulk edits, which would take quadratic time to
+    /// update the index for each edit. The index is not used for lookups
+    /// while the updates are deferred.
+    pub(crate) fn with_child_name_index_deferred<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let outermost = match &mut self.child_name_index {
+            Some(index) if !index.is_deferred() => {
+                index.stale = Some(HashSet::new());
+                true
+            }
+            _ => false,
+        };
+        let result = f(self);
+        if outermost {
+            if let Some(mut index) = self.child_name_index.take() {
+                index.rebuild_stale(self);
+                self.child_name_index = Some(index);
+            }
+        }
+        result
+    }
+
+    /// Adds the node to the index of children by names if available.
+    ///
+    /// This should be called after the node is attached to the new parent.
+    pub(crate) fn link_to_child_name_index(&mut self, node: NodeId) {
+        if let Some(mut index) = self.child_name_index.take() {
+            index.link(self, node);
+            self.child_name_index = Some(index);
+        }
+    }
+
+    /// Removes the node from the index of children by names if available.
+    ///
+    /// This should be called before the node is detached from the parent.
+    pub(crate) fn unlink_from_child_name_index(&mut self, node: NodeId) {
+        if let Some(mut index) = self.child_name_index.take() {
+            index.unlink(self, node);
+            self.child_name_index = Some(index);
+        }
+    }
+
+    /// Removes the entries of the parents in the subtree from the index of
+    /// children by names if available.
+    ///
+    /// This should be called before the nodes are removed from the arena.
+    pub(crate) fn forget_subtree_in_child_name_index(&mut self, subtree_root: NodeId) {
+        if let Some(mut index) = self.child_name_index.take() {
+            index.forget_subtree(self, subtree_root);
+            self.child_name_index = Some(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tree::v7400::{CanonicalizeOptions, NodeId, Tree},
+        tree_v7400,
+    };
+
+    use super::ChildNameIndex;
+
+    /// Returns the first attributes of the children with the given name.
+    fn children_ids(tree: &Tree, name: &str) -> Vec<i32> {
+        tree.root()
+            .first_child()
+            .unwrap()
+            .children_by_name(name)
+            .map(|node| node.attributes()[0].get_i32().unwrap())
+            .collect()
+    }
+
+    /// Checks the index is the same as the one built from scratch.
+    fn assert_index_up_to_date(tree: &Tree) {
+        assert_eq!(
+            tree.child_name_index(),
+            Some(&ChildNameIndex::build(tree)),
+            "The index should be up to date"
+        );
+    }
+
+    #[test]
+    fn indexed_lookup_and_maintenance() {
+        let mut tree = tree_v7400! {
+            Objects: {
+                Model: [1i32] {},
+                Geometry: [2i32] {},
+                Model: [3i32] {},
+            },
+        };
+        let objects = tree.root().first_child().unwrap().node_id();
+        let first = tree.root().first_child().unwrap().first_child().unwrap();
+        let first = first.node_id();
+        // Move a node so that the arena order differs from the sibling order.
+        tree.append(first, objects);
+        tree.build_child_name_index();
+        assert_eq!(children_ids(&tree, "Model"), [3, 1]);
+        assert_eq!(children_ids(&tree, "Geometry"), [2]);
+        assert!(children_ids(&tree, "Unknown").is_empty());
+
+        // Attribute edits keep the index.
+        *tree.get_attribute_mut(first, 0).unwrap() = 4i32.into();
+        assert_eq!(children_ids(&tree, "Model"), [3, 4]);
+
+        tree.get_node_mut(first).unwrap().rename("Geometry");
+        assert_index_up_to_date(&tree);
+        assert_eq!(children_ids(&tree, "Geometry"), [2, 4]);
+        tree.get_node_mut(first).unwrap().rename("Model");
+        tree.set_journal_enabled(true);
+
+        let mutations: [fn(&mut Tree, NodeId); 10] = [
+            |tree, objects| {
+                let child = tree.append_new(objects, "Model");
+                tree.append_attribute(child, 5i32);
+            },
+            |tree, objects| {
+                let child = tree.prepend_new(objects, "Model");
+                tree.append_attribute(child, 6i32);
+            },
+            |tree, objects| {
+                let geometry = objects.to_handle(tree).first_child_by_name("Geometry");
+                let child = tree.insert_new_after(geometry.unwrap().node_id(), "Model");
+                tree.append_attribute(child, 7i32);
+            },
+            |tree, objects| {
+                let last = objects.to_handle(tree).last_child().unwrap().node_id();
+                tree.detach(last);
+            },
+            |tree, objects| {
+                let last = objects.to_handle(tree).last_child().unwrap().node_id();
+                let geometry = objects.to_handle(tree).first_child_by_name("Geometry");
+                tree.insert_before(last, geometry.unwrap().node_id());
+            },
+            |tree, objects| {
+                let first = objects.to_handle(tree).first_child().unwrap().node_id();
+                tree.remove_subtree(first);
+            },
+            |tree, objects| {
+                let last = objects.to_handle(tree).last_child().unwrap().node_id();
+                tree.get_node_mut(last).unwrap().rename("Geometry");
+            },
+            |tree, objects| {
+                let first = objects.to_handle(tree).first_child().unwrap().node_id();
+                tree.clone_subtree(objects, first);
+            },
+            |tree, _| {
+                let mut options = CanonicalizeOptions::new();
+                options.sort_children("Objects", 1);
+                tree.canonicalize(&options);
+            },
+            |tree, _| {
+                tree.retain(|node| node.name() != "Geometry");
+            },
+        ];
+        for mutate in mutations {
+            let expected = {
+                let mut tree = tree.clone();
+                tree.clear_child_name_index();
+                mutate(&mut tree, objects);
+                (
+                    children_ids(&tree, "Model"),
+                    children_ids(&tree, "Geometry"),
+                )
+            };
+            let before = children_ids(&tree, "Model");
+            tree.begin_transaction();
+            mutate(&mut tree, objects);
+            tree.commit_transaction();
+            assert_index_up_to_date(&tree);
+            assert_eq!(
+                (
+                    children_ids(&tree, "Model"),
+                    children_ids(&tree, "Geometry")
+                ),
+                expected
+            );
+
+            assert!(tree.undo());
+            assert_index_up_to_date(&tree);
+            assert_eq!(children_ids(&tree, "Model"), before);
+            assert!(tree.redo());
+            assert_index_up_to_date(&tree);
+        }
+
+        // Slots of removed nodes are reused by new nodes.
+        tree.set_journal_enabled(false);
+        let first = tree.root().first_child().unwrap().first_child().unwrap();
+        let first = first.node_id();
+        tree.append_new(first, "Child");
+        tree.remove_subtree(first);
+        let new = tree.append_new(objects, "Model");
+        tree.append_new(new, "Model");
+        assert_index_up_to_date(&tree);
+        assert_eq!(new.to_handle(&tree).children_by_name("Child").count(), 0);
+
+        let map = tree.compact();
+        assert!(tree.has_child_name_index());
+        assert_index_up_to_date(&tree);
+        assert_eq!(
+            map[&new].to_handle(&tree).children_by_name("Model").count(),
+            1
+        );
+    }
+}
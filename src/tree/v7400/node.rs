@@ -1,30 +1,72 @@
 //! Node type.
 
-use crate::tree::v7400::{DepthFirstTraverseSubtree, NodeHandle, Tree};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-pub(crate) use self::{data::NodeData, name::NodeNameSym};
+use crate::tree::v7400::{DepthFirstTraverseSubtree, NodeHandle, NodeHandleMut, Tree};
+
+pub(crate) use self::{
+    data::{AttributeValues, NodeData},
+    name::NodeNameSym,
+    pool::{AttributePool, StringSym},
+};
 
 mod data;
 pub(crate) mod handle;
+pub(crate) mod handle_mut;
+pub(crate) mod iter;
 mod name;
+mod pool;
+
+/// Identity of a tree.
+///
+/// This is used to detect node IDs of other trees.
+/// Clones of a tree share the identity, since node IDs are valid in both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct TreeId(usize);
+
+impl TreeId {
+    /// Creates a new unique `TreeId`.
+    #[must_use]
+    pub(crate) fn new() -> Self {
+        /// Next tree ID.
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        TreeId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
 
 /// Node ID in FBX data tree.
+///
+/// A node ID remembers the tree it belongs to, and using it with other trees
+/// is detected (see [`Tree`] for details).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct NodeId(indextree::NodeId);
+pub struct NodeId {
+    /// Raw node ID.
+    raw: indextree::NodeId,
+    /// Identity of the tree.
+    tree_id: TreeId,
+}
 
 impl NodeId {
     /// Creates a new `NodeId`.
     #[inline]
     #[must_use]
-    pub(crate) fn new(id: indextree::NodeId) -> Self {
-        NodeId(id)
+    pub(crate) fn new(raw: indextree::NodeId, tree_id: TreeId) -> Self {
+        Self { raw, tree_id }
     }
 
     /// Returns the raw node ID used by internal tree implementation.
     #[inline]
     #[must_use]
     pub(crate) fn raw(self) -> indextree::NodeId {
-        self.0
+        self.raw
+    }
+
+    /// Returns the identity of the tree the node belongs to.
+    #[inline]
+    #[must_use]
+    pub(crate) fn tree_id(self) -> TreeId {
+        self.tree_id
     }
 
     /// Creates a new `NodeHandle` to make accesible to the node in the tree.
@@ -42,6 +84,19 @@ impl NodeId {
         NodeHandle::new(tree, self)
     }
 
+    /// Creates a new `NodeHandleMut` to make accesible to the node in the
+    /// tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node ID is not used in the given tree.
+    /// To avoid panics, use [`Tree::get_node_mut`].
+    #[inline]
+    #[must_use]
+    pub fn to_handle_mut(self, tree: &mut Tree) -> NodeHandleMut<'_> {
+        NodeHandleMut::new(tree, self)
+    }
+
     /// Returns the helper object to traverse the subtree (the node itself and its descendant).
     #[inline]
     #[must_use]
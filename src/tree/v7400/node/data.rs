@@ -1,6 +1,13 @@
 //! Node-local data.
 
-use crate::{low::v7400::AttributeValue, tree::v7400::node::NodeNameSym};
+use std::{borrow::Cow, fmt, ops::Range, slice};
+
+use once_cell::sync::OnceCell;
+
+use crate::{
+    low::v7400::AttributeValue,
+    tree::v7400::node::{AttributePool, NodeNameSym, StringSym},
+};
 
 /// Node-local data in FBX data tree.
 ///
@@ -11,7 +18,49 @@ pub(crate) struct NodeData {
     /// Node name.
     name_sym: NodeNameSym,
     /// Node attributes.
-    attributes: Vec<AttributeValue>,
+    attributes: NodeAttributes,
+}
+
+/// Storage of node attributes.
+#[derive(Debug, Clone)]
+enum NodeAttributes {
+    /// Attributes owned by the node.
+    Owned(Vec<AttributeValue>),
+    /// Attributes in the attribute pool of the tree, without string values.
+    Pooled(Range<usize>),
+    /// Attributes in the attribute pool of the tree, with string values in
+    /// the string table of the pool.
+    Interned {
+        /// Range of the value slots.
+        values: Range<usize>,
+        /// Range of the string symbol slots.
+        strings: Range<usize>,
+        /// Attributes with the string values, created on the first access.
+        cache: OnceCell<Box<[AttributeValue]>>,
+    },
+}
+
+impl PartialEq for NodeAttributes {
+    /// Compares the storages, except for the caches.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Owned(l), Self::Owned(r)) => l == r,
+            (Self::Pooled(l), Self::Pooled(r)) => l == r,
+            (
+                Self::Interned {
+                    values: l_values,
+                    strings: l_strings,
+                    ..
+                },
+                Self::Interned {
+                    values: r_values,
+                    strings: r_strings,
+                    ..
+                },
+            ) => l_values == r_values && l_strings == r_strings,
+            _ => false,
+        }
+    }
 }
 
 impl NodeData {
@@ -22,29 +71,208 @@ impl NodeData {
         self.name_sym
     }
 
+    /// Sets the node name symbol.
+    #[inline]
+    pub(crate) fn set_name_sym(&mut self, name_sym: NodeNameSym) {
+        self.name_sym = name_sym;
+    }
+
     /// Returns the reference to the attributes.
+    ///
+    /// `pool` should be the attribute pool of the tree.
+    /// If the string values are interned, the attributes are created and
+    /// cached on the first call.
     #[inline]
     #[must_use]
-    pub(crate) fn attributes(&self) -> &[AttributeValue] {
-        &self.attributes
+    pub(crate) fn attributes<'a>(&'a self, pool: &'a AttributePool) -> &'a [AttributeValue] {
+        match &self.attributes {
+            NodeAttributes::Owned(vec) => vec,
+            NodeAttributes::Pooled(range) => pool.values(range.clone()),
+            NodeAttributes::Interned {
+                values,
+                strings,
+                cache,
+            } => cache.get_or_init(|| {
+                pool.materialize(values.clone(), strings.clone())
+                    .into_boxed_slice()
+            }),
+        }
     }
 
-    /// Appends the given value to the attributes.
-    #[inline]
-    pub(crate) fn append_attribute(&mut self, v: AttributeValue) {
-        self.attributes.push(v)
+    /// Returns an iterator of the attributes.
+    ///
+    /// `pool` should be the attribute pool of the tree.
+    /// Unlike [`attributes`][`Self::attributes`], this does not create the
+    /// cache: interned string values are created for each iteration, and the
+    /// other values are borrowed.
+    #[must_use]
+    pub(crate) fn attribute_values<'a>(&'a self, pool: &'a AttributePool) -> AttributeValues<'a> {
+        let (values, strings) = match &self.attributes {
+            NodeAttributes::Owned(vec) => (&vec[..], None),
+            NodeAttributes::Pooled(range) => (pool.values(range.clone()), None),
+            NodeAttributes::Interned {
+                values,
+                strings,
+                cache,
+            } => match cache.get() {
+                Some(cache) => (&cache[..], None),
+                None => (
+                    pool.values(values.clone()),
+                    Some((pool, pool.string_syms(strings.clone()).iter())),
+                ),
+            },
+        };
+        AttributeValues {
+            values: values.iter(),
+            strings,
+        }
     }
 
-    /// Appends the given value to the attributes.
-    #[inline]
-    pub(crate) fn get_attribute_mut(&mut self, i: usize) -> Option<&mut AttributeValue> {
-        self.attributes.get_mut(i)
+    /// Puts the interned string values back into the pool, and returns the
+    /// number of the string symbol slots left unused.
+    ///
+    /// `pool` should be the attribute pool of the tree.
+    pub(crate) fn restore_strings(&mut self, pool: &mut AttributePool) -> usize {
+        match &self.attributes {
+            NodeAttributes::Interned {
+                values, strings, ..
+            } => {
+                pool.restore_strings(values.clone(), strings.clone());
+                let count = strings.len();
+                self.attributes = NodeAttributes::Pooled(values.clone());
+                count
+            }
+            _ => 0,
+        }
     }
 
-    /// Replaces all attributes by the given one, and returns the old.
+    /// Returns the mutable reference to the attributes.
+    ///
+    /// `pool` should be the attribute pool of the tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string values are interned. They should have been
+    /// restored by [`restore_strings`][`Self::restore_strings`].
     #[inline]
-    pub(crate) fn replace_attributes(&mut self, new: Vec<AttributeValue>) -> Vec<AttributeValue> {
-        std::mem::replace(&mut self.attributes, new)
+    #[must_use]
+    pub(crate) fn attributes_mut<'a>(
+        &'a mut self,
+        pool: &'a mut AttributePool,
+    ) -> &'a mut [AttributeValue] {
+        match &mut self.attributes {
+            NodeAttributes::Owned(vec) => vec,
+            NodeAttributes::Pooled(range) => pool.values_mut(range.clone()),
+            NodeAttributes::Interned { .. } => {
+                panic!("Interned string values should be restored before modification")
+            }
+        }
+    }
+
+    /// Returns the mutable reference to the attributes `Vec`.
+    ///
+    /// If the attributes are in the pool, they are moved out to the node, and
+    /// the slots in the pool are left unused.
+    #[must_use]
+    pub(crate) fn attributes_vec_mut(
+        &mut self,
+        pool: &mut AttributePool,
+    ) -> &mut Vec<AttributeValue> {
+        let vec = match &self.attributes {
+            NodeAttributes::Owned(_) => None,
+            NodeAttributes::Pooled(range) => Some(pool.take(range.clone(), 0..0)),
+            NodeAttributes::Interned {
+                values, strings, ..
+            } => Some(pool.take(values.clone(), strings.clone())),
+        };
+        if let Some(vec) = vec {
+            self.attributes = NodeAttributes::Owned(vec);
+        }
+        match &mut self.attributes {
+            NodeAttributes::Owned(vec) => vec,
+            _ => unreachable!("Should never fail: attributes are owned"),
+        }
+    }
+
+    /// Moves the attributes to the end of the given pool.
+    ///
+    /// `old_pool` should be the current attribute pool of the tree, and
+    /// `new_pool` is the pool to be used after this call.
+    pub(crate) fn move_attributes_to_pool(
+        &mut self,
+        old_pool: &mut AttributePool,
+        new_pool: &mut AttributePool,
+    ) {
+        let attributes = std::mem::take(self.attributes_vec_mut(old_pool));
+        let (values, strings) = new_pool.push(attributes);
+        self.attributes = NodeAttributes::pooled(values, strings);
+    }
+
+    /// Moves the attributes in the pool to the end of the new pool.
+    ///
+    /// Attributes owned by the node are not moved.
+    /// `new_pool` should share the string table with `old_pool`.
+    pub(crate) fn repack_pooled_attributes(
+        &mut self,
+        old_pool: &mut AttributePool,
+        new_pool: &mut AttributePool,
+    ) {
+        match &mut self.attributes {
+            NodeAttributes::Owned(_) => {}
+            NodeAttributes::Pooled(range) => {
+                *range = old_pool.move_slots_to(new_pool, range.clone(), 0..0).0;
+            }
+            NodeAttributes::Interned {
+                values, strings, ..
+            } => {
+                let (new_values, new_strings) =
+                    old_pool.move_slots_to(new_pool, values.clone(), strings.clone());
+                *values = new_values;
+                *strings = new_strings;
+            }
+        }
+    }
+
+    /// Moves the attributes out of the pool.
+    pub(crate) fn move_attributes_from_pool(&mut self, pool: &mut AttributePool) {
+        self.attributes_vec_mut(pool).shrink_to_fit();
+    }
+
+    /// Returns the numbers of the value slots and the string symbol slots in
+    /// the pool, and the capacity of the attributes `Vec` owned by the node.
+    #[must_use]
+    pub(crate) fn attributes_storage(&self) -> (usize, usize, usize) {
+        match &self.attributes {
+            NodeAttributes::Owned(vec) => (0, 0, vec.capacity()),
+            NodeAttributes::Pooled(range) => (range.len(), 0, 0),
+            NodeAttributes::Interned {
+                values, strings, ..
+            } => (values.len(), strings.len(), 0),
+        }
+    }
+
+    /// Returns the stored attributes without creating the cache, the number of
+    /// the interned string values, and the cached attributes if available.
+    ///
+    /// Interned string values in the stored attributes are left empty.
+    #[must_use]
+    pub(crate) fn stored_attributes<'a>(
+        &'a self,
+        pool: &'a AttributePool,
+    ) -> (&'a [AttributeValue], usize, Option<&'a [AttributeValue]>) {
+        match &self.attributes {
+            NodeAttributes::Owned(vec) => (vec, 0, None),
+            NodeAttributes::Pooled(range) => (pool.values(range.clone()), 0, None),
+            NodeAttributes::Interned {
+                values,
+                strings,
+                cache,
+            } => (
+                pool.values(values.clone()),
+                strings.len(),
+                cache.get().map(|v| &**v),
+            ),
+        }
     }
 
     /// Creates a new `NodeData`.
@@ -53,7 +281,79 @@ impl NodeData {
     pub(crate) fn new(name_sym: NodeNameSym, attributes: Vec<AttributeValue>) -> Self {
         Self {
             name_sym,
-            attributes,
+            attributes: NodeAttributes::Owned(attributes),
+        }
+    }
+
+    /// Creates a new `NodeData` with the attributes in the pool.
+    #[inline]
+    #[must_use]
+    pub(crate) fn with_pooled_attributes(
+        name_sym: NodeNameSym,
+        attributes: impl IntoIterator<Item = AttributeValue>,
+        pool: &mut AttributePool,
+    ) -> Self {
+        let (values, strings) = pool.push(attributes);
+        Self {
+            name_sym,
+            attributes: NodeAttributes::pooled(values, strings),
+        }
+    }
+}
+
+impl NodeAttributes {
+    /// Creates a new storage of the attributes in the pool.
+    #[must_use]
+    fn pooled(values: Range<usize>, strings: Range<usize>) -> Self {
+        if strings.is_empty() {
+            Self::Pooled(values)
+        } else {
+            Self::Interned {
+                values,
+                strings,
+                cache: OnceCell::new(),
+            }
+        }
+    }
+}
+
+/// Iterator of node attributes, which does not create the cache.
+#[derive(Clone)]
+pub(crate) struct AttributeValues<'a> {
+    /// Value slots.
+    values: slice::Iter<'a, AttributeValue>,
+    /// Attribute pool and the symbols of the interned string values.
+    strings: Option<(&'a AttributePool, slice::Iter<'a, StringSym>)>,
+}
+
+impl<'a> Iterator for AttributeValues<'a> {
+    type Item = Cow<'a, AttributeValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.values.next()?;
+        match (value, &mut self.strings) {
+            (AttributeValue::String(_), Some((pool, syms))) => {
+                let sym = *syms
+                    .next()
+                    .expect("Should never fail: each string value has a symbol");
+                Some(Cow::Owned(AttributeValue::String(
+                    pool.resolve(sym).to_owned(),
+                )))
+            }
+            _ => Some(Cow::Borrowed(value)),
         }
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl ExactSizeIterator for AttributeValues<'_> {}
+
+impl fmt::Debug for AttributeValues<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
 }
@@ -4,7 +4,10 @@ use std::fmt;
 
 use crate::{
     low::v7400::AttributeValue,
-    tree::v7400::{NodeData, NodeId, NodeNameSym, Tree},
+    tree::v7400::{
+        AttributeValues, NodeData, NodeId, NodeNameSym, Query, QueryError, Select, Tree,
+        TreeApproxEqOptions,
+    },
 };
 
 /// Node handle.
@@ -74,10 +77,23 @@ impl<'a> NodeHandle<'a> {
     }
 
     /// Returns the node attributes.
+    ///
+    /// In the compact storage mode, this caches the attributes with string
+    /// values on the first call (see [`Tree::set_compact_storage`]).
     #[inline]
     #[must_use]
     pub fn attributes(&self) -> &'a [AttributeValue] {
-        self.node().get().attributes()
+        self.tree.node_attributes(self.node_id)
+    }
+
+    /// Returns an iterator of the node attributes, without creating the cache
+    /// of the attributes with interned string values.
+    ///
+    /// See [`Tree::set_compact_storage`].
+    #[inline]
+    #[must_use]
+    pub(crate) fn attribute_values(&self) -> AttributeValues<'a> {
+        self.tree.node_attribute_values(self.node_id)
     }
 
     /// Returns an iterator of children with the given name.
@@ -91,12 +107,24 @@ impl<'a> NodeHandle<'a> {
     }
 
     /// Returns an iterator of children with the given name.
+    ///
+    /// If the index of children by names is available (see
+    /// [`Tree::build_child_name_index`]), this uses the index instead of
+    /// scanning all children.
     #[inline]
     #[must_use]
     pub fn children_by_name(&self, name: &str) -> ChildrenByName<'a> {
+        let name_sym = self.tree.node_name_sym(name);
+        let iter = match (name_sym, self.tree.child_name_index()) {
+            (Some(name_sym), Some(index)) => {
+                ChildrenByNameIter::Indexed(index.get(self.node_id, name_sym).iter())
+            }
+            _ => ChildrenByNameIter::Scan(self.children()),
+        };
         ChildrenByName {
-            name_sym: self.tree.node_name_sym(name),
-            children_iter: self.children(),
+            tree: self.tree,
+            name_sym,
+            iter,
         }
     }
 
@@ -107,6 +135,20 @@ impl<'a> NodeHandle<'a> {
         self.children_by_name(name).next()
     }
 
+    /// Returns an iterator of nodes selected by the given query.
+    ///
+    /// Relative queries are evaluated from this node, and absolute queries
+    /// (starting with `/`) are evaluated from the root node of the tree.
+    /// See [`query`][`crate::tree::v7400::query`] module documentation for
+    /// syntax.
+    ///
+    /// To evaluate the same query many times, parse it once by
+    /// [`Query::parse`] and use [`Query::select`].
+    #[inline]
+    pub fn select(&self, query: &str) -> Result<Select<'a>, QueryError> {
+        Query::parse(query).map(|query| query.select(*self))
+    }
+
     /// Compares nodes strictly.
     ///
     /// Returns `true` if the two trees are same.
@@ -120,6 +162,32 @@ impl<'a> NodeHandle<'a> {
     pub fn strict_eq(&self, other: &Self) -> bool {
         nodes_strict_eq(*self, *other)
     }
+
+    /// Compares nodes approximately.
+    ///
+    /// Ignored subtrees are skipped only among descendants; the given nodes
+    /// themselves are always compared.
+    ///
+    /// See [`TreeApproxEqOptions`] for details.
+    #[inline]
+    #[must_use]
+    pub fn approx_eq(&self, other: &Self, options: &TreeApproxEqOptions) -> bool {
+        options.nodes_eq(*self, *other)
+    }
+
+    /// Deserializes the node into a value of the given type.
+    ///
+    /// See [`NodeDeserializer`][`crate::tree::v7400::NodeDeserializer`] for
+    /// how nodes are mapped to the serde data model.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    #[inline]
+    pub fn deserialize<T>(&self) -> Result<T, crate::tree::v7400::DeserializeError>
+    where
+        T: serde::Deserialize<'a>,
+    {
+        T::deserialize(crate::tree::v7400::NodeDeserializer::new(*self))
+    }
 }
 
 /// Implement accessors to neighbor nodes.
@@ -142,7 +210,7 @@ macro_rules! impl_related_node_accessor {
         pub fn $accessor(&self) -> Option<NodeHandle<'a>> {
             self.node()
                 .$accessor()
-                .map(|id| NodeId::new(id).to_handle(&self.tree))
+                .map(|id| NodeId::new(id, self.tree.tree_id()).to_handle(self.tree))
         }
     };
 }
@@ -169,12 +237,12 @@ fn nodes_strict_eq(left: NodeHandle<'_>, right: NodeHandle<'_>) -> bool {
     }
     // Compare attributes.
     {
-        let left = left.attributes();
-        let right = right.attributes();
+        let left = left.attribute_values();
+        let right = right.attribute_values();
         if left.len() != right.len() {
             return false;
         }
-        if !left.iter().zip(right).all(|(l, r)| l.strict_eq(r)) {
+        if !left.zip(right).all(|(l, r)| l.strict_eq(&r)) {
             return false;
         }
     }
@@ -211,7 +279,7 @@ impl<'a> Iterator for Children<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let child_id = self.iter.next()?;
-        Some(NodeId::new(child_id).to_handle(self.tree))
+        Some(NodeId::new(child_id, self.tree.tree_id()).to_handle(self.tree))
     }
 }
 
@@ -227,10 +295,21 @@ impl<'a> fmt::Debug for Children<'a> {
 /// An iterator of children of a node, with a specific name.
 #[derive(Clone)]
 pub struct ChildrenByName<'a> {
+    /// Tree.
+    tree: &'a Tree,
     /// Name symbol.
     name_sym: Option<NodeNameSym>,
-    /// Children node iterator.
-    children_iter: Children<'a>,
+    /// Underlying iterator.
+    iter: ChildrenByNameIter<'a>,
+}
+
+/// Underlying iterator of [`ChildrenByName`].
+#[derive(Clone)]
+enum ChildrenByNameIter<'a> {
+    /// Scans all children.
+    Scan(Children<'a>),
+    /// Iterates over the children in the index.
+    Indexed(std::slice::Iter<'a, NodeId>),
 }
 
 impl<'a> Iterator for ChildrenByName<'a> {
@@ -238,8 +317,12 @@ impl<'a> Iterator for ChildrenByName<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let name_sym = self.name_sym?;
-        self.children_iter
-            .find(|child| child.name_sym() == name_sym)
+        match &mut self.iter {
+            ChildrenByNameIter::Scan(children) => {
+                children.find(|child| child.name_sym() == name_sym)
+            }
+            ChildrenByNameIter::Indexed(ids) => ids.next().map(|id| id.to_handle(self.tree)),
+        }
     }
 }
 
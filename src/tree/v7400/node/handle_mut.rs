@@ -0,0 +1,411 @@
+//! Mutable node handle.
+
+use std::{borrow::Cow, fmt};
+
+use crate::{
+    low::v7400::AttributeValue,
+    tree::v7400::{Change, NodeData, NodeHandle, NodeId, Tree},
+};
+
+/// Mutable node handle.
+///
+/// This exclusively borrows the tree, so the node is guaranteed to exist
+/// while the handle is alive.
+///
+/// # Examples
+///
+/// ```
+/// use fbxcel::tree_v7400;
+///
+/// let mut tree = tree_v7400! {
+///     Objects: {
+///         Model: [1i64, "Cube\u{0}\u{1}Model", "Mesh"] {},
+///         Model: [2i64, "Lamp\u{0}\u{1}Model", "Light"] {},
+///     },
+/// };
+///
+/// let mut root = tree.root_mut();
+/// let mut objects = root.first_child().expect("should exist");
+/// let mut children = objects.children_mut();
+/// while let Some(mut model) = children.next() {
+///     model.rename("LegacyModel");
+///     model.attributes_mut()[0] = 0i64.into();
+///     model.remove_attribute(2);
+/// }
+///
+/// let expected = tree_v7400! {
+///     Objects: {
+///         LegacyModel: [0i64, "Cube\u{0}\u{1}Model"] {},
+///         LegacyModel: [0i64, "Lamp\u{0}\u{1}Model"] {},
+///     },
+/// };
+/// assert!(tree.strict_eq(&expected));
+/// ```
+pub struct NodeHandleMut<'a> {
+    /// The tree the node belongs to.
+    tree: &'a mut Tree,
+    /// Node ID.
+    node_id: NodeId,
+}
+
+impl<'a> NodeHandleMut<'a> {
+    /// Creates a new `NodeHandleMut`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given node ID is not used in the given tree.
+    #[inline]
+    #[must_use]
+    pub(crate) fn new(tree: &'a mut Tree, node_id: NodeId) -> Self {
+        assert!(
+            tree.contains_node(node_id),
+            "The node ID is not used in the given tree: node_id={:?}",
+            node_id
+        );
+
+        Self { tree, node_id }
+    }
+
+    /// Returns a reference to the tree.
+    #[inline]
+    #[must_use]
+    pub fn tree(&self) -> &Tree {
+        self.tree
+    }
+
+    /// Returns the node ID.
+    #[inline]
+    #[must_use]
+    pub fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// Returns the immutable node handle.
+    #[inline]
+    #[must_use]
+    pub fn as_handle(&self) -> NodeHandle<'_> {
+        NodeHandle::new(self.tree, self.node_id)
+    }
+
+    /// Returns a mutable node handle with the shorter lifetime.
+    #[inline]
+    #[must_use]
+    pub fn reborrow(&mut self) -> NodeHandleMut<'_> {
+        NodeHandleMut {
+            tree: self.tree,
+            node_id: self.node_id,
+        }
+    }
+
+    /// Returns the mutable internally managed node data.
+    #[must_use]
+    fn data_mut(&mut self) -> &mut NodeData {
+        self.tree
+            .arena
+            .get_mut(self.node_id.raw())
+            .expect("Should never fail: the node should exist")
+            .get_mut()
+    }
+
+    /// Returns `true` if the node is the root node.
+    #[inline]
+    #[must_use]
+    fn is_root(&self) -> bool {
+        self.node_id == self.tree.root_id
+    }
+
+    /// Returns the node name.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.tree
+            .resolve_node_name(self.tree.node(self.node_id).get().name_sym())
+    }
+
+    /// Renames the node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is the root node.
+    pub fn rename(&mut self, name: &str) {
+        assert!(!self.is_root(), "Root node should not be renamed");
+        let node_id = self.node_id;
+        self.tree.record_with(|tree| Change::Rename {
+            node: node_id,
+            name_sym: tree.node(node_id).get().name_sym(),
+        });
+        let name_sym = self.tree.node_names.get_or_intern(name);
+        self.tree.unlink_from_child_name_index(node_id);
+        self.data_mut().set_name_sym(name_sym);
+        self.tree.link_to_child_name_index(node_id);
+    }
+
+    /// Returns the node attributes.
+    #[inline]
+    #[must_use]
+    pub fn attributes(&self) -> &[AttributeValue] {
+        self.tree.node_attributes(self.node_id)
+    }
+
+    /// Returns the mutable node attributes.
+    ///
+    /// If the change journal is enabled, all attributes are cloned to record
+    /// the old values.
+    #[must_use]
+    pub fn attributes_mut(&mut self) -> &mut [AttributeValue] {
+        let node_id = self.node_id;
+        self.tree.record_with(|tree| Change::SetAttributes {
+            node: node_id,
+            attributes: tree
+                .node_attribute_values(node_id)
+                .map(Cow::into_owned)
+                .collect(),
+        });
+        self.tree.node_attributes_mut(self.node_id)
+    }
+
+    /// Appends the given value to the attributes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is the root node.
+    #[inline]
+    pub fn append_attribute(&mut self, v: impl Into<AttributeValue>) {
+        let len = self.tree.node_attribute_values(self.node_id).len();
+        self.insert_attribute(len, v);
+    }
+
+    /// Inserts the given value to the attributes at the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    ///
+    /// * the node is the root node, or
+    /// * `index` is greater than the number of the attributes.
+    pub fn insert_attribute(&mut self, index: usize, v: impl Into<AttributeValue>) {
+        assert!(!self.is_root(), "Root node should have no attributes");
+        self.tree
+            .node_attributes_vec_mut(self.node_id)
+            .insert(index, v.into());
+        let node_id = self.node_id;
+        self.tree.record_with(|_| Change::RemoveAttribute {
+            node: node_id,
+            index,
+        });
+    }
+
+    /// Removes the attribute at the given index and returns it.
+    ///
+    /// Returns `None` if the index is out of range.
+    pub fn remove_attribute(&mut self, index: usize) -> Option<AttributeValue> {
+        if index >= self.tree.node_attribute_values(self.node_id).len() {
+            return None;
+        }
+        let value = self
+            .tree
+            .node_attributes_vec_mut(self.node_id)
+            .remove(index);
+        let node_id = self.node_id;
+        self.tree.record_with(|_| Change::InsertAttribute {
+            node: node_id,
+            index,
+            value: value.clone(),
+        });
+        Some(value)
+    }
+
+    /// Creates a new node and appends it to this node.
+    ///
+    /// Returns the handle of the new child.
+    pub fn append_new(&mut self, name: &str) -> NodeHandleMut<'_> {
+        let child = self.tree.append_new(self.node_id, name);
+        NodeHandleMut::new(self.tree, child)
+    }
+
+    /// Returns a lending iterator of mutable children handles.
+    #[inline]
+    #[must_use]
+    pub fn children_mut(&mut self) -> ChildrenMut<'_> {
+        ChildrenMut {
+            parent: self.node_id,
+            tree: self.tree,
+            current: None,
+        }
+    }
+
+    /// Converts the handle into the handle of the related node.
+    #[inline]
+    fn related(&mut self, id: Option<indextree::NodeId>) -> Option<NodeHandleMut<'_>> {
+        let id = NodeId::new(id?, self.tree.tree_id());
+        Some(NodeHandleMut::new(self.tree, id))
+    }
+}
+
+/// Implement accessors to neighbor nodes.
+macro_rules! impl_related_node_accessor_mut {
+    (
+        $(
+            $(#[$meta:meta])*
+            $accessor:ident;
+        )*
+    ) => {
+        impl<'a> NodeHandleMut<'a> {
+            $(
+                $(#[$meta])*
+                #[must_use]
+                pub fn $accessor(&mut self) -> Option<NodeHandleMut<'_>> {
+                    let id = self.tree.node(self.node_id).$accessor();
+                    self.related(id)
+                }
+            )*
+        }
+    };
+}
+
+impl_related_node_accessor_mut! {
+    /// Returns mutable parent node handle if available.
+    parent;
+    /// Returns mutable first child node handle if available.
+    first_child;
+    /// Returns mutable last child node handle if available.
+    last_child;
+    /// Returns mutable previous sibling node handle if available.
+    previous_sibling;
+    /// Returns mutable next sibling node handle if available.
+    next_sibling;
+}
+
+impl fmt::Debug for NodeHandleMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeHandleMut")
+            .field("node_id", &self.node_id)
+            .finish()
+    }
+}
+
+/// A lending iterator of mutable children handles of a node.
+///
+/// This does not implement [`Iterator`], since each handle borrows the
+/// iterator. Use `while let Some(child) = children.next() { .. }` instead.
+///
+/// The next child is looked up from the current one, so siblings appended to
+/// the parent while iterating (for example through
+/// `child.reborrow().parent()`) come after the current position and are also
+/// visited. Children appended to the visited children are not.
+pub struct ChildrenMut<'a> {
+    /// Tree.
+    tree: &'a mut Tree,
+    /// Parent.
+    parent: NodeId,
+    /// The child returned last.
+    current: Option<NodeId>,
+}
+
+impl ChildrenMut<'_> {
+    /// Returns the next child.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<NodeHandleMut<'_>> {
+        let next = match self.current {
+            Some(current) => self.tree.node(current).next_sibling(),
+            None => self.tree.node(self.parent).first_child(),
+        }?;
+        let id = NodeId::new(next, self.tree.tree_id());
+        self.current = Some(id);
+        Some(NodeHandleMut::new(self.tree, id))
+    }
+}
+
+impl fmt::Debug for ChildrenMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChildrenMut")
+            .field("parent", &self.parent)
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tree_v7400;
+
+    #[test]
+    fn edit_and_navigate() {
+        let mut tree = tree_v7400! {
+            A: [1i32] {
+                B: {},
+                C: {},
+            },
+        };
+
+        {
+            let mut root = tree.root_mut();
+            let mut a = root.first_child().unwrap();
+            a.insert_attribute(0, "first");
+            a.append_attribute(true);
+            assert_eq!(a.remove_attribute(1), Some(1i32.into()));
+            assert_eq!(a.remove_attribute(5), None);
+
+            let mut b = a.first_child().unwrap();
+            b.rename("Renamed");
+            b.append_new("D").append_attribute(4i64);
+            let mut c = b.next_sibling().unwrap();
+            assert_eq!(c.name(), "C");
+            assert!(c.next_sibling().is_none());
+            assert_eq!(c.parent().unwrap().name(), "A");
+        }
+
+        let expected = tree_v7400! {
+            A: ["first", true] {
+                Renamed: {
+                    D: [4i64] {},
+                },
+                C: {},
+            },
+        };
+        assert!(tree.strict_eq(&expected));
+    }
+
+    #[test]
+    fn append_sibling_while_iterating() {
+        let mut tree = tree_v7400! {
+            A: {
+                B: {},
+                C: {},
+            },
+        };
+
+        let mut visited = Vec::new();
+        {
+            let mut root = tree.root_mut();
+            let mut a = root.first_child().unwrap();
+            let mut children = a.children_mut();
+            while let Some(mut child) = children.next() {
+                visited.push(child.name().to_owned());
+                if child.name() == "C" {
+                    child.reborrow().parent().unwrap().append_new("D");
+                    child.append_new("E");
+                }
+            }
+        }
+
+        assert_eq!(visited, ["B", "C", "D"]);
+        let expected = tree_v7400! {
+            A: {
+                B: {},
+                C: {
+                    E: {},
+                },
+                D: {},
+            },
+        };
+        assert!(tree.strict_eq(&expected));
+    }
+
+    #[test]
+    #[should_panic]
+    fn rename_root() {
+        let mut tree = tree_v7400! {};
+        tree.root_mut().rename("Root");
+    }
+}
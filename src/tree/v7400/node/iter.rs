@@ -0,0 +1,270 @@
+//! Traversal iterators of nodes.
+
+use std::{collections::VecDeque, fmt, iter::FusedIterator};
+
+use crate::tree::v7400::{DepthFirstTraverseSubtree, NodeData, NodeHandle, NodeId, Tree};
+
+/// Implements `Iterator` for the iterator wrapping an `indextree` iterator.
+macro_rules! impl_raw_iter_wrapper {
+    ($(#[$meta:meta])* $name:ident, $raw:ident) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        pub struct $name<'a> {
+            /// Tree.
+            tree: &'a Tree,
+            /// Raw iterator.
+            iter: indextree::$raw<'a, NodeData>,
+        }
+
+        impl<'a> Iterator for $name<'a> {
+            type Item = NodeHandle<'a>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                let id = self.iter.next()?;
+                Some(NodeId::new(id, self.tree.tree_id()).to_handle(self.tree))
+            }
+        }
+
+        impl FusedIterator for $name<'_> {}
+
+        impl fmt::Debug for $name<'_> {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name)).finish()
+            }
+        }
+    };
+}
+
+impl_raw_iter_wrapper! {
+    /// An iterator of descendants of a node in preorder.
+    Descendants, Descendants
+}
+
+impl_raw_iter_wrapper! {
+    /// An iterator of ancestors of a node, from the parent to the root.
+    Ancestors, Ancestors
+}
+
+impl_raw_iter_wrapper! {
+    /// An iterator of following siblings of a node, from the nearest.
+    FollowingSiblings, FollowingSiblings
+}
+
+impl_raw_iter_wrapper! {
+    /// An iterator of preceding siblings of a node, from the nearest.
+    PrecedingSiblings, PrecedingSiblings
+}
+
+/// An iterator of descendants of a node in postorder.
+#[derive(Clone)]
+pub struct DescendantsPostOrder<'a> {
+    /// Tree.
+    tree: &'a Tree,
+    /// Traversal of the subtree.
+    traverse: DepthFirstTraverseSubtree,
+    /// The node whose descendants are iterated.
+    root: NodeId,
+}
+
+impl<'a> Iterator for DescendantsPostOrder<'a> {
+    type Item = NodeHandle<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.traverse.next_close_forward(self.tree)?;
+        if id == self.root {
+            return None;
+        }
+        Some(id.to_handle(self.tree))
+    }
+}
+
+impl FusedIterator for DescendantsPostOrder<'_> {}
+
+impl fmt::Debug for DescendantsPostOrder<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DescendantsPostOrder").finish()
+    }
+}
+
+/// An iterator of descendants of a node in breadth-first order.
+#[derive(Clone)]
+pub struct BreadthFirst<'a> {
+    /// Tree.
+    tree: &'a Tree,
+    /// Nodes whose children are not yet emitted.
+    queue: VecDeque<NodeId>,
+    /// Children being emitted.
+    children: Option<indextree::Children<'a, NodeData>>,
+}
+
+impl<'a> Iterator for BreadthFirst<'a> {
+    type Item = NodeHandle<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(child) = self.children.as_mut().and_then(Iterator::next) {
+                let child = NodeId::new(child, self.tree.tree_id());
+                self.queue.push_back(child);
+                return Some(child.to_handle(self.tree));
+            }
+            let parent = self.queue.pop_front()?;
+            self.children = Some(parent.raw().children(&self.tree.arena));
+        }
+    }
+}
+
+impl FusedIterator for BreadthFirst<'_> {}
+
+impl fmt::Debug for BreadthFirst<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BreadthFirst")
+            .field("queue", &self.queue)
+            .finish()
+    }
+}
+
+impl<'a> NodeHandle<'a> {
+    /// Returns an iterator of descendants (excluding the node itself) in
+    /// preorder.
+    #[inline]
+    #[must_use]
+    pub fn descendants(&self) -> Descendants<'a> {
+        let mut iter = self.node_id().raw().descendants(&self.tree().arena);
+        // Skip the node itself.
+        iter.next();
+        Descendants {
+            tree: self.tree(),
+            iter,
+        }
+    }
+
+    /// Returns an iterator of descendants (excluding the node itself) in
+    /// postorder.
+    #[inline]
+    #[must_use]
+    pub fn descendants_post_order(&self) -> DescendantsPostOrder<'a> {
+        DescendantsPostOrder {
+            tree: self.tree(),
+            traverse: self.node_id().traverse_depth_first(),
+            root: self.node_id(),
+        }
+    }
+
+    /// Returns an iterator of descendants (excluding the node itself) in
+    /// breadth-first order.
+    #[inline]
+    #[must_use]
+    pub fn breadth_first(&self) -> BreadthFirst<'a> {
+        BreadthFirst {
+            tree: self.tree(),
+            queue: std::iter::once(self.node_id()).collect(),
+            children: None,
+        }
+    }
+
+    /// Returns an iterator of ancestors (excluding the node itself), from the
+    /// parent to the root node.
+    #[inline]
+    #[must_use]
+    pub fn ancestors(&self) -> Ancestors<'a> {
+        let mut iter = self.node_id().raw().ancestors(&self.tree().arena);
+        // Skip the node itself.
+        iter.next();
+        Ancestors {
+            tree: self.tree(),
+            iter,
+        }
+    }
+
+    /// Returns an iterator of following siblings (excluding the node itself),
+    /// from the nearest.
+    #[inline]
+    #[must_use]
+    pub fn following_siblings(&self) -> FollowingSiblings<'a> {
+        let mut iter = self.node_id().raw().following_siblings(&self.tree().arena);
+        // Skip the node itself.
+        iter.next();
+        FollowingSiblings {
+            tree: self.tree(),
+            iter,
+        }
+    }
+
+    /// Returns an iterator of preceding siblings (excluding the node itself),
+    /// from the nearest.
+    #[inline]
+    #[must_use]
+    pub fn preceding_siblings(&self) -> PrecedingSiblings<'a> {
+        let mut iter = self.node_id().raw().preceding_siblings(&self.tree().arena);
+        // Skip the node itself.
+        iter.next();
+        PrecedingSiblings {
+            tree: self.tree(),
+            iter,
+        }
+    }
+
+    /// Returns the node path from the top-level node.
+    ///
+    /// This is a vector of pairs of node indices in siblings (i.e. the number
+    /// of preceding siblings) and node names, in the same shape as
+    /// [`SyntacticPosition::node_path`][`crate::pull_parser::SyntacticPosition::node_path`].
+    /// The path of the root node is empty.
+    #[must_use]
+    pub fn path(&self) -> Vec<(usize, String)> {
+        let mut path = std::iter::once(*self)
+            .chain(self.ancestors())
+            .filter(|node| node.parent().is_some())
+            .map(|node| (node.preceding_siblings().count(), node.name().to_owned()))
+            .collect::<Vec<_>>();
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tree_v7400;
+
+    #[test]
+    fn traversal_orders() {
+        let tree = tree_v7400! {
+            A: {
+                B: { D: {} },
+                C: {},
+            },
+            E: {},
+        };
+        let names = |iter: &mut dyn Iterator<Item = crate::tree::v7400::NodeHandle<'_>>| {
+            iter.map(|node| node.name()).collect::<String>()
+        };
+        let root = tree.root();
+        let a = root.first_child().unwrap();
+        let b = a.first_child().unwrap();
+        let c = b.next_sibling().unwrap();
+        let d = b.first_child().unwrap();
+
+        assert_eq!(names(&mut root.descendants()), "ABDCE");
+        assert_eq!(names(&mut root.descendants_post_order()), "DBCAE");
+        assert_eq!(names(&mut root.breadth_first()), "AEBCD");
+        assert_eq!(names(&mut a.descendants()), "BDC");
+        assert_eq!(names(&mut a.descendants_post_order()), "DBC");
+        assert_eq!(names(&mut a.breadth_first()), "BCD");
+        assert_eq!(names(&mut d.descendants()), "");
+        assert_eq!(names(&mut d.ancestors()), "BA");
+        assert_eq!(d.ancestors().count(), 3);
+        assert_eq!(names(&mut b.following_siblings()), "C");
+        assert_eq!(names(&mut c.preceding_siblings()), "B");
+        assert_eq!(names(&mut a.preceding_siblings()), "");
+
+        assert_eq!(
+            d.path(),
+            [(0, "A".into()), (0, "B".into()), (0, "D".into())]
+        );
+        assert_eq!(c.path(), [(0, "A".into()), (1, "C".into())]);
+        assert!(root.path().is_empty());
+    }
+}
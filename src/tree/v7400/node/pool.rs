@@ -0,0 +1,235 @@
+//! Attribute pool.
+
+use std::{mem, ops::Range};
+
+use string_interner::{
+    symbol::{Symbol, SymbolU32},
+    DefaultBackend, StringInterner,
+};
+
+use crate::low::v7400::AttributeValue;
+
+/// Symbol for interned string attribute value.
+// This is an opaque-typedef pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct StringSym(SymbolU32);
+
+impl Symbol for StringSym {
+    #[inline]
+    fn try_from_usize(v: usize) -> Option<Self> {
+        SymbolU32::try_from_usize(v).map(Self)
+    }
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self.0.to_usize()
+    }
+}
+
+/// Storage of attributes of the nodes in the compact storage mode.
+///
+/// String values are interned in the string table of the pool.
+/// Their slots in `values` are left empty, and the symbols are stored in
+/// `string_syms` in the same order.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AttributePool {
+    /// Attribute values.
+    values: Vec<AttributeValue>,
+    /// Symbols of string values.
+    string_syms: Vec<StringSym>,
+    /// String table.
+    strings: StringInterner<DefaultBackend<StringSym>>,
+}
+
+impl AttributePool {
+    /// Returns the numbers of the value slots and the string symbol slots.
+    #[inline]
+    #[must_use]
+    pub(crate) fn lens(&self) -> (usize, usize) {
+        (self.values.len(), self.string_syms.len())
+    }
+
+    /// Returns the capacities of the value slots and the string symbol slots.
+    #[inline]
+    #[must_use]
+    pub(crate) fn capacities(&self) -> (usize, usize) {
+        (self.values.capacity(), self.string_syms.capacity())
+    }
+
+    /// Returns the size of the strings in the string table in bytes.
+    #[must_use]
+    pub(crate) fn strings_bytes(&self) -> usize {
+        self.strings.into_iter().map(|(_, s)| s.len()).sum()
+    }
+
+    /// Adds the attributes to the end of the pool, and returns the ranges of
+    /// the value slots and the string symbol slots.
+    ///
+    /// Heap buffers of arrays and binaries are shrunk to fit.
+    pub(crate) fn push(
+        &mut self,
+        attributes: impl IntoIterator<Item = AttributeValue>,
+    ) -> (Range<usize>, Range<usize>) {
+        let values_start = self.values.len();
+        let strings_start = self.string_syms.len();
+        for mut v in attributes {
+            match &mut v {
+                AttributeValue::ArrBool(v) => v.shrink_to_fit(),
+                AttributeValue::ArrI32(v) => v.shrink_to_fit(),
+                AttributeValue::ArrI64(v) => v.shrink_to_fit(),
+                AttributeValue::ArrF32(v) => v.shrink_to_fit(),
+                AttributeValue::ArrF64(v) => v.shrink_to_fit(),
+                AttributeValue::Binary(v) => v.shrink_to_fit(),
+                AttributeValue::String(v) => {
+                    self.string_syms.push(self.strings.get_or_intern(&v));
+                    *v = String::new();
+                }
+                _ => {}
+            }
+            self.values.push(v);
+        }
+        (
+            values_start..self.values.len(),
+            strings_start..self.string_syms.len(),
+        )
+    }
+
+    /// Returns the value slots.
+    ///
+    /// String values in the slots are left empty.
+    #[inline]
+    #[must_use]
+    pub(crate) fn values(&self, values: Range<usize>) -> &[AttributeValue] {
+        &self.values[values]
+    }
+
+    /// Returns the string symbol slots.
+    #[inline]
+    #[must_use]
+    pub(crate) fn string_syms(&self, strings: Range<usize>) -> &[StringSym] {
+        &self.string_syms[strings]
+    }
+
+    /// Returns the interned string.
+    #[inline]
+    #[must_use]
+    pub(crate) fn resolve(&self, sym: StringSym) -> &str {
+        self.strings
+            .resolve(sym)
+            .expect("Should never fail: the string is interned")
+    }
+
+    /// Returns the mutable value slots.
+    ///
+    /// The string values should have been restored by
+    /// [`restore_strings`][`Self::restore_strings`].
+    #[inline]
+    #[must_use]
+    pub(crate) fn values_mut(&mut self, values: Range<usize>) -> &mut [AttributeValue] {
+        &mut self.values[values]
+    }
+
+    /// Returns the clone of the attributes with the string values.
+    #[must_use]
+    pub(crate) fn materialize(
+        &self,
+        values: Range<usize>,
+        strings: Range<usize>,
+    ) -> Vec<AttributeValue> {
+        let mut attributes = self.values[values].to_vec();
+        self.fill_strings(&mut attributes, strings);
+        attributes
+    }
+
+    /// Moves the attributes out of the pool, with the string values.
+    ///
+    /// The slots are left unused.
+    #[must_use]
+    pub(crate) fn take(
+        &mut self,
+        values: Range<usize>,
+        strings: Range<usize>,
+    ) -> Vec<AttributeValue> {
+        let mut attributes = self.values[values]
+            .iter_mut()
+            .map(|v| mem::replace(v, AttributeValue::Bool(false)))
+            .collect::<Vec<_>>();
+        self.fill_strings(&mut attributes, strings);
+        attributes
+    }
+
+    /// Puts the string values back into the value slots.
+    ///
+    /// The string symbol slots are left unused.
+    pub(crate) fn restore_strings(&mut self, values: Range<usize>, strings: Range<usize>) {
+        let mut attributes = mem::take(&mut self.values);
+        self.fill_strings(&mut attributes[values], strings);
+        self.values = attributes;
+    }
+
+    /// Sets the string values to the empty string slots.
+    fn fill_strings(&self, attributes: &mut [AttributeValue], strings: Range<usize>) {
+        let empty_slots = attributes.iter_mut().filter_map(|v| match v {
+            AttributeValue::String(s) => Some(s),
+            _ => None,
+        });
+        for (slot, &sym) in empty_slots.zip(&self.string_syms[strings]) {
+            *slot = self.resolve(sym).to_owned();
+        }
+    }
+
+    /// Moves the slots to the end of the other pool sharing the string table,
+    /// and returns the new ranges.
+    ///
+    /// The slots in this pool are left unused.
+    pub(crate) fn move_slots_to(
+        &mut self,
+        other: &mut Self,
+        values: Range<usize>,
+        strings: Range<usize>,
+    ) -> (Range<usize>, Range<usize>) {
+        let values_start = other.values.len();
+        let strings_start = other.string_syms.len();
+        other.values.extend(
+            self.values[values]
+                .iter_mut()
+                .map(|v| mem::replace(v, AttributeValue::Bool(false))),
+        );
+        other
+            .string_syms
+            .extend_from_slice(&self.string_syms[strings]);
+        (
+            values_start..other.values.len(),
+            strings_start..other.string_syms.len(),
+        )
+    }
+
+    /// Creates an empty pool sharing the string table with this pool.
+    ///
+    /// The string table is moved to the new pool.
+    #[must_use]
+    pub(crate) fn take_string_table(&mut self) -> Self {
+        Self {
+            values: Vec::new(),
+            string_syms: Vec::new(),
+            strings: mem::replace(&mut self.strings, StringInterner::new()),
+        }
+    }
+
+    /// Shrinks the capacities of the pool to fit.
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.values.shrink_to_fit();
+        self.string_syms.shrink_to_fit();
+        self.strings.shrink_to_fit();
+    }
+}
+
+impl Default for AttributePool {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            string_syms: Vec::new(),
+            strings: StringInterner::new(),
+        }
+    }
+}
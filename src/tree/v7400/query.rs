@@ -0,0 +1,410 @@
+//! Node query.
+//!
+//! A query is a path-like expression to select nodes in a tree.
+//!
+//! # Syntax
+//!
+//! A query consists of steps separated by `/` or `//`.
+//!
+//! * `/` selects children of the nodes selected by the previous step.
+//! * `//` selects descendants of the nodes selected by the previous step.
+//! * A query starting with `/` or `//` is absolute, i.e. evaluated from the
+//!   root node of the tree.
+//!   Otherwise, the query is evaluated from the given node.
+//!
+//! Each step is a node name or `*` (which matches any name), followed by zero
+//! or more predicates in brackets:
+//!
+//! * `[n]` selects `n`-th (0-based) node among the nodes selected from the
+//!   same context node.
+//!   Negative `n` counts from the last, i.e. `[-1]` is the last one.
+//! * `[n=value]` selects nodes whose `n`-th (0-based) attribute equals to the
+//!   value.
+//!     + A value is a string quoted with `'` or `"`, a number, `true`, or
+//!       `false`.
+//!       In strings, backslash escapes the next character.
+//!     + A string matches string attributes.
+//!     + A number matches integer and floating point attributes with exactly
+//!       the same numeric value (integers are not rounded to floating point
+//!       numbers).
+//!     + `true` and `false` match boolean attributes.
+//!
+//! Predicates are applied in order, so `Geometry[2='Mesh'][0]` is the first
+//! `Geometry` node whose attribute 2 is `"Mesh"`, while `Geometry[0][2='Mesh']`
+//! is the first `Geometry` node if its attribute 2 is `"Mesh"`.
+//!
+//! # Examples
+//!
+//! ```
+//! use fbxcel::tree_v7400;
+//!
+//! let tree = tree_v7400! {
+//!     Objects: {
+//!         Geometry: [1i64, "Cube\u{0}\u{1}Geometry", "Mesh"] {
+//!             Vertices: [vec![0.0f64; 24]] {},
+//!         },
+//!         Geometry: [2i64, "Line\u{0}\u{1}Geometry", "Line"] {},
+//!         Model: [3i64, "Cube\u{0}\u{1}Model", "Mesh"] {
+//!             Properties70: {
+//!                 P: ["Lcl Translation", "Lcl Translation", "", "A", 0.0f64, 1.0f64, 2.0f64] {},
+//!             },
+//!         },
+//!     },
+//! };
+//!
+//! let vertices = tree
+//!     .select("Objects/Geometry[2='Mesh']/Vertices")?
+//!     .collect::<Vec<_>>();
+//! assert_eq!(vertices.len(), 1);
+//!
+//! let translation = tree
+//!     .select("//P[0='Lcl Translation']")?
+//!     .next()
+//!     .expect("should exist");
+//! assert_eq!(translation.attributes().len(), 7);
+//!
+//! assert_eq!(tree.select("/Objects/*[-1]")?.next().unwrap().name(), "Model");
+//! # Ok::<_, fbxcel::tree::v7400::QueryError>(())
+//! ```
+
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use crate::{
+    low::v7400::AttributeValue,
+    tree::v7400::{NodeHandle, NodeId, Tree},
+};
+
+pub use self::error::QueryError;
+
+mod error;
+mod parser;
+
+/// Parsed node query.
+///
+/// See [module documentation][`self`] for syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// Whether the query is evaluated from the root node.
+    absolute: bool,
+    /// Steps.
+    steps: Vec<Step>,
+}
+
+impl Query {
+    /// Parses the given query string.
+    #[inline]
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        parser::parse(query)
+    }
+
+    /// Returns an iterator of nodes selected by the query.
+    ///
+    /// Relative queries are evaluated from the given node, and absolute
+    /// queries are evaluated from the root node of the tree.
+    ///
+    /// Selected nodes are deduplicated.
+    #[must_use]
+    pub fn select<'a>(&self, node: NodeHandle<'a>) -> Select<'a> {
+        let tree = node.tree();
+        let mut current = vec![if self.absolute { tree.root() } else { node }];
+        for step in &self.steps {
+            current = step.select(tree, &current);
+            if current.is_empty() {
+                break;
+            }
+        }
+
+        Select {
+            iter: current.into_iter(),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// A step of a query.
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    /// Axis.
+    axis: Axis,
+    /// Node name, or `None` for any names.
+    name: Option<String>,
+    /// Predicates.
+    predicates: Vec<Predicate>,
+}
+
+impl Step {
+    /// Selects nodes from the given context nodes.
+    fn select<'a>(&self, tree: &'a Tree, contexts: &[NodeHandle<'a>]) -> Vec<NodeHandle<'a>> {
+        let name_sym = match &self.name {
+            Some(name) => match tree.node_name_sym(name) {
+                Some(sym) => Some(sym),
+                None => return Vec::new(),
+            },
+            None => None,
+        };
+        let name_matches =
+            |node: &NodeHandle<'_>| name_sym.map_or(true, |sym| node.name_sym() == sym);
+
+        let mut seen = HashSet::new();
+        let mut selected = Vec::new();
+        for context in contexts {
+            let mut candidates: Vec<_> = match self.axis {
+                Axis::Child => match &self.name {
+                    Some(name) => context.children_by_name(name).collect(),
+                    None => context.children().collect(),
+                },
+                Axis::Descendant => context
+                    .node_id()
+                    .raw()
+                    .descendants(&tree.arena)
+                    .skip(1)
+                    .map(|id| NodeId::new(id, tree.tree_id()).to_handle(tree))
+                    .filter(name_matches)
+                    .collect(),
+            };
+            for predicate in &self.predicates {
+                candidates = predicate.apply(candidates);
+            }
+            selected.extend(
+                candidates
+                    .into_iter()
+                    .filter(|node| seen.insert(node.node_id())),
+            );
+        }
+
+        selected
+    }
+}
+
+/// Axis of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Axis {
+    /// Children.
+    Child,
+    /// Descendants.
+    Descendant,
+}
+
+/// A predicate of a step.
+#[derive(Debug, Clone, PartialEq)]
+enum Predicate {
+    /// Node index among candidates.
+    Index(isize),
+    /// Attribute value at the given index.
+    Attribute(usize, Literal),
+}
+
+impl Predicate {
+    /// Filters the given candidates.
+    fn apply<'a>(&self, mut candidates: Vec<NodeHandle<'a>>) -> Vec<NodeHandle<'a>> {
+        match self {
+            Predicate::Index(index) => {
+                let index = if *index >= 0 {
+                    Some(index.unsigned_abs())
+                } else {
+                    candidates.len().checked_sub(index.unsigned_abs())
+                };
+                match index {
+                    Some(index) if index < candidates.len() => {
+                        vec![candidates.swap_remove(index)]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            Predicate::Attribute(index, value) => {
+                candidates.retain(|node| {
+                    node.attribute_values()
+                        .nth(*index)
+                        .map_or(false, |attr| value.matches(&attr))
+                });
+                candidates
+            }
+        }
+    }
+}
+
+/// A literal value in a query.
+#[derive(Debug, Clone, PartialEq)]
+enum Literal {
+    /// Boolean.
+    Bool(bool),
+    /// Integer.
+    Int(i64),
+    /// Floating point number.
+    Float(f64),
+    /// String.
+    String(String),
+}
+
+impl Literal {
+    /// Checks whether the given attribute value matches the literal.
+    fn matches(&self, attr: &AttributeValue) -> bool {
+        match *self {
+            Literal::Bool(v) => attr.get_bool() == Some(v),
+            Literal::Int(v) => match attr {
+                AttributeValue::I16(attr) => i64::from(*attr) == v,
+                AttributeValue::I32(attr) => i64::from(*attr) == v,
+                AttributeValue::I64(attr) => *attr == v,
+                AttributeValue::F32(attr) => float_eq_int(f64::from(*attr), v),
+                AttributeValue::F64(attr) => float_eq_int(*attr, v),
+                _ => false,
+            },
+            Literal::Float(v) => match attr {
+                AttributeValue::I16(attr) => f64::from(*attr) == v,
+                AttributeValue::I32(attr) => f64::from(*attr) == v,
+                AttributeValue::I64(attr) => float_eq_int(v, *attr),
+                AttributeValue::F32(attr) => f64::from(*attr) == v,
+                AttributeValue::F64(attr) => *attr == v,
+                _ => false,
+            },
+            Literal::String(ref v) => attr.get_string() == Some(v.as_str()),
+        }
+    }
+}
+
+/// Checks whether the floating point number is equal to the integer, without
+/// rounding either of them.
+fn float_eq_int(f: f64, i: i64) -> bool {
+    /// 2^63, which is exactly representable as `f64`.
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    f.fract() == 0.0 && (-LIMIT..LIMIT).contains(&f) && f as i64 == i
+}
+
+/// An iterator of nodes selected by a query.
+#[derive(Clone)]
+pub struct Select<'a> {
+    /// Selected nodes.
+    iter: std::vec::IntoIter<NodeHandle<'a>>,
+}
+
+impl<'a> Iterator for Select<'a> {
+    type Item = NodeHandle<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Select<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl ExactSizeIterator for Select<'_> {}
+
+impl std::iter::FusedIterator for Select<'_> {}
+
+impl fmt::Debug for Select<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Select")
+            .field("len", &self.iter.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tree_v7400;
+
+    /// Returns names and first attributes of the selected nodes.
+    fn select(tree: &Tree, query: &str) -> Vec<(String, Option<AttributeValue>)> {
+        tree.select(query)
+            .expect("Query should be valid")
+            .map(|node| (node.name().to_owned(), node.attributes().first().cloned()))
+            .collect()
+    }
+
+    #[test]
+    fn paths_and_predicates() {
+        let tree = tree_v7400! {
+            A: [0i32] {
+                B: [1i32, "x"] {
+                    C: [2i32] {},
+                },
+                B: [3i32, "y"] {
+                    B: [4i32, "x"] {},
+                },
+            },
+            C: [5.0f64, true] {},
+        };
+
+        assert_eq!(select(&tree, "A/B").len(), 2);
+        assert_eq!(
+            select(&tree, "A/B[1='x']"),
+            [("B".into(), Some(1i32.into()))]
+        );
+        assert_eq!(select(&tree, "//B[1=\"x\"]").len(), 2);
+        assert_eq!(select(&tree, "//B[-1]"), [("B".into(), Some(4i32.into()))]);
+        assert_eq!(select(&tree, "A/*[1]/*"), [("B".into(), Some(4i32.into()))]);
+        assert_eq!(select(&tree, "//C").len(), 2);
+        assert_eq!(select(&tree, "A//C"), [("C".into(), Some(2i32.into()))]);
+        assert_eq!(select(&tree, "C[0=5][1=true]").len(), 1);
+        assert_eq!(
+            select(&tree, "//*[0=3.0]"),
+            [("B".into(), Some(3i32.into()))]
+        );
+        assert!(select(&tree, "A/B[5]").is_empty());
+        assert!(select(&tree, "A/Unknown").is_empty());
+        // Nested matches are deduplicated.
+        assert_eq!(select(&tree, "//*//B").len(), 3);
+        // Absolute queries ignore the context node.
+        let b = tree.select("A/B").unwrap().next().unwrap();
+        assert_eq!(b.select("C").unwrap().count(), 1);
+        assert_eq!(b.select("/C").unwrap().count(), 1);
+        assert_eq!(b.select("/A").unwrap().count(), 1);
+        assert_eq!(b.select("A").unwrap().count(), 0);
+    }
+
+    #[test]
+    fn exact_number_comparison() {
+        let tree = tree_v7400! {
+            A: [9_007_199_254_740_993i64] {},
+            B: [9_007_199_254_740_992.0f64] {},
+            C: [i64::MAX] {},
+        };
+        // Integers and floating point numbers match only if they are equal
+        // without rounding.
+        assert_eq!(select(&tree, "A[0=9007199254740993]").len(), 1);
+        assert!(select(&tree, "A[0=9007199254740992]").is_empty());
+        assert!(select(&tree, "A[0=9007199254740992.0]").is_empty());
+        assert_eq!(select(&tree, "B[0=9007199254740992]").len(), 1);
+        assert!(select(&tree, "B[0=9007199254740993]").is_empty());
+        assert!(select(&tree, "C[0=9223372036854775808.0]").is_empty());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Query::parse(""), Err(QueryError::EmptyName(0)));
+        assert_eq!(Query::parse("A/"), Err(QueryError::EmptyName(2)));
+        assert_eq!(Query::parse("A[0='x'"), Err(QueryError::UnexpectedEnd));
+        assert_eq!(Query::parse("A[0='x"), Err(QueryError::UnexpectedEnd));
+        assert_eq!(
+            Query::parse("A[x]"),
+            Err(QueryError::InvalidNumber(2, String::new()))
+        );
+        assert_eq!(
+            Query::parse("A[0=1.2.3]"),
+            Err(QueryError::InvalidNumber(4, "1.2.3".into()))
+        );
+        assert_eq!(Query::parse("A]"), Err(QueryError::UnexpectedChar(1, ']')));
+        assert!(Query::parse(r"A[ 0 = 'it\'s' ]/B[-2]").is_ok());
+    }
+}
@@ -0,0 +1,36 @@
+//! Query error.
+
+use std::{error, fmt};
+
+/// Query parse error.
+///
+/// Positions are byte offsets in the query string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum QueryError {
+    /// Query ended unexpectedly.
+    UnexpectedEnd,
+    /// Unexpected character.
+    UnexpectedChar(usize, char),
+    /// Node name is empty.
+    EmptyName(usize),
+    /// Invalid number.
+    InvalidNumber(usize, String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::UnexpectedEnd => f.write_str("Query ended unexpectedly"),
+            QueryError::UnexpectedChar(pos, c) => {
+                write!(f, "Unexpected character {:?} at position {}", c, pos)
+            }
+            QueryError::EmptyName(pos) => write!(f, "Empty node name at position {}", pos),
+            QueryError::InvalidNumber(pos, s) => {
+                write!(f, "Invalid number {:?} at position {}", s, pos)
+            }
+        }
+    }
+}
+
+impl error::Error for QueryError {}
@@ -0,0 +1,199 @@
+//! Query string parser.
+
+use std::{iter::Peekable, str::CharIndices};
+
+use super::{Axis, Literal, Predicate, Query, QueryError, Step};
+
+/// Query string parser.
+struct QueryParser<'a> {
+    /// Source string.
+    source: &'a str,
+    /// Characters.
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> QueryParser<'a> {
+    /// Returns the position of the next character.
+    fn position(&mut self) -> usize {
+        self.chars.peek().map_or(self.source.len(), |&(pos, _)| pos)
+    }
+
+    /// Returns the next character without consuming it.
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    /// Consumes the next character if it is the given one.
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the given character, or returns an error.
+    fn expect(&mut self, expected: char) -> Result<(), QueryError> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((pos, c)) => Err(QueryError::UnexpectedChar(pos, c)),
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+
+    /// Skips whitespaces.
+    fn skip_whitespaces(&mut self) {
+        while self.peek().map_or(false, char::is_whitespace) {
+            self.chars.next();
+        }
+    }
+
+    /// Consumes characters while the predicate holds, and returns them.
+    fn take_while(&mut self, mut pred: impl FnMut(char) -> bool) -> &'a str {
+        let start = self.position();
+        while self.peek().map_or(false, &mut pred) {
+            self.chars.next();
+        }
+        let end = self.position();
+        &self.source[start..end]
+    }
+
+    /// Parses the whole query.
+    fn parse_query(&mut self) -> Result<Query, QueryError> {
+        let mut steps = Vec::new();
+        let absolute = self.peek() == Some('/');
+        let mut axis = self.parse_separator().unwrap_or(Axis::Child);
+        loop {
+            steps.push(self.parse_step(axis)?);
+            match self.parse_separator() {
+                Some(next) => axis = next,
+                None => break,
+            }
+        }
+        if let Some((pos, c)) = self.chars.next() {
+            return Err(QueryError::UnexpectedChar(pos, c));
+        }
+
+        Ok(Query { absolute, steps })
+    }
+
+    /// Parses `/` or `//`.
+    fn parse_separator(&mut self) -> Option<Axis> {
+        if !self.eat('/') {
+            return None;
+        }
+        if self.eat('/') {
+            Some(Axis::Descendant)
+        } else {
+            Some(Axis::Child)
+        }
+    }
+
+    /// Parses a step.
+    fn parse_step(&mut self, axis: Axis) -> Result<Step, QueryError> {
+        let name_pos = self.position();
+        let name = self.take_while(|c| !matches!(c, '/' | '[' | ']'));
+        let name = match name {
+            "" => return Err(QueryError::EmptyName(name_pos)),
+            "*" => None,
+            name => Some(name.to_owned()),
+        };
+        let mut predicates = Vec::new();
+        while self.eat('[') {
+            predicates.push(self.parse_predicate()?);
+        }
+
+        Ok(Step {
+            axis,
+            name,
+            predicates,
+        })
+    }
+
+    /// Parses a predicate, after the opening `[`.
+    fn parse_predicate(&mut self) -> Result<Predicate, QueryError> {
+        self.skip_whitespaces();
+        let index_pos = self.position();
+        let index = self.take_while(|c| c == '-' || c.is_ascii_digit());
+        self.skip_whitespaces();
+        let predicate = if self.eat('=') {
+            let index = index
+                .parse::<usize>()
+                .map_err(|_| QueryError::InvalidNumber(index_pos, index.to_owned()))?;
+            self.skip_whitespaces();
+            let value = self.parse_literal()?;
+            Predicate::Attribute(index, value)
+        } else {
+            let index = index
+                .parse::<isize>()
+                .map_err(|_| QueryError::InvalidNumber(index_pos, index.to_owned()))?;
+            Predicate::Index(index)
+        };
+        self.skip_whitespaces();
+        self.expect(']')?;
+
+        Ok(predicate)
+    }
+
+    /// Parses a literal.
+    fn parse_literal(&mut self) -> Result<Literal, QueryError> {
+        match self.peek() {
+            Some(quote @ ('\'' | '"')) => {
+                self.chars.next();
+                self.parse_string(quote).map(Literal::String)
+            }
+            Some(_) => {
+                let pos = self.position();
+                let token = self.take_while(|c| {
+                    c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.' | '_')
+                });
+                match token {
+                    "" => match self.chars.next() {
+                        Some((pos, c)) => Err(QueryError::UnexpectedChar(pos, c)),
+                        None => Err(QueryError::UnexpectedEnd),
+                    },
+                    "true" => Ok(Literal::Bool(true)),
+                    "false" => Ok(Literal::Bool(false)),
+                    token => {
+                        if let Ok(v) = token.parse::<i64>() {
+                            Ok(Literal::Int(v))
+                        } else if let Ok(v) = token.parse::<f64>() {
+                            Ok(Literal::Float(v))
+                        } else {
+                            Err(QueryError::InvalidNumber(pos, token.to_owned()))
+                        }
+                    }
+                }
+            }
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+
+    /// Parses a string literal, after the opening quote.
+    ///
+    /// A backslash escapes the following character.
+    fn parse_string(&mut self, quote: char) -> Result<String, QueryError> {
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, c)) if c == quote => return Ok(s),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, c)) => s.push(c),
+                    None => return Err(QueryError::UnexpectedEnd),
+                },
+                Some((_, c)) => s.push(c),
+                None => return Err(QueryError::UnexpectedEnd),
+            }
+        }
+    }
+}
+
+/// Parses the given query string.
+pub(super) fn parse(source: &str) -> Result<Query, QueryError> {
+    QueryParser {
+        source,
+        chars: source.char_indices().peekable(),
+    }
+    .parse_query()
+}
@@ -0,0 +1,353 @@
+//! Serde support for the tree.
+//!
+//! See the documentation of [`Tree`] for the representation.
+
+use std::fmt;
+
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    low::v7400::AttributeValue,
+    tree::v7400::{NodeHandle, NodeId, Tree},
+};
+
+/// Field names of a tree.
+const TREE_FIELDS: &[&str] = &["children"];
+
+/// Field names of a node.
+const NODE_FIELDS: &[&str] = &["name", "attributes", "children"];
+
+/// Field of a tree.
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum TreeField {
+    /// Children of the root node.
+    Children,
+    /// Unknown field.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Field of a node.
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum NodeField {
+    /// Node name.
+    Name,
+    /// Node attributes.
+    Attributes,
+    /// Child nodes.
+    Children,
+    /// Unknown field.
+    #[serde(other)]
+    Unknown,
+}
+
+impl Serialize for Tree {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Tree", TREE_FIELDS.len())?;
+        state.serialize_field("children", &SerializeChildren(self.root()))?;
+        state.end()
+    }
+}
+
+/// Serializable children of a node.
+struct SerializeChildren<'a>(NodeHandle<'a>);
+
+impl Serialize for SerializeChildren<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.0.children().map(SerializeNode))
+    }
+}
+
+/// Serializable attributes of a node.
+struct SerializeAttributes<'a>(NodeHandle<'a>);
+
+impl Serialize for SerializeAttributes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.0.attribute_values())
+    }
+}
+
+/// Serializable node.
+struct SerializeNode<'a>(NodeHandle<'a>);
+
+impl Serialize for SerializeNode<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Node", NODE_FIELDS.len())?;
+        state.serialize_field("name", self.0.name())?;
+        state.serialize_field("attributes", &SerializeAttributes(self.0))?;
+        state.serialize_field("children", &SerializeChildren(self.0))?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Tree {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("Tree", TREE_FIELDS, TreeVisitor)
+    }
+}
+
+/// Visitor for a tree.
+struct TreeVisitor;
+
+impl<'de> Visitor<'de> for TreeVisitor {
+    type Value = Tree;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an FBX data tree")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut tree = Tree::default();
+        let root = tree.root().node_id();
+        seq.next_element_seed(ChildrenSeed::new(&mut tree, root))?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        Ok(tree)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut tree = Tree::default();
+        let root = tree.root().node_id();
+        let mut has_children = false;
+        while let Some(field) = map.next_key()? {
+            match field {
+                TreeField::Children => {
+                    if has_children {
+                        return Err(de::Error::duplicate_field("children"));
+                    }
+                    map.next_value_seed(ChildrenSeed::new(&mut tree, root))?;
+                    has_children = true;
+                }
+                TreeField::Unknown => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(tree)
+    }
+}
+
+/// Seed to deserialize nodes and append them to the parent node.
+struct ChildrenSeed<'a> {
+    /// Tree.
+    tree: &'a mut Tree,
+    /// Parent node.
+    parent: NodeId,
+}
+
+impl<'a> ChildrenSeed<'a> {
+    /// Creates a new `ChildrenSeed`.
+    #[inline]
+    #[must_use]
+    fn new(tree: &'a mut Tree, parent: NodeId) -> Self {
+        Self { tree, parent }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for ChildrenSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ChildrenSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence of FBX nodes")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        while seq
+            .next_element_seed(NodeSeed::new(&mut *self.tree, self.parent))?
+            .is_some()
+        {}
+        Ok(())
+    }
+}
+
+/// Seed to deserialize a node and append it to the parent node.
+struct NodeSeed<'a> {
+    /// Tree.
+    tree: &'a mut Tree,
+    /// Parent node.
+    parent: NodeId,
+}
+
+impl<'a> NodeSeed<'a> {
+    /// Creates a new `NodeSeed`.
+    #[inline]
+    #[must_use]
+    fn new(tree: &'a mut Tree, parent: NodeId) -> Self {
+        Self { tree, parent }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for NodeSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("Node", NODE_FIELDS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for NodeSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an FBX node")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        /// Expected length.
+        const EXPECTED: &str = "a sequence of node name, attributes, and children";
+
+        let name: String = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &EXPECTED))?;
+        let attributes: Vec<AttributeValue> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &EXPECTED))?;
+        let node = self.tree.append_new(self.parent, &name);
+        self.tree.set_attributes_vec(node, attributes);
+        seq.next_element_seed(ChildrenSeed::new(self.tree, node))?
+            .ok_or_else(|| de::Error::invalid_length(2, &EXPECTED))?;
+        Ok(())
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        // Fields can come in any order, so create the node first and set the
+        // name later.
+        let node = self.tree.append_new(self.parent, "");
+        let mut has_name = false;
+        let mut has_attributes = false;
+        let mut has_children = false;
+        while let Some(field) = map.next_key()? {
+            match field {
+                NodeField::Name => {
+                    if has_name {
+                        return Err(de::Error::duplicate_field("name"));
+                    }
+                    let name: String = map.next_value()?;
+                    self.tree
+                        .get_node_mut(node)
+                        .expect("Should never fail: the node is just created")
+                        .rename(&name);
+                    has_name = true;
+                }
+                NodeField::Attributes => {
+                    if has_attributes {
+                        return Err(de::Error::duplicate_field("attributes"));
+                    }
+                    self.tree.set_attributes_vec(node, map.next_value()?);
+                    has_attributes = true;
+                }
+                NodeField::Children => {
+                    if has_children {
+                        return Err(de::Error::duplicate_field("children"));
+                    }
+                    map.next_value_seed(ChildrenSeed::new(self.tree, node))?;
+                    has_children = true;
+                }
+                NodeField::Unknown => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if !has_name {
+            return Err(de::Error::missing_field("name"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tree::v7400::Tree, tree_v7400};
+
+    #[test]
+    fn json_representation() {
+        let tree = tree_v7400! {
+            Node0: [42i16] {
+                Node0_0: {},
+            },
+        };
+        let json = serde_json::to_string(&tree).unwrap();
+        assert_eq!(
+            json,
+            r#"{"children":[{"name":"Node0","attributes":[{"I16":42}],"children":[{"name":"Node0_0","attributes":[],"children":[]}]}]}"#
+        );
+
+        // Fields in other orders, omitted fields, and unknown fields.
+        let json = r#"{
+            "children": [
+                {
+                    "children": [{"name": "Node0_0", "comment": "ignored"}],
+                    "name": "Node0",
+                    "attributes": [{"I16": 42}]
+                }
+            ]
+        }"#;
+        let deserialized: Tree = serde_json::from_str(json).unwrap();
+        assert!(deserialized.strict_eq(&tree));
+    }
+
+    #[test]
+    fn invalid_json() {
+        for invalid in [
+            r#"{"children":[{"attributes":[]}]}"#,
+            r#"{"children":[{"name":"A","name":"B"}]}"#,
+            r#"{"children":[{"name":"A","attributes":[42]}]}"#,
+            r#"{"children":{}}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<Tree>(invalid).is_err(),
+                "{:?}",
+                invalid
+            );
+        }
+    }
+}
@@ -1,5 +1,21 @@
 //! FBX writer.
 //!
 //! Enabled by `writer` feature.
+//!
+//! # Using writer
+//!
+//! If you don't care about the writer implementation for each FBX version,
+//! you can use [`any::AnyWriter`] to get the writer appropriate for the target
+//! FBX version.
+//!
+//! To write a whole tree, `tree::any::AnyTree::write_to()` is also available
+//! when `tree` feature is enabled.
+//!
+//! To use the writer for the specific version directly, see the documentation
+//! of each writer module such as [`v7400::binary`].
+
+pub use self::version::WriterVersion;
 
+pub mod any;
 pub mod v7400;
+mod version;
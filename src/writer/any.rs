@@ -0,0 +1,74 @@
+//! Types and functions for all supported versions.
+
+use std::io::{Seek, Write};
+
+use crate::{
+    low::FbxVersion,
+    writer::{self, WriterVersion},
+};
+
+pub use self::error::{Error, Result};
+
+mod error;
+
+/// FBX writer type with any supported version.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AnyWriter<W: Write> {
+    /// FBX 7.4 or later.
+    V7400(writer::v7400::binary::Writer<W>),
+}
+
+impl<W: Write + Seek> AnyWriter<W> {
+    /// Creates a writer appropriate for the given FBX version, and writes FBX
+    /// file header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fbxcel::{low::FbxVersion, writer::any::AnyWriter};
+    ///
+    /// let sink = std::io::Cursor::new(Vec::new());
+    /// match AnyWriter::new(sink, FbxVersion::V7_4)? {
+    ///     AnyWriter::V7400(writer) => {
+    ///         // You got a writer! Do what you want!
+    ///         # let _ = writer;
+    ///     }
+    ///     // `AnyWriter` is nonexhaustive.
+    ///     // You should handle new unknown writer version case.
+    ///     _ => panic!("Unsupported FBX writer is required"),
+    /// }
+    /// # Ok::<_, fbxcel::writer::any::Error>(())
+    /// ```
+    pub fn new(sink: W, fbx_version: FbxVersion) -> Result<Self> {
+        match writer_version(fbx_version)? {
+            WriterVersion::V7400 => {
+                let writer = writer::v7400::binary::Writer::new(sink, fbx_version)?;
+                Ok(AnyWriter::V7400(writer))
+            }
+        }
+    }
+
+    /// Returns the writer version.
+    #[inline]
+    #[must_use]
+    pub fn writer_version(&self) -> WriterVersion {
+        match self {
+            AnyWriter::V7400(_) => WriterVersion::V7400,
+        }
+    }
+
+    /// Returns the FBX version.
+    #[inline]
+    #[must_use]
+    pub fn fbx_version(&self) -> FbxVersion {
+        match self {
+            AnyWriter::V7400(writer) => writer.fbx_version(),
+        }
+    }
+}
+
+/// Returns the writer version for the FBX data.
+fn writer_version(fbx_version: FbxVersion) -> Result<WriterVersion> {
+    WriterVersion::from_fbx_version(fbx_version).ok_or(Error::UnsupportedVersion(fbx_version))
+}
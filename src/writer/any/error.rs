@@ -0,0 +1,44 @@
+//! Error and result types for `writer::any` module.
+
+use std::{error, fmt};
+
+use crate::{low::FbxVersion, writer};
+
+/// AnyWriter result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Error.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Unsupported version.
+    UnsupportedVersion(FbxVersion),
+    /// Writer error for FBX 7.4 or later.
+    V7400(writer::v7400::binary::Error),
+}
+
+impl error::Error for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::V7400(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnsupportedVersion(ver) => write!(f, "Unsupported FBX version: {:?}", ver),
+            Error::V7400(e) => write!(f, "Writer error: {}", e),
+        }
+    }
+}
+
+impl From<writer::v7400::binary::Error> for Error {
+    #[inline]
+    fn from(e: writer::v7400::binary::Error) -> Self {
+        Error::V7400(e)
+    }
+}
@@ -97,19 +97,32 @@ use std::{
 
 use log::{debug, trace};
 
-use crate::low::{v7400::NodeHeader, FbxVersion, MAGIC};
+use crate::{
+    low::{v7400::NodeHeader, FbxVersion, MAGIC},
+    pull_parser::{
+        error::DataError,
+        v7400::{FromParser, Parser},
+        Error as ParserError,
+    },
+};
 
+use self::sink::Sink;
 pub use self::{
     attributes::AttributesWriter,
-    error::{CompressionError, Error, Result},
+    error::{CompressionError, Error, PatchError, Result},
+    faults::FaultInjection,
     footer::{FbxFooter, FbxFooterPaddingLength},
+    patch::AttributePatcher,
 };
 
 mod macros;
 
 mod attributes;
 mod error;
+mod faults;
 mod footer;
+mod patch;
+mod sink;
 
 /// Binary writer.
 ///
@@ -117,11 +130,13 @@ mod footer;
 #[derive(Debug, Clone)]
 pub struct Writer<W: Write> {
     /// Writer destination.
-    sink: W,
+    sink: Sink<W>,
     /// FBX version.
     fbx_version: FbxVersion,
     /// Node header positions not yet closed.
     open_nodes: Vec<OpenNode>,
+    /// Fault injection options.
+    faults: FaultInjection,
 }
 
 impl<W: Write + Seek> Writer<W> {
@@ -136,18 +151,51 @@ impl<W: Write + Seek> Writer<W> {
         sink.seek(SeekFrom::Start(0))?;
         sink.write_all(MAGIC)?;
         sink.write_all(&fbx_version.raw().to_le_bytes())?;
+        let position = (MAGIC.len() + std::mem::size_of::<u32>()) as u64;
 
         Ok(Self {
-            sink,
+            sink: Sink::new(sink, position),
             fbx_version,
             open_nodes: Vec::new(),
+            faults: FaultInjection::default(),
         })
     }
 
+    /// Returns the FBX version.
+    #[inline]
+    #[must_use]
+    pub fn fbx_version(&self) -> FbxVersion {
+        self.fbx_version
+    }
+
+    /// Returns the fault injection options.
+    #[inline]
+    #[must_use]
+    pub fn fault_injection(&self) -> &FaultInjection {
+        &self.faults
+    }
+
+    /// Returns a mutable reference to the fault injection options.
+    ///
+    /// See [`FaultInjection`] for details.
+    #[inline]
+    #[must_use]
+    pub fn fault_injection_mut(&mut self) -> &mut FaultInjection {
+        &mut self.faults
+    }
+
+    /// Sets the fault injection options.
+    ///
+    /// See [`FaultInjection`] for details.
+    #[inline]
+    pub fn set_fault_injection(&mut self, faults: FaultInjection) {
+        self.faults = faults;
+    }
+
     /// Returns a mutable reference to the sink.
     #[inline]
     #[must_use]
-    fn sink(&mut self) -> &mut W {
+    fn sink(&mut self) -> &mut Sink<W> {
         &mut self.sink
     }
 
@@ -217,7 +265,7 @@ impl<W: Write + Seek> Writer<W> {
             return Ok(());
         }
 
-        let current_pos = self.sink.stream_position()?;
+        let current_pos = self.sink.position();
         current_node.header.bytelen_attributes = current_pos - current_node.body_pos;
         current_node.is_attrs_finalized = true;
 
@@ -243,7 +291,7 @@ impl<W: Write + Seek> Writer<W> {
         let bytelen_name =
             u8::try_from(name.len()).map_err(|_| Error::NodeNameTooLong(name.len()))?;
 
-        let header_pos = self.sink.stream_position()?;
+        let header_pos = self.sink.position();
 
         let header = NodeHeader {
             end_offset: 0,
@@ -258,7 +306,7 @@ impl<W: Write + Seek> Writer<W> {
         // Write node name.
         self.sink.write_all(name.as_ref())?;
 
-        let body_pos = self.sink.stream_position()?;
+        let body_pos = self.sink.position();
 
         self.open_nodes.push(OpenNode {
             header_pos,
@@ -282,89 +330,200 @@ impl<W: Write + Seek> Writer<W> {
         };
 
         // Write node end marker if necessary.
-        if current_node.has_child || current_node.header.num_attributes == 0 {
+        let needs_end_marker = current_node.has_child || current_node.header.num_attributes == 0;
+        let writes_end_marker = if needs_end_marker {
+            !self.faults.omit_node_end_marker
+        } else {
+            self.faults.extra_node_end_marker
+        };
+        if writes_end_marker {
             self.write_node_header(&NodeHeader::node_end())?;
         }
 
         // Update node header.
-        let node_end_pos = self.sink.stream_position()?;
-        self.sink.seek(SeekFrom::Start(current_node.header_pos))?;
-        current_node.header.end_offset = node_end_pos;
+        let node_end_pos = self.sink.position();
+        self.sink.seek_to(current_node.header_pos)?;
+        current_node.header.end_offset =
+            node_end_pos.wrapping_add(self.faults.end_offset_delta as u64);
         assert_eq!(
             current_node.header.num_attributes == 0,
             current_node.header.bytelen_attributes == 0,
             "Length of attributes can be zero iff there are no attributes"
         );
         self.write_node_header(&current_node.header)?;
-        self.sink.seek(SeekFrom::Start(node_end_pos))?;
+        self.sink.seek_to(node_end_pos)?;
 
         Ok(())
     }
 
     /// Writes the given tree.
+    ///
+    /// Top-level nodes of the tree (i.e. children of the implicit root node)
+    /// are written as children of the current node of the writer.
     #[cfg(feature = "tree")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tree")))]
+    #[inline]
     pub fn write_tree(&mut self, tree: &crate::tree::v7400::Tree) -> Result<()> {
-        use crate::low::v7400::AttributeValue;
+        self.write_subtree(tree.root())
+    }
 
-        let mut current = match tree.root().first_child() {
-            Some(v) => v,
-            None => return Ok(()),
-        };
+    /// Writes the given node and its descendants.
+    ///
+    /// The node is written as a child of the current node of the writer.
+    ///
+    /// If the given node is the implicit root node of the tree, its children
+    /// are written instead of the root node itself, i.e. this works same as
+    /// [`write_tree`][`Self::write_tree`].
+    #[cfg(feature = "tree")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tree")))]
+    pub fn write_subtree(&mut self, node: crate::tree::v7400::NodeHandle<'_>) -> Result<()> {
+        use crate::tree::v7400::DepthFirstTraversed;
+
+        let tree = node.tree();
+        if node.node_id() == tree.root().node_id() {
+            return node
+                .children()
+                .try_for_each(|child| self.write_subtree(child));
+        }
 
-        'all: loop {
-            let mut attrs_writer = self.new_node(current.name())?;
-            for attr in current.attributes() {
-                match attr {
-                    AttributeValue::Bool(v) => attrs_writer.append_bool(*v)?,
-                    AttributeValue::I16(v) => attrs_writer.append_i16(*v)?,
-                    AttributeValue::I32(v) => attrs_writer.append_i32(*v)?,
-                    AttributeValue::I64(v) => attrs_writer.append_i64(*v)?,
-                    AttributeValue::F32(v) => attrs_writer.append_f32(*v)?,
-                    AttributeValue::F64(v) => attrs_writer.append_f64(*v)?,
-                    AttributeValue::ArrBool(v) => {
-                        attrs_writer.append_arr_bool_from_iter(None, v.iter().cloned())?
-                    }
-                    AttributeValue::ArrI32(v) => {
-                        attrs_writer.append_arr_i32_from_iter(None, v.iter().cloned())?
-                    }
-                    AttributeValue::ArrI64(v) => {
-                        attrs_writer.append_arr_i64_from_iter(None, v.iter().cloned())?
-                    }
-                    AttributeValue::ArrF32(v) => {
-                        attrs_writer.append_arr_f32_from_iter(None, v.iter().cloned())?
-                    }
-                    AttributeValue::ArrF64(v) => {
-                        attrs_writer.append_arr_f64_from_iter(None, v.iter().cloned())?
-                    }
-                    AttributeValue::Binary(v) => attrs_writer.append_binary_direct(v)?,
-                    AttributeValue::String(v) => attrs_writer.append_string_direct(v)?,
+        let mut traverse = node.node_id().traverse_depth_first();
+        while let Some(event) = traverse.next_forward(tree) {
+            match event {
+                DepthFirstTraversed::Open(id) => {
+                    let current = id.to_handle(tree);
+                    let mut attrs_writer = self.new_node(current.name())?;
+                    current
+                        .attribute_values()
+                        .try_for_each(|attr| attrs_writer.append_attribute(&attr))?;
                 }
+                DepthFirstTraversed::Close(_) => self.close_node()?,
             }
+        }
 
-            let mut visit_child = true;
-            current = 'next: loop {
-                if visit_child {
-                    if let Some(child) = current.first_child() {
-                        break 'next child;
-                    }
-                    // No children.
-                    visit_child = false;
-                }
-                self.close_node()?;
-                if let Some(sib) = current.next_sibling() {
-                    break 'next sib;
-                }
-                let parent = current
-                    .parent()
-                    .expect("Should never fail: `current` must not be the root note");
-                if parent.node_id() == tree.root().node_id() {
-                    break 'all;
-                }
-                current = parent;
-            };
+        Ok(())
+    }
+
+    /// Writes the given nodes and their descendants in order.
+    ///
+    /// The nodes are written as children of the current node of the writer.
+    /// They can belong to different trees, so this can be used to assemble
+    /// output from pieces of several trees.
+    ///
+    /// Each node is written by [`write_subtree`][`Self::write_subtree`].
+    #[cfg(feature = "tree")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tree")))]
+    pub fn write_nodes<'a>(
+        &mut self,
+        nodes: impl IntoIterator<Item = crate::tree::v7400::NodeHandle<'a>>,
+    ) -> Result<()> {
+        nodes
+            .into_iter()
+            .try_for_each(|node| self.write_subtree(node))
+    }
+
+    /// Copies the current node of the parser and its descendants without
+    /// decoding attributes.
+    ///
+    /// The node is written as a child of the current node of the writer.
+    /// Node names and attributes (including compressed arrays) are copied
+    /// verbatim, and only node headers are rewritten for the new position
+    /// and the FBX version of the writer.
+    /// This is much faster than reading and writing attributes one by one.
+    ///
+    /// The parser should be positioned in the node to copy, i.e. the last
+    /// event should be the [`StartNode`][`crate::pull_parser::v7400::Event::StartNode`]
+    /// of the node or its attributes can be partially read.
+    /// On success, the parser skips the node as
+    /// [`Parser::skip_current_node`] does, so the next event is the one after
+    /// the [`EndNode`][`crate::pull_parser::v7400::Event::EndNode`] of the
+    /// copied node.
+    /// On failure, the parser is aborted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parser has no open nodes, i.e. when
+    /// [`Parser::current_depth`] returns 0.
+    pub fn copy_current_node<R>(&mut self, parser: &mut Parser<R>) -> Result<()>
+    where
+        R: Read + Seek,
+    {
+        parser.ensure_continuable()?;
+        let (start_offset, end_offset) = parser
+            .current_node_range()
+            .expect("Attempt to copy implicit top-level node");
+        trace!(
+            "Copy node: name={:?}, range={:?}",
+            parser.current_node_name(),
+            start_offset..end_offset
+        );
+
+        self.finalize_attributes()?;
+        if let Some(current_node) = self.current_node() {
+            current_node.has_child = true;
         }
 
+        let res = (|| -> Result<()> {
+            parser.reader().seek_to(start_offset)?;
+            let header = NodeHeader::read_from_parser(parser)?;
+            self.copy_raw_node(parser, &header)
+        })();
+        if let Err(e) = res {
+            let pos = parser.position();
+            parser.set_aborted(pos);
+            return Err(e);
+        }
+
+        parser.skip_current_node().map_err(Into::into)
+    }
+
+    /// Copies the node with the given header, and its descendants.
+    ///
+    /// The source should be positioned right after the node header.
+    fn copy_raw_node<R>(&mut self, parser: &mut Parser<R>, header: &NodeHeader) -> Result<()>
+    where
+        R: Read,
+    {
+        // Write dummy header (placeholder).
+        let header_pos = self.sink.position();
+        self.write_node_header(&NodeHeader {
+            end_offset: 0,
+            ..*header
+        })?;
+
+        // Copy the node name and the attributes.
+        let len = u64::from(header.bytelen_name) + header.bytelen_attributes;
+        let copied = io::copy(&mut parser.reader().take(len), &mut self.sink)?;
+        if copied != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        // Copy children.
+        while parser.reader().position() < header.end_offset {
+            let child = NodeHeader::read_from_parser(parser)?;
+            if child.is_node_end() {
+                self.write_node_header(&child)?;
+                break;
+            }
+            self.copy_raw_node(parser, &child)?;
+        }
+        let source_pos = parser.reader().position();
+        if source_pos != header.end_offset {
+            return Err(ParserError::from(DataError::NodeLengthMismatch(
+                header.end_offset,
+                Some(source_pos),
+            ))
+            .into());
+        }
+
+        // Update node header.
+        let node_end_pos = self.sink.position();
+        self.sink.seek_to(header_pos)?;
+        self.write_node_header(&NodeHeader {
+            end_offset: node_end_pos,
+            ..*header
+        })?;
+        self.sink.seek_to(node_end_pos)?;
+
         Ok(())
     }
 
@@ -374,7 +533,7 @@ impl<W: Write + Seek> Writer<W> {
     pub fn finalize(mut self, footer: &FbxFooter<'_>) -> Result<W> {
         self.finalize_impl(footer)?;
 
-        Ok(self.sink)
+        Ok(self.sink.into_inner())
     }
 
     /// Finalizes the FBX binary, and returns the inner sink after flushing.
@@ -382,7 +541,7 @@ impl<W: Write + Seek> Writer<W> {
         self.finalize_impl(footer)?;
         self.sink.flush()?;
 
-        Ok(self.sink)
+        Ok(self.sink.into_inner())
     }
 
     /// Internal implementation of `finalize()` and `finalize_and_flush()`.
@@ -399,7 +558,7 @@ impl<W: Write + Seek> Writer<W> {
         {
             let len = match footer.padding_len {
                 FbxFooterPaddingLength::Default => {
-                    let current = self.sink.stream_position()?;
+                    let current = self.sink.position();
                     current.wrapping_neg() & 0x0f
                 }
                 FbxFooterPaddingLength::Forced(len) => u64::from(len),
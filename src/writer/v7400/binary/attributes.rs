@@ -2,12 +2,12 @@
 
 use std::{
     convert::TryFrom,
-    io::{self, Seek, SeekFrom, Write},
+    io::{self, Seek, Write},
 };
 
 use crate::{
-    low::v7400::{ArrayAttributeEncoding, ArrayAttributeHeader, AttributeType},
-    writer::v7400::binary::{Error, Result, Writer},
+    low::v7400::{ArrayAttributeEncoding, ArrayAttributeHeader, AttributeType, AttributeValue},
+    writer::v7400::binary::{sink::Sink, Error, FaultInjection, Result, Writer},
 };
 
 mod array;
@@ -140,6 +140,29 @@ macro_rules! impl_arr_from_iter {
     )*}
 }
 
+/// Implement `append_*` methods for array values given as slices.
+macro_rules! impl_arr_from_slice {
+    ($(
+        $(#[$meta:meta])*
+        $name:ident: $ty_elem:ty, $tyval:ident;
+    )*) => {$(
+        $(#[$meta])*
+        #[inline]
+        pub fn $name(
+            &mut self,
+            encoding: impl Into<Option<ArrayAttributeEncoding>>,
+            elements: &[$ty_elem],
+        ) -> Result<()> {
+            array::write_array_attr_slice(
+                self,
+                AttributeType::$tyval,
+                encoding.into(),
+                elements,
+            )
+        }
+    )*}
+}
+
 impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
     /// Creates a new `AttributesWriter`.
     #[inline]
@@ -151,10 +174,17 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
     /// Returns the inner writer.
     #[inline]
     #[must_use]
-    pub(crate) fn sink(&mut self) -> &mut W {
+    pub(crate) fn sink(&mut self) -> &mut Sink<W> {
         self.writer.sink()
     }
 
+    /// Returns the fault injection options of the writer.
+    #[inline]
+    #[must_use]
+    pub(crate) fn faults(&self) -> &FaultInjection {
+        &self.writer.faults
+    }
+
     /// Writes the given attribute type as type code.
     fn write_type_code(&mut self, ty: AttributeType) -> Result<()> {
         self.writer
@@ -180,9 +210,15 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
         Ok(())
     }
 
+    /// Writes a single boolean attribute.
+    pub fn append_bool(&mut self, v: bool) -> Result<()> {
+        self.update_node_header()?;
+        self.write_type_code(AttributeType::Bool)?;
+        let byte = self.faults().bool_byte(v);
+        self.writer.sink().write_all(&[byte]).map_err(Into::into)
+    }
+
     impl_single_attr_append! {
-        /// Writes a single boolean attribute.
-        append_bool(bool): Bool;
         /// Writes a single `i16` attribute.
         append_i16(i16): I16;
         /// Writes a single `i32` attribute.
@@ -211,7 +247,7 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
 
         // Write attribute header.
         self.write_type_code(ty)?;
-        let header_pos = self.writer.sink().stream_position()?;
+        let header_pos = self.writer.sink().position();
 
         // Write array header placeholder.
         self.write_array_header(&ArrayAttributeHeader {
@@ -223,15 +259,34 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
         Ok(header_pos)
     }
 
+    /// Writes headers for an array attribute whose header fields are already
+    /// known.
+    ///
+    /// Unlike [`initialize_array`][`Self::initialize_array`], this does not
+    /// require the header to be updated later.
+    pub(crate) fn write_array_attr_header(
+        &mut self,
+        ty: AttributeType,
+        header: &ArrayAttributeHeader,
+    ) -> Result<()> {
+        self.update_node_header()?;
+
+        // Write attribute header.
+        self.write_type_code(ty)?;
+
+        // Write array header.
+        self.write_array_header(header)
+    }
+
     /// Updates an array attribute header.
     ///
     /// Note that this should be called at the end of the array attribute.
     fn finalize_array(&mut self, header_pos: u64, header: &ArrayAttributeHeader) -> Result<()> {
         // Write real array header.
-        let end_pos = self.writer.sink().stream_position()?;
-        self.writer.sink().seek(SeekFrom::Start(header_pos))?;
+        let end_pos = self.writer.sink().position();
+        self.writer.sink().seek_to(header_pos)?;
         self.write_array_header(header)?;
-        self.writer.sink().seek(SeekFrom::Start(end_pos))?;
+        self.writer.sink().seek_to(end_pos)?;
 
         Ok(())
     }
@@ -268,6 +323,64 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
         },
     }
 
+    impl_arr_from_slice! {
+        /// Writes a boolean array attribute from the slice.
+        ///
+        /// This is more efficient than
+        /// [`append_arr_bool_from_iter`][`Self::append_arr_bool_from_iter`],
+        /// since the elements are written as a single block.
+        append_arr_bool: bool, ArrBool;
+
+        /// Writes an `i32` array attribute from the slice.
+        ///
+        /// This is more efficient than
+        /// [`append_arr_i32_from_iter`][`Self::append_arr_i32_from_iter`],
+        /// since the elements are written as a single block.
+        append_arr_i32: i32, ArrI32;
+
+        /// Writes an `i64` array attribute from the slice.
+        ///
+        /// This is more efficient than
+        /// [`append_arr_i64_from_iter`][`Self::append_arr_i64_from_iter`],
+        /// since the elements are written as a single block.
+        append_arr_i64: i64, ArrI64;
+
+        /// Writes an `f32` array attribute from the slice.
+        ///
+        /// This is more efficient than
+        /// [`append_arr_f32_from_iter`][`Self::append_arr_f32_from_iter`],
+        /// since the elements are written as a single block.
+        append_arr_f32: f32, ArrF32;
+
+        /// Writes an `f64` array attribute from the slice.
+        ///
+        /// This is more efficient than
+        /// [`append_arr_f64_from_iter`][`Self::append_arr_f64_from_iter`],
+        /// since the elements are written as a single block.
+        append_arr_f64: f64, ArrF64;
+    }
+
+    /// Writes the given attribute value.
+    ///
+    /// Array attributes are written with the default encoding.
+    pub fn append_attribute(&mut self, attr: &AttributeValue) -> Result<()> {
+        match attr {
+            AttributeValue::Bool(v) => self.append_bool(*v),
+            AttributeValue::I16(v) => self.append_i16(*v),
+            AttributeValue::I32(v) => self.append_i32(*v),
+            AttributeValue::I64(v) => self.append_i64(*v),
+            AttributeValue::F32(v) => self.append_f32(*v),
+            AttributeValue::F64(v) => self.append_f64(*v),
+            AttributeValue::ArrBool(v) => self.append_arr_bool(None, v),
+            AttributeValue::ArrI32(v) => self.append_arr_i32(None, v),
+            AttributeValue::ArrI64(v) => self.append_arr_i64(None, v),
+            AttributeValue::ArrF32(v) => self.append_arr_f32(None, v),
+            AttributeValue::ArrF64(v) => self.append_arr_f64(None, v),
+            AttributeValue::Binary(v) => self.append_binary_direct(v),
+            AttributeValue::String(v) => self.append_string_direct(v),
+        }
+    }
+
     /// Writes some headers for a special attribute, and returns the special
     /// header position.
     fn initialize_special(&mut self, ty: AttributeType) -> Result<u64> {
@@ -277,7 +390,7 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
         self.write_type_code(ty)?;
 
         // Write special attribute header (dummy).
-        let header_pos = self.writer.sink().stream_position()?;
+        let header_pos = self.writer.sink().position();
         self.writer.sink().write_all(&0u32.to_le_bytes())?;
 
         Ok(header_pos)
@@ -291,33 +404,47 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
         let bytelen = u32::try_from(bytelen).map_err(|_| Error::AttributeTooLong(bytelen))?;
 
         // Write real special attribute header.
-        let end_pos = self.writer.sink().stream_position()?;
-        self.writer.sink().seek(SeekFrom::Start(header_pos))?;
+        let end_pos = self.writer.sink().position();
+        self.writer.sink().seek_to(header_pos)?;
+        self.writer.sink().write_all(&bytelen.to_le_bytes())?;
+        self.writer.sink().seek_to(end_pos)?;
+
+        Ok(())
+    }
+
+    /// Writes headers for a special attribute whose length is already known.
+    ///
+    /// Unlike [`initialize_special`][`Self::initialize_special`], this does
+    /// not require the header to be updated later.
+    fn write_special_header(&mut self, ty: AttributeType, bytelen: usize) -> Result<()> {
+        let bytelen = u32::try_from(bytelen).map_err(|_| Error::AttributeTooLong(bytelen))?;
+
+        self.update_node_header()?;
+
+        // Write attribute header.
+        self.write_type_code(ty)?;
+
+        // Write special attribute header.
         self.writer.sink().write_all(&bytelen.to_le_bytes())?;
-        self.writer.sink().seek(SeekFrom::Start(end_pos))?;
 
         Ok(())
     }
 
     /// Writes a binary attribute.
     pub fn append_binary_direct(&mut self, binary: &[u8]) -> Result<()> {
-        let header_pos = self.initialize_special(AttributeType::Binary)?;
+        self.write_special_header(AttributeType::Binary, binary.len())?;
 
         self.writer.sink().write_all(binary)?;
 
-        self.finalize_special(header_pos, binary.len())?;
-
         Ok(())
     }
 
     /// Writes a string attribute.
     pub fn append_string_direct(&mut self, string: &str) -> Result<()> {
-        let header_pos = self.initialize_special(AttributeType::String)?;
+        self.write_special_header(AttributeType::String, string.len())?;
 
         self.writer.sink().write_all(string.as_ref())?;
 
-        self.finalize_special(header_pos, string.len())?;
-
         Ok(())
     }
 
@@ -342,7 +469,7 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
             self.writer.sink().write_all(&[v])?;
             len = len
                 .checked_add(1)
-                .ok_or(Error::AttributeTooLong(std::usize::MAX))?;
+                .ok_or(Error::AttributeTooLong(usize::MAX))?;
 
             Ok(())
         })?;
@@ -368,7 +495,7 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
             self.writer.sink().write_all(&[v])?;
             len = len
                 .checked_add(1)
-                .ok_or(Error::AttributeTooLong(std::usize::MAX))?;
+                .ok_or(Error::AttributeTooLong(usize::MAX))?;
 
             Ok(())
         })?;
@@ -389,7 +516,7 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
             self.writer.sink().write_all(buf)?;
             len = len
                 .checked_add(char_len)
-                .ok_or(Error::AttributeTooLong(std::usize::MAX))?;
+                .ok_or(Error::AttributeTooLong(usize::MAX))?;
 
             Ok(())
         })?;
@@ -417,7 +544,7 @@ impl<'a, W: Write + Seek> AttributesWriter<'a, W> {
             self.writer.sink().write_all(buf)?;
             len = len
                 .checked_add(char_len)
-                .ok_or(Error::AttributeTooLong(std::usize::MAX))?;
+                .ok_or(Error::AttributeTooLong(usize::MAX))?;
 
             Ok(())
         })?;
@@ -3,12 +3,13 @@
 use std::{
     convert::TryFrom,
     io::{self, Seek, Write},
+    mem,
 };
 
 use crate::{
     low::v7400::{ArrayAttributeEncoding, ArrayAttributeHeader, AttributeType},
     writer::v7400::binary::{
-        attributes::IntoBytes, AttributesWriter, CompressionError, Error, Result,
+        attributes::IntoBytes, AttributesWriter, CompressionError, Error, FaultInjection, Result,
     },
 };
 
@@ -35,6 +36,36 @@ impl<T: IntoBytes, E, I: IntoIterator<Item = std::result::Result<T, E>>> IntoByt
     }
 }
 
+/// A trait for types whose slices can be represented as single bytes array.
+pub(crate) trait SliceIntoBytes: IntoBytes + Copy {
+    /// Returns little-endian bytes of all the elements in the slice.
+    fn slice_to_le_bytes(slice: &[Self]) -> Vec<u8>;
+}
+
+impl SliceIntoBytes for bool {
+    #[inline]
+    fn slice_to_le_bytes(slice: &[Self]) -> Vec<u8> {
+        slice.iter().map(|&v| if v { b'Y' } else { b'T' }).collect()
+    }
+}
+
+/// Implement `SliceIntoBytes` for primitive numeric types.
+macro_rules! impl_slice_into_bytes_for_primitives {
+    ($($ty:ty),*) => {$(
+        impl SliceIntoBytes for $ty {
+            fn slice_to_le_bytes(slice: &[Self]) -> Vec<u8> {
+                let mut buf = Vec::with_capacity(slice.len() * mem::size_of::<$ty>());
+                slice
+                    .iter()
+                    .for_each(|v| buf.extend_from_slice(&v.to_le_bytes()));
+                buf
+            }
+        }
+    )*};
+}
+
+impl_slice_into_bytes_for_primitives! { i32, i64, f32, f64 }
+
 /// Writes array elements into the given writer.
 pub(crate) fn write_elements_result_iter<T, E>(
     mut writer: impl Write,
@@ -54,6 +85,42 @@ where
     Ok(elements_count)
 }
 
+/// Encodes the given bytes with the given array encoding.
+fn encode_bytes(encoding: ArrayAttributeEncoding, bytes: Vec<u8>) -> Result<Vec<u8>> {
+    match encoding {
+        ArrayAttributeEncoding::Direct => Ok(bytes),
+        ArrayAttributeEncoding::Zlib => {
+            let mut encoder = libflate::zlib::Encoder::new(Vec::new())?;
+            encoder.write_all(&bytes)?;
+            let encoded = encoder
+                .finish()
+                .into_result()
+                .map_err(CompressionError::Zlib)?;
+            Ok(encoded)
+        }
+    }
+}
+
+/// Applies the fault injection to the raw (not yet encoded) array elements.
+fn inject_raw_faults(faults: &FaultInjection, ty: AttributeType, bytes: &mut [u8]) {
+    if ty == AttributeType::ArrBool && faults.bool_bytes.is_some() {
+        bytes
+            .iter_mut()
+            .for_each(|b| *b = faults.bool_byte(*b == b'Y'));
+    }
+}
+
+/// Applies the fault injection to the encoded array elements.
+fn inject_encoded_faults(
+    faults: &FaultInjection,
+    encoding: ArrayAttributeEncoding,
+    bytes: &mut Vec<u8>,
+) {
+    if encoding == ArrayAttributeEncoding::Zlib {
+        bytes.truncate(bytes.len().saturating_sub(faults.zlib_truncate_len));
+    }
+}
+
 /// Writes the given array attribute header.
 pub(crate) fn write_array_header(
     mut writer: impl Write,
@@ -75,10 +142,17 @@ pub(crate) fn write_array_attr_result_iter<W: Write + Seek, T: IntoBytes, E: Int
 ) -> Result<()> {
     let encoding = encoding.unwrap_or(ArrayAttributeEncoding::Direct);
 
+    if writer.faults().affects_arrays() {
+        // Elements should be modified, so write them through a buffer.
+        let mut bytes = Vec::new();
+        let elements_count = write_elements_result_iter(&mut bytes, iter)?;
+        return write_array_attr_bytes(writer, ty, encoding, elements_count, bytes);
+    }
+
     let header_pos = writer.initialize_array(ty, encoding)?;
 
     // Write elements.
-    let start_pos = writer.sink().stream_position()?;
+    let start_pos = writer.sink().position();
     let elements_count = match encoding {
         ArrayAttributeEncoding::Direct => write_elements_result_iter(writer.sink(), iter)?,
         ArrayAttributeEncoding::Zlib => {
@@ -90,7 +164,7 @@ pub(crate) fn write_array_attr_result_iter<W: Write + Seek, T: IntoBytes, E: Int
             count
         }
     };
-    let end_pos = writer.sink().stream_position()?;
+    let end_pos = writer.sink().position();
     let bytelen = end_pos - start_pos;
 
     // Calculate header fields.
@@ -108,3 +182,58 @@ pub(crate) fn write_array_attr_result_iter<W: Write + Seek, T: IntoBytes, E: Int
 
     Ok(())
 }
+
+/// Writes the given array attribute from a slice.
+///
+/// Elements are converted and encoded into a single block in memory before
+/// being written, so the header can be written first and no seek is required.
+pub(crate) fn write_array_attr_slice<W: Write + Seek, T: SliceIntoBytes>(
+    writer: &mut AttributesWriter<'_, W>,
+    ty: AttributeType,
+    encoding: Option<ArrayAttributeEncoding>,
+    elements: &[T],
+) -> Result<()> {
+    let encoding = encoding.unwrap_or(ArrayAttributeEncoding::Direct);
+
+    let elements_count = u32::try_from(elements.len())
+        .map_err(|_| Error::TooManyArrayAttributeElements(elements.len()))?;
+    write_array_attr_bytes(
+        writer,
+        ty,
+        encoding,
+        elements_count,
+        T::slice_to_le_bytes(elements),
+    )
+}
+
+/// Writes the given array attribute from the raw (not yet encoded) bytes.
+fn write_array_attr_bytes<W: Write + Seek>(
+    writer: &mut AttributesWriter<'_, W>,
+    ty: AttributeType,
+    encoding: ArrayAttributeEncoding,
+    elements_count: u32,
+    mut bytes: Vec<u8>,
+) -> Result<()> {
+    let faults = *writer.faults();
+    inject_raw_faults(&faults, ty, &mut bytes);
+    let mut bytes = encode_bytes(encoding, bytes)?;
+    inject_encoded_faults(&faults, encoding, &mut bytes);
+
+    // Calculate header fields.
+    let bytelen = u32::try_from(bytes.len()).map_err(|_| Error::AttributeTooLong(bytes.len()))?;
+
+    // Write headers.
+    writer.write_array_attr_header(
+        ty,
+        &ArrayAttributeHeader {
+            elements_count,
+            encoding,
+            bytelen,
+        },
+    )?;
+
+    // Write elements.
+    writer.sink().write_all(&bytes)?;
+
+    Ok(())
+}
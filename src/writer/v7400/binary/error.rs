@@ -2,7 +2,10 @@
 
 use std::{error, fmt, io};
 
-use crate::low::FbxVersion;
+use crate::{
+    low::FbxVersion,
+    pull_parser::{any::Error as AnyParserError, Error as ParserError},
+};
 
 /// Write result.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -22,6 +25,8 @@ pub enum Error {
     NoNodesToClose,
     /// Node name is too long.
     NodeNameTooLong(usize),
+    /// Parser error while copying nodes from the source.
+    Parser(ParserError),
     /// Too many array attribute elements.
     TooManyArrayAttributeElements(usize),
     /// Too many attributes.
@@ -39,6 +44,7 @@ impl error::Error for Error {
         match self {
             Error::Compression(e) => Some(e),
             Error::Io(e) => Some(e),
+            Error::Parser(e) => Some(e),
             Error::UserDefined(e) => Some(&**e),
             _ => None,
         }
@@ -54,6 +60,7 @@ impl fmt::Display for Error {
             Error::Io(e) => write!(f, "I/O error: {}", e),
             Error::NoNodesToClose => write!(f, "There are no nodes to close"),
             Error::NodeNameTooLong(v) => write!(f, "Node name is too long: {} bytes", v),
+            Error::Parser(e) => write!(f, "Parser error: {}", e),
             Error::TooManyArrayAttributeElements(v) => write!(
                 f,
                 "Too many array elements for a single node attribute: count={}",
@@ -74,6 +81,13 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<ParserError> for Error {
+    #[inline]
+    fn from(e: ParserError) -> Self {
+        Error::Parser(e)
+    }
+}
+
 impl From<CompressionError> for Error {
     #[inline]
     fn from(e: CompressionError) -> Self {
@@ -105,3 +119,70 @@ impl fmt::Display for CompressionError {
         }
     }
 }
+
+/// Attribute patch error.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PatchError {
+    /// I/O error.
+    Io(io::Error),
+    /// Parser creation error.
+    ParserCreation(AnyParserError),
+    /// Parser error while locating attributes.
+    Parser(ParserError),
+    /// The encoded size of the new value differs from the old one.
+    SizeMismatch {
+        /// Size of the old attribute in bytes.
+        expected: u64,
+        /// Size of the new attribute in bytes.
+        actual: u64,
+    },
+}
+
+impl error::Error for PatchError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PatchError::Io(e) => Some(e),
+            PatchError::ParserCreation(e) => Some(e),
+            PatchError::Parser(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::Io(e) => write!(f, "I/O error: {}", e),
+            PatchError::ParserCreation(e) => write!(f, "Failed to create a parser: {}", e),
+            PatchError::Parser(e) => write!(f, "Parser error: {}", e),
+            PatchError::SizeMismatch { expected, actual } => write!(
+                f,
+                "Attribute cannot be patched in place: the new value is {} bytes, \
+                 but the old value is {} bytes",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for PatchError {
+    #[inline]
+    fn from(e: io::Error) -> Self {
+        PatchError::Io(e)
+    }
+}
+
+impl From<ParserError> for PatchError {
+    #[inline]
+    fn from(e: ParserError) -> Self {
+        PatchError::Parser(e)
+    }
+}
+
+impl From<AnyParserError> for PatchError {
+    #[inline]
+    fn from(e: AnyParserError) -> Self {
+        PatchError::ParserCreation(e)
+    }
+}
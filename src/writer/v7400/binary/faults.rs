@@ -0,0 +1,86 @@
+//! Fault injection.
+//!
+//! Faults are deliberately emitted defects.
+//! They are useful to test error handling of FBX consumers, and should not be
+//! used to write normal FBX data.
+
+/// Fault injection options for the binary writer.
+///
+/// The writer checks the options each time it writes the corresponding part,
+/// so faults can be enabled only for specific nodes or attributes by updating
+/// the options through [`Writer::fault_injection_mut`] between writes.
+///
+/// The default value injects no faults.
+///
+/// Note that wrong footer padding can be emitted without fault injection,
+/// by [`FbxFooterPaddingLength::Forced`].
+///
+/// [`Writer::fault_injection_mut`]: super::Writer::fault_injection_mut
+/// [`FbxFooterPaddingLength::Forced`]: super::FbxFooterPaddingLength::Forced
+///
+/// # Examples
+///
+/// ```
+/// use fbxcel::{low::FbxVersion, writer::v7400::binary::Writer};
+/// # let sink = std::io::Cursor::new(Vec::new());
+/// let mut writer = Writer::new(sink, FbxVersion::V7_4)?;
+///
+/// writer.new_node("Parent")?;
+/// writer.new_node("Child")?;
+/// writer.close_node()?;
+///
+/// // Close `Parent` without a node end marker.
+/// writer.fault_injection_mut().omit_node_end_marker = true;
+/// writer.close_node()?;
+/// writer.fault_injection_mut().omit_node_end_marker = false;
+/// # Ok::<_, fbxcel::writer::v7400::binary::Error>(())
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct FaultInjection {
+    /// Omits the node end marker even if it is necessary.
+    ///
+    /// The node end marker is necessary if the node has children or has no
+    /// attributes.
+    pub omit_node_end_marker: bool,
+    /// Writes the node end marker even if it is unnecessary.
+    ///
+    /// The node end marker is unnecessary if the node has attributes but no
+    /// children.
+    pub extra_node_end_marker: bool,
+    /// Offset added to the end offset written in node headers.
+    pub end_offset_delta: i64,
+    /// Bytes used to represent boolean values, as `(false, true)`.
+    ///
+    /// This affects both single boolean attributes and boolean array
+    /// attributes.
+    /// If `None`, `b'T'` and `b'Y'` are used.
+    pub bool_bytes: Option<(u8, u8)>,
+    /// Number of bytes to drop from the end of zlib-compressed array
+    /// attributes.
+    ///
+    /// The array attribute header is consistent with the truncated stream,
+    /// so the following data can still be read correctly.
+    pub zlib_truncate_len: usize,
+}
+
+impl FaultInjection {
+    /// Returns the byte representation of the given boolean value.
+    #[inline]
+    #[must_use]
+    pub(crate) fn bool_byte(&self, v: bool) -> u8 {
+        match (self.bool_bytes, v) {
+            (Some((f, _)), false) => f,
+            (Some((_, t)), true) => t,
+            (None, false) => b'T',
+            (None, true) => b'Y',
+        }
+    }
+
+    /// Returns whether array attributes need to be modified.
+    #[inline]
+    #[must_use]
+    pub(crate) fn affects_arrays(&self) -> bool {
+        self.bool_bytes.is_some() || self.zlib_truncate_len != 0
+    }
+}
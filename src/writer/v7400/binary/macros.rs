@@ -35,6 +35,43 @@
 /// let _buf = writer.finalize_and_flush(&Default::default())?;
 /// # Ok::<_, Box<dyn std::error::Error>>(())
 /// ```
+///
+/// The macro also supports the extended syntax of [`tree_v7400!`]: string
+/// literal and expression node names, `for` loops, `if` conditionals, and the
+/// shorthand form of `P` nodes.
+/// Additionally, array attributes in brackets can have encoding annotations.
+///
+/// ```
+/// # use fbxcel::write_v7400_binary;
+/// use fbxcel::{
+///     low::{v7400::ArrayAttributeEncoding, FbxVersion},
+///     writer::v7400::binary::Writer,
+/// };
+/// let mut writer = Writer::new(std::io::Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+/// let encoding = ArrayAttributeEncoding::Direct;
+///
+/// write_v7400_binary!(
+///     writer=writer,
+///     tree={
+///         "Node with spaces": {}
+///         Objects: {
+///             for i in (0..3i64) {
+///                 (format!("Model{}", i)): [i] {}
+///             }
+///         }
+///         Properties70: {
+///             P: "UpAxis", "int", "Integer", "", 1i32;
+///         }
+///         // Encodings can be variant names of `ArrayAttributeEncoding`, or
+///         // expressions.
+///         Vertices: [vec![0.0f64; 9] => Zlib, vec![0i32; 3] => encoding] {}
+///     },
+/// )?;
+/// let _buf = writer.finalize_and_flush(&Default::default())?;
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [`tree_v7400!`]: macro.tree_v7400.html
 #[cfg_attr(docsrs, doc(cfg(feature = "writer")))]
 #[macro_export]
 macro_rules! write_v7400_binary {
@@ -57,47 +94,119 @@ macro_rules! write_v7400_binary {
     };
 
     (@__node, $writer:ident,
-        $name:ident: {
+        for $pat:pat in ($iter:expr) {
+            $($body:tt)*
+        }
+        $($rest:tt)*
+    ) => {{
+        for $pat in $iter {
+            write_v7400_binary! { @__node, $writer, $($body)* }
+        }
+        write_v7400_binary! { @__node, $writer, $($rest)* }
+    }};
+    (@__node, $writer:ident,
+        if ($cond:expr) {
+            $($body:tt)*
+        } else {
+            $($else_body:tt)*
+        }
+        $($rest:tt)*
+    ) => {{
+        if $cond {
+            write_v7400_binary! { @__node, $writer, $($body)* }
+        } else {
+            write_v7400_binary! { @__node, $writer, $($else_body)* }
+        }
+        write_v7400_binary! { @__node, $writer, $($rest)* }
+    }};
+    (@__node, $writer:ident,
+        if ($cond:expr) {
+            $($body:tt)*
+        }
+        $($rest:tt)*
+    ) => {{
+        if $cond {
+            write_v7400_binary! { @__node, $writer, $($body)* }
+        }
+        write_v7400_binary! { @__node, $writer, $($rest)* }
+    }};
+
+    (@__node, $writer:ident,
+        P: $name:literal $(, $attr:expr)* $(,)?;
+        $($rest:tt)*
+    ) => {
+        write_v7400_binary! { @__named, $writer, ("P"), [$name $(, $attr)*] {} $($rest)* }
+    };
+    (@__node, $writer:ident,
+        P: ($name:expr) $(, $attr:expr)* $(,)?;
+        $($rest:tt)*
+    ) => {
+        write_v7400_binary! { @__named, $writer, ("P"), [$name $(, $attr)*] {} $($rest)* }
+    };
+
+    (@__node, $writer:ident, $name:ident: $($rest:tt)*) => {
+        write_v7400_binary! { @__named, $writer, (stringify!($name)), $($rest)* }
+    };
+    (@__node, $writer:ident, $name:literal: $($rest:tt)*) => {
+        write_v7400_binary! { @__named, $writer, ($name), $($rest)* }
+    };
+    (@__node, $writer:ident, ($name:expr): $($rest:tt)*) => {
+        write_v7400_binary! { @__named, $writer, ($name), $($rest)* }
+    };
+
+    (@__named, $writer:ident, ($name:expr),
+        {
             $($subtree:tt)*
         }
         $($rest:tt)*
     ) => {{
-        $writer.new_node(stringify!($name))?;
+        $writer.new_node(::std::convert::AsRef::<str>::as_ref(&$name))?;
         write_v7400_binary! { @__node, $writer, $($subtree)* }
         $writer.close_node()?;
         write_v7400_binary! { @__node, $writer, $($rest)* }
     }};
-    (@__node, $writer:ident,
-        $name:ident: [$($attr:expr),* $(,)?] {
+    (@__named, $writer:ident, ($name:expr),
+        [$($attr:expr $(=> $encoding:expr)?),* $(,)?] {
             $($subtree:tt)*
         }
         $($rest:tt)*
     ) => {{
-        let mut _attrs = $writer.new_node(stringify!($name))?;
+        let mut _attrs = $writer.new_node(::std::convert::AsRef::<str>::as_ref(&$name))?;
         $({
             let attr = $attr;
-            write_v7400_binary!(@__attr, _attrs, attr.into())?;
+            let encoding = write_v7400_binary!(@__encoding $(, $encoding)?);
+            write_v7400_binary!(@__attr, _attrs, attr.into(), encoding)?;
         })*
         write_v7400_binary! { @__node, $writer, $($subtree)* }
         $writer.close_node()?;
         write_v7400_binary! { @__node, $writer, $($rest)* }
     }};
-    (@__node, $writer:ident,
-        $name:ident: ($attrs:expr) {
+    (@__named, $writer:ident, ($name:expr),
+        ($attrs:expr) {
             $($subtree:tt)*
         }
         $($rest:tt)*
     ) => {{
-        let mut _attrs = $writer.new_node(stringify!($name))?;
+        let mut _attrs = $writer.new_node(::std::convert::AsRef::<str>::as_ref(&$name))?;
         $attrs.into_iter().try_for_each(|attr: $crate::low::v7400::AttributeValue| {
-            write_v7400_binary!(@__attr, _attrs, attr.into())
+            write_v7400_binary!(@__attr, _attrs, attr.into(), write_v7400_binary!(@__encoding))
         })?;
         write_v7400_binary! { @__node, $writer, $($subtree)* }
         $writer.close_node()?;
         write_v7400_binary! { @__node, $writer, $($rest)* }
     }};
 
-    (@__attr, $attrs:ident, $attr:expr) => {{
+    (@__encoding) => {
+        ::std::option::Option::<$crate::low::v7400::ArrayAttributeEncoding>::None
+    };
+    (@__encoding, $encoding:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::low::v7400::ArrayAttributeEncoding::*;
+        let encoding: $crate::low::v7400::ArrayAttributeEncoding = $encoding;
+        ::std::option::Option::Some(encoding)
+    }};
+
+    (@__attr, $attrs:ident, $attr:expr, $encoding:expr) => {{
         use $crate::low::v7400::AttributeValue::*;
         match $attr {
             Bool(v) => $attrs.append_bool(v),
@@ -106,11 +215,11 @@ macro_rules! write_v7400_binary {
             I64(v) => $attrs.append_i64(v),
             F32(v) => $attrs.append_f32(v),
             F64(v) => $attrs.append_f64(v),
-            ArrBool(v) => $attrs.append_arr_bool_from_iter(None, v),
-            ArrI32(v) => $attrs.append_arr_i32_from_iter(None, v),
-            ArrI64(v) => $attrs.append_arr_i64_from_iter(None, v),
-            ArrF32(v) => $attrs.append_arr_f32_from_iter(None, v),
-            ArrF64(v) => $attrs.append_arr_f64_from_iter(None, v),
+            ArrBool(v) => $attrs.append_arr_bool_from_iter($encoding, v),
+            ArrI32(v) => $attrs.append_arr_i32_from_iter($encoding, v),
+            ArrI64(v) => $attrs.append_arr_i64_from_iter($encoding, v),
+            ArrF32(v) => $attrs.append_arr_f32_from_iter($encoding, v),
+            ArrF64(v) => $attrs.append_arr_f64_from_iter($encoding, v),
             Binary(v) => $attrs.append_binary_direct(&v),
             String(v) => $attrs.append_string_direct(&v),
         }
@@ -122,7 +231,7 @@ mod tests {
     use std::io::Cursor;
 
     use crate::{
-        low::FbxVersion,
+        low::{v7400::ArrayAttributeEncoding, FbxVersion},
         writer::v7400::binary::{Result, Writer},
     };
 
@@ -197,4 +306,39 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn extended_syntax() -> Result<()> {
+        let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+        let prop_name = "Scale";
+        let include_extra = false;
+        write_v7400_binary!(
+            writer=writer,
+            tree={
+                "Node with spaces": {},
+                Objects: {
+                    for i in (0..3i64) {
+                        (format!("Model{}", i)): [i] {
+                            if (i % 2 == 0) {
+                                Even: {}
+                            } else {
+                                Odd: {}
+                            }
+                        }
+                    }
+                    if (include_extra) {
+                        Extra: {}
+                    }
+                },
+                Properties70: {
+                    P: "UpAxis", "int", "Integer", "", 1i32;
+                    P: (prop_name), "double", "Number", "", 2.0f64,;
+                },
+                Vertices: [vec![0.0f64; 3] => Zlib, vec![1i32, 2] => ArrayAttributeEncoding::Direct] {},
+            },
+        )?;
+        let _buf = writer.finalize_and_flush(&Default::default())?;
+
+        Ok(())
+    }
 }
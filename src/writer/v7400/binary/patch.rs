@@ -0,0 +1,274 @@
+//! In-place patching of node attributes.
+
+use std::{
+    convert::TryFrom,
+    io::{Read, Seek, SeekFrom, Write},
+};
+
+use log::debug;
+
+use crate::{
+    low::v7400::AttributeValue,
+    pull_parser::{
+        any::AnyParser,
+        v7400::{AttributeLocation, Event},
+    },
+};
+
+use super::{attributes::IntoBytes, PatchError};
+
+/// Patcher of node attributes in existing binary FBX data.
+///
+/// This overwrites the bytes of an attribute in place, without rewriting the
+/// rest of the data. Only the values with the same encoded size as the old
+/// ones can be written, and others are refused by
+/// [`PatchError::SizeMismatch`].
+///
+/// Arrays are written without compression, so in practice a compressed array
+/// attribute cannot be patched.
+///
+/// # Examples
+///
+/// To locate an attribute by node names, use [`locate`][`Self::locate`].
+///
+/// ```no_run
+/// use fbxcel::{low::v7400::AttributeValue, writer::v7400::binary::AttributePatcher};
+///
+/// # fn f() -> Result<(), Box<dyn std::error::Error>> {
+/// let file = std::fs::OpenOptions::new()
+///     .read(true)
+///     .write(true)
+///     .open("model.fbx")?;
+/// let mut patcher = AttributePatcher::new(file);
+///
+/// let path = ["FBXHeaderExtension", "CreationTimeStamp", "Year"];
+/// if let Some(location) = patcher.locate(&path, 0)? {
+///     patcher.patch(&location, &AttributeValue::I32(2000))?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// For other conditions, locate attributes by
+/// [`Attributes::locate_next`][`crate::pull_parser::v7400::Attributes::locate_next`].
+///
+/// ```no_run
+/// use fbxcel::{
+///     low::v7400::AttributeValue,
+///     pull_parser::{any::AnyParser, v7400::{attribute::loaders::DirectLoader, Event}},
+///     writer::v7400::binary::AttributePatcher,
+/// };
+///
+/// # fn f() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut file = std::fs::OpenOptions::new()
+///     .read(true)
+///     .write(true)
+///     .open("model.fbx")?;
+///
+/// // Locate the value of `UnitScaleFactor` property.
+/// let mut locations = Vec::new();
+/// let mut parser = match AnyParser::from_seekable_reader(&mut file)? {
+///     AnyParser::V7400(parser) => parser,
+///     _ => panic!("Unsupported FBX version"),
+/// };
+/// loop {
+///     match parser.next_event()? {
+///         Event::StartNode(start) if start.name() == "P" => {
+///             let mut attrs = start.attributes();
+///             let name = attrs.load_next(DirectLoader)?;
+///             if name.as_ref().and_then(AttributeValue::get_string) == Some("UnitScaleFactor") {
+///                 // Skip the type name, the label, and the flags.
+///                 for _ in 0..3 {
+///                     attrs.locate_next()?;
+///                 }
+///                 locations.extend(attrs.locate_next()?);
+///             }
+///         }
+///         Event::EndFbx(_) => break,
+///         _ => {}
+///     }
+/// }
+/// drop(parser);
+///
+/// let mut patcher = AttributePatcher::new(&mut file);
+/// for location in &locations {
+///     patcher.patch(location, &AttributeValue::F64(100.0))?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AttributePatcher<F> {
+    /// FBX data.
+    file: F,
+}
+
+impl<F: Read + Write + Seek> AttributePatcher<F> {
+    /// Creates a new `AttributePatcher`.
+    ///
+    /// The given file should contain the whole binary FBX data from the
+    /// beginning.
+    #[inline]
+    #[must_use]
+    pub fn new(file: F) -> Self {
+        Self { file }
+    }
+
+    /// Returns the inner file.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> F {
+        self.file
+    }
+
+    /// Locates the attribute of the node specified by the names.
+    ///
+    /// `node_path` is the names of the nodes from a top-level node to the
+    /// target node. The first node matching the path and having the attribute
+    /// at `attribute_index` is used.
+    /// Subtrees with non-matching names are skipped without reading
+    /// attributes.
+    ///
+    /// Returns `Ok(None)` if there are no such attributes.
+    pub fn locate(
+        &mut self,
+        node_path: &[&str],
+        attribute_index: usize,
+    ) -> Result<Option<AttributeLocation>, PatchError> {
+        if node_path.is_empty() {
+            return Ok(None);
+        }
+
+        self.file.seek(SeekFrom::Start(0))?;
+        let AnyParser::V7400(mut parser) = AnyParser::from_seekable_reader(&mut self.file)?;
+        // Number of open nodes matching the path.
+        let mut depth = 0;
+        loop {
+            match parser.next_event()? {
+                Event::StartNode(start) => {
+                    if start.name() != node_path[depth] {
+                        parser.skip_current_node()?;
+                        continue;
+                    }
+                    if depth + 1 < node_path.len() {
+                        depth += 1;
+                        continue;
+                    }
+                    let mut attrs = start.attributes();
+                    for _ in 0..attribute_index {
+                        attrs.locate_next()?;
+                    }
+                    if let Some(location) = attrs.locate_next()? {
+                        debug!("Located attribute: {:?}", location);
+                        return Ok(Some(location));
+                    }
+                    parser.skip_current_node()?;
+                }
+                Event::EndNode => depth -= 1,
+                Event::EndFbx(_) => return Ok(None),
+            }
+        }
+    }
+
+    /// Overwrites the attribute at the given location with the given value.
+    ///
+    /// The type of the value can differ from the old one, as long as their
+    /// encoded sizes are same.
+    ///
+    /// Note that this does not flush the file.
+    pub fn patch(
+        &mut self,
+        location: &AttributeLocation,
+        value: &AttributeValue,
+    ) -> Result<(), PatchError> {
+        let size = encoded_size(value);
+        if size != location.size() {
+            return Err(PatchError::SizeMismatch {
+                expected: location.size(),
+                actual: size,
+            });
+        }
+        let bytes = encode(value);
+        debug_assert_eq!(bytes.len() as u64, size);
+
+        self.file.seek(SeekFrom::Start(location.offset()))?;
+        self.file.write_all(&bytes)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the encoded size of the attribute in bytes, including the type
+/// code and headers.
+#[must_use]
+fn encoded_size(value: &AttributeValue) -> u64 {
+    /// Size of array attribute headers.
+    const ARRAY_HEADER_SIZE: u64 = 12;
+    /// Size of binary and string attribute headers.
+    const SPECIAL_HEADER_SIZE: u64 = 4;
+
+    let value_size = match value {
+        AttributeValue::Bool(_) => 1,
+        AttributeValue::I16(_) => 2,
+        AttributeValue::I32(_) | AttributeValue::F32(_) => 4,
+        AttributeValue::I64(_) | AttributeValue::F64(_) => 8,
+        AttributeValue::ArrBool(v) => ARRAY_HEADER_SIZE + v.len() as u64,
+        AttributeValue::ArrI32(v) => ARRAY_HEADER_SIZE + 4 * v.len() as u64,
+        AttributeValue::ArrI64(v) => ARRAY_HEADER_SIZE + 8 * v.len() as u64,
+        AttributeValue::ArrF32(v) => ARRAY_HEADER_SIZE + 4 * v.len() as u64,
+        AttributeValue::ArrF64(v) => ARRAY_HEADER_SIZE + 8 * v.len() as u64,
+        AttributeValue::Binary(v) => SPECIAL_HEADER_SIZE + v.len() as u64,
+        AttributeValue::String(v) => SPECIAL_HEADER_SIZE + v.len() as u64,
+    };
+    1 + value_size
+}
+
+/// Encodes the attribute, including the type code and headers.
+///
+/// Arrays are not compressed.
+///
+/// # Panics
+///
+/// Panics if the value is too large to be an attribute.
+#[must_use]
+fn encode(value: &AttributeValue) -> Vec<u8> {
+    /// Converts the length to `u32`.
+    fn len_u32(len: usize) -> u32 {
+        u32::try_from(len).expect("Should never fail: the size is checked by the caller")
+    }
+    /// Appends the array header and the elements.
+    fn push_array<T: IntoBytes + Copy>(bytes: &mut Vec<u8>, values: &[T], elem_size: usize) {
+        bytes.extend_from_slice(&len_u32(values.len()).to_le_bytes());
+        // Direct (uncompressed) encoding.
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&len_u32(values.len() * elem_size).to_le_bytes());
+        for &v in values {
+            v.call_with_le_bytes(|v| bytes.extend_from_slice(v));
+        }
+    }
+    /// Appends the special attribute header and the bytes.
+    fn push_special(bytes: &mut Vec<u8>, values: &[u8]) {
+        bytes.extend_from_slice(&len_u32(values.len()).to_le_bytes());
+        bytes.extend_from_slice(values);
+    }
+
+    let mut bytes = Vec::with_capacity(encoded_size(value) as usize);
+    bytes.push(value.type_().type_code());
+    match value {
+        AttributeValue::Bool(v) => v.call_with_le_bytes(|v| bytes.extend_from_slice(v)),
+        AttributeValue::I16(v) => v.call_with_le_bytes(|v| bytes.extend_from_slice(v)),
+        AttributeValue::I32(v) => v.call_with_le_bytes(|v| bytes.extend_from_slice(v)),
+        AttributeValue::I64(v) => v.call_with_le_bytes(|v| bytes.extend_from_slice(v)),
+        AttributeValue::F32(v) => v.call_with_le_bytes(|v| bytes.extend_from_slice(v)),
+        AttributeValue::F64(v) => v.call_with_le_bytes(|v| bytes.extend_from_slice(v)),
+        AttributeValue::ArrBool(v) => push_array(&mut bytes, v, 1),
+        AttributeValue::ArrI32(v) => push_array(&mut bytes, v, 4),
+        AttributeValue::ArrI64(v) => push_array(&mut bytes, v, 8),
+        AttributeValue::ArrF32(v) => push_array(&mut bytes, v, 4),
+        AttributeValue::ArrF64(v) => push_array(&mut bytes, v, 8),
+        AttributeValue::Binary(v) => push_special(&mut bytes, v),
+        AttributeValue::String(v) => push_special(&mut bytes, v.as_bytes()),
+    }
+
+    bytes
+}
@@ -0,0 +1,89 @@
+//! Writer destination.
+//!
+//! FBX writers require the current position of the destination very frequently
+//! (for example, to calculate node end offsets and attribute lengths).
+//! Querying the underlying stream by [`std::io::Seek::stream_position`] can be
+//! costly (for example, [`std::io::BufWriter`] flushes its buffer on seek), so
+//! the position is tracked by the `Sink<W>` wrapper type.
+
+use std::io::{self, Seek, SeekFrom, Write};
+
+/// A wrapper type of the destination writer.
+#[derive(Debug, Clone)]
+pub(crate) struct Sink<W> {
+    /// Inner stream.
+    inner: W,
+    /// Cached current stream position.
+    position: u64,
+}
+
+impl<W: Write> Sink<W> {
+    /// Creates a new sink.
+    ///
+    /// `current_position` should be the current position of the inner stream.
+    #[inline]
+    #[must_use]
+    pub(crate) fn new(inner: W, current_position: u64) -> Self {
+        Self {
+            inner,
+            position: current_position,
+        }
+    }
+
+    /// Returns the current position.
+    #[inline]
+    #[must_use]
+    pub(crate) fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Returns the inner stream.
+    #[inline]
+    #[must_use]
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Seeks to the given absolute position.
+    ///
+    /// The seek is skipped if the sink is already at the given position.
+    pub(crate) fn seek_to(&mut self, pos: u64) -> io::Result<()>
+    where
+        W: Seek,
+    {
+        if pos != self.position {
+            self.position = self.inner.seek(SeekFrom::Start(pos))?;
+        }
+        Ok(())
+    }
+
+    /// Advances the position counter.
+    #[inline]
+    fn advance(&mut self, n: usize) {
+        self.position = self
+            .position
+            .checked_add(n as u64)
+            .expect("Position overflowed");
+    }
+}
+
+impl<W: Write> Write for Sink<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let size = self.inner.write(buf)?;
+        self.advance(size);
+        Ok(size)
+    }
+
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.inner.write_all(buf)?;
+        self.advance(buf.len());
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,35 @@
+//! FBX writer version types.
+
+use log::info;
+
+use crate::low::FbxVersion;
+
+/// Writer version for each version of FBX.
+///
+/// Some writer supports multiple versions of FBX binary.
+/// Each variants of this type corresponds to a writer implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum WriterVersion {
+    /// FBX 7.4 and 7.5.
+    V7400,
+}
+
+impl WriterVersion {
+    /// Returns the writer version corresponding to the given FBX version.
+    #[must_use]
+    pub fn from_fbx_version(fbx_version: FbxVersion) -> Option<Self> {
+        let raw = fbx_version.raw();
+        match raw {
+            7000..=7999 => {
+                if raw < 7400 {
+                    info!("<FBX-7.4 might be successfully written, but unsupported");
+                } else if raw > 7500 {
+                    info!(">FBX-7.5 might be successfully written, but unsupported");
+                }
+                Some(WriterVersion::V7400)
+            }
+            _ => None,
+        }
+    }
+}
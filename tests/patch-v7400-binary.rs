@@ -0,0 +1,180 @@
+//! Tests for in-place attribute patcher.
+#![cfg(all(feature = "tree", feature = "writer"))]
+
+use std::io::Cursor;
+
+use fbxcel::{
+    low::{
+        v7400::{ArrayAttributeEncoding, AttributeType, AttributeValue},
+        FbxVersion,
+    },
+    pull_parser::{
+        any::AnyParser,
+        v7400::{attribute::loaders::DirectLoader, Event},
+    },
+    tree::v7400::{Loader as TreeLoader, Tree},
+    tree_v7400,
+    writer::v7400::binary::{AttributePatcher, PatchError, Writer},
+};
+
+/// Writes the tree as binary FBX data.
+fn write(tree: &Tree) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.write_tree(tree)?;
+    Ok(writer.finalize_and_flush(&Default::default())?.into_inner())
+}
+
+/// Loads the tree from binary FBX data.
+fn load(bin: &[u8]) -> Result<Tree, Box<dyn std::error::Error>> {
+    let mut parser = match AnyParser::from_seekable_reader(Cursor::new(bin))? {
+        AnyParser::V7400(parser) => parser,
+        _ => panic!("Generated data should be parsable with v7400 parser"),
+    };
+    let (tree, footer_res) = TreeLoader::new().load(&mut parser)?;
+    assert!(footer_res.is_ok());
+    Ok(tree)
+}
+
+/// Patch attributes located by node names.
+#[test]
+fn patch_by_path() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = tree_v7400! {
+        Header: {
+            Version: [7400i32] {},
+            Creator: ["Program A"] {},
+        },
+        Objects: {
+            Model: [1i64] {},
+            Model: [2i64, "Cube", true] {
+                Vertices: [vec![0.0f64; 3]] {},
+            },
+        },
+    };
+    let mut patcher = AttributePatcher::new(Cursor::new(write(&tree)?));
+
+    // Scalars, including type changes with the same size.
+    let location = patcher.locate(&["Header", "Version"], 0)?.unwrap();
+    assert_eq!(location.attribute_type(), AttributeType::I32);
+    assert_eq!(location.size(), 5);
+    patcher.patch(&location, &AttributeValue::I32(7500))?;
+    let location = patcher.locate(&["Objects", "Model"], 2)?.unwrap();
+    assert_eq!(location.attribute_type(), AttributeType::Bool);
+    patcher.patch(&location, &AttributeValue::Bool(false))?;
+    let location = patcher.locate(&["Objects", "Model"], 0)?.unwrap();
+    patcher.patch(&location, &AttributeValue::F64(1.5))?;
+
+    // Strings, binaries, and arrays with the same length.
+    let location = patcher.locate(&["Header", "Creator"], 0)?.unwrap();
+    patcher.patch(&location, &AttributeValue::Binary(b"Program B".to_vec()))?;
+    let location = patcher
+        .locate(&["Objects", "Model", "Vertices"], 0)?
+        .unwrap();
+    patcher.patch(&location, &AttributeValue::ArrI64(vec![1, 2, 3]))?;
+
+    // Missing nodes and attributes.
+    assert_eq!(patcher.locate(&["Objects", "Geometry"], 0)?, None);
+    assert_eq!(patcher.locate(&["Header", "Version"], 1)?, None);
+    assert_eq!(patcher.locate(&[], 0)?, None);
+
+    let expected = tree_v7400! {
+        Header: {
+            Version: [7500i32] {},
+            Creator: [b"Program B".to_vec()] {},
+        },
+        Objects: {
+            Model: [1.5f64] {},
+            Model: [2i64, "Cube", false] {
+                Vertices: [vec![1i64, 2, 3]] {},
+            },
+        },
+    };
+    assert!(load(&patcher.into_inner().into_inner())?.strict_eq(&expected));
+
+    Ok(())
+}
+
+/// Patch attributes located by the pull parser.
+#[test]
+fn patch_located_by_parser() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = tree_v7400! {
+        Properties70: {
+            P: ["UpAxis", "int", "Integer", "", 1i32] {},
+            P: ["UnitScaleFactor", "double", "Number", "", 1.0f64] {},
+        },
+    };
+    let mut file = Cursor::new(write(&tree)?);
+
+    let mut locations = Vec::new();
+    let mut parser = match AnyParser::from_seekable_reader(&mut file)? {
+        AnyParser::V7400(parser) => parser,
+        _ => panic!("Generated data should be parsable with v7400 parser"),
+    };
+    loop {
+        match parser.next_event()? {
+            Event::StartNode(start) if start.name() == "P" => {
+                let mut attrs = start.attributes();
+                let name = attrs.load_next(DirectLoader)?;
+                if name.as_ref().and_then(AttributeValue::get_string) == Some("UnitScaleFactor") {
+                    for _ in 0..3 {
+                        attrs.locate_next()?;
+                    }
+                    locations.extend(attrs.locate_next()?);
+                    assert_eq!(attrs.locate_next()?, None);
+                }
+            }
+            Event::EndFbx(_) => break,
+            _ => {}
+        }
+    }
+    drop(parser);
+    assert_eq!(locations.len(), 1);
+
+    let mut patcher = AttributePatcher::new(&mut file);
+    patcher.patch(&locations[0], &AttributeValue::F64(100.0))?;
+
+    let expected = tree_v7400! {
+        Properties70: {
+            P: ["UpAxis", "int", "Integer", "", 1i32] {},
+            P: ["UnitScaleFactor", "double", "Number", "", 100.0f64] {},
+        },
+    };
+    assert!(load(file.get_ref())?.strict_eq(&expected));
+
+    Ok(())
+}
+
+/// Values with different sizes are refused without modifying the data.
+#[test]
+fn refuse_size_mismatch() -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    {
+        let mut attrs = writer.new_node("Node")?;
+        attrs.append_i32(1)?;
+        attrs.append_string_direct("abc")?;
+        attrs.append_arr_i32(ArrayAttributeEncoding::Zlib, &[0; 64])?;
+    }
+    writer.close_node()?;
+    let bin = writer.finalize_and_flush(&Default::default())?.into_inner();
+    let mut patcher = AttributePatcher::new(Cursor::new(bin.clone()));
+
+    let cases = [
+        (0, AttributeValue::I64(1), Some(5), 9),
+        (1, AttributeValue::from("abcd"), Some(8), 9),
+        // The size of the compressed array depends on the compressor.
+        (2, AttributeValue::ArrI32(vec![0; 64]), None, 269),
+    ];
+    for (index, value, expected_size, actual_size) in cases {
+        let location = patcher.locate(&["Node"], index)?.unwrap();
+        let expected_size = expected_size.unwrap_or_else(|| location.size());
+        match patcher.patch(&location, &value) {
+            Err(PatchError::SizeMismatch { expected, actual }) => {
+                assert_eq!(expected, expected_size);
+                assert_eq!(actual, actual_size);
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+    assert_eq!(patcher.into_inner().into_inner(), bin);
+
+    Ok(())
+}
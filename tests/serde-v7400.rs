@@ -0,0 +1,228 @@
+//! Tests for serde support.
+#![cfg(all(feature = "tree", feature = "serde"))]
+
+use fbxcel::{
+    low::{
+        v7400::{AttributeType, AttributeValue},
+        FbxVersion,
+    },
+    tree::v7400::Tree,
+    tree_v7400,
+};
+
+/// Returns a tree with attributes of all types and edge-case values.
+fn gentree() -> Tree {
+    tree_v7400! {
+        Header: {
+            Version: [7400i32] {},
+            "Node with spaces": ["", "Hello, world"] {},
+        },
+        Scalars: [true, 42i16, 42i32, 42i64, 0.1f32, 0.1f64] {
+            Floats32: [
+                -0.0f32,
+                f32::MIN_POSITIVE,
+                f32::from_bits(1),
+                f32::MAX,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+                f32::NAN,
+                f32::from_bits(0xffc0_1234),
+            ] {},
+            Floats64: [
+                -0.0f64,
+                f64::MIN_POSITIVE,
+                f64::from_bits(1),
+                f64::MAX,
+                1.0f64 / 3.0,
+                f64::NEG_INFINITY,
+                f64::NAN,
+                f64::from_bits(0x7ff0_0000_0000_0001),
+            ] {},
+        },
+        Arrays: [
+            vec![true, false],
+            vec![i32::MIN, 0, i32::MAX],
+            vec![i64::MIN, 0, i64::MAX],
+            vec![0.1f32, f32::NAN, -0.0],
+            vec![0.1f64, f64::INFINITY, -0.0],
+        ] {
+            Empty: [Vec::<f32>::new(), Vec::<u8>::new()] {},
+        },
+        Binary: [(0..=255u8).cycle().take(1024).collect::<Vec<_>>()] {},
+    }
+}
+
+/// JSON round trips preserve attribute types and values.
+#[test]
+fn json_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = gentree();
+
+    let json = serde_json::to_string(&tree)?;
+    let deserialized: Tree = serde_json::from_str(&json)?;
+    assert!(deserialized.strict_eq(&tree));
+
+    let json = serde_json::to_string_pretty(&tree)?;
+    let deserialized: Tree = serde_json::from_str(&json)?;
+    assert!(deserialized.strict_eq(&tree));
+
+    // Via a JSON value.
+    let value = serde_json::to_value(&tree)?;
+    let deserialized: Tree = serde_json::from_value(value)?;
+    assert!(deserialized.strict_eq(&tree));
+
+    Ok(())
+}
+
+/// MessagePack round trips preserve attribute types and values.
+#[test]
+fn msgpack_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = gentree();
+
+    // Structs as arrays.
+    let msgpack = rmp_serde::to_vec(&tree)?;
+    let deserialized: Tree = rmp_serde::from_slice(&msgpack)?;
+    assert!(deserialized.strict_eq(&tree));
+
+    // Structs as maps.
+    let msgpack_named = rmp_serde::to_vec_named(&tree)?;
+    let deserialized: Tree = rmp_serde::from_slice(&msgpack_named)?;
+    assert!(deserialized.strict_eq(&tree));
+
+    // Binary is serialized as bytes, not as a sequence of integers.
+    let binary = tree_v7400! {
+        Binary: [vec![0xffu8; 1024]] {},
+    };
+    assert!(rmp_serde::to_vec(&binary)?.len() < 1024 + 32);
+
+    Ok(())
+}
+
+/// Attribute types and FBX versions can be serialized.
+#[test]
+fn types_and_versions() -> Result<(), Box<dyn std::error::Error>> {
+    let types = [
+        AttributeType::Bool,
+        AttributeType::I16,
+        AttributeType::F32,
+        AttributeType::ArrF64,
+        AttributeType::Binary,
+        AttributeType::String,
+    ];
+    let json = serde_json::to_string(&types)?;
+    assert_eq!(json, r#"["Bool","I16","F32","ArrF64","Binary","String"]"#);
+    assert_eq!(serde_json::from_str::<Vec<AttributeType>>(&json)?, types);
+
+    assert_eq!(serde_json::to_string(&FbxVersion::V7_4)?, "7400");
+    assert_eq!(
+        serde_json::from_str::<FbxVersion>("7500")?,
+        FbxVersion::V7_5
+    );
+
+    let value = AttributeValue::ArrI64(vec![1, 2, 3]);
+    let msgpack = rmp_serde::to_vec(&value)?;
+    assert_eq!(rmp_serde::from_slice::<AttributeValue>(&msgpack)?, value);
+
+    Ok(())
+}
+
+/// Nodes loaded from a parser can be deserialized into user types.
+#[cfg(feature = "writer")]
+#[test]
+fn deserialize_loaded_subtrees() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Cursor;
+
+    use fbxcel::{
+        pull_parser::{any::AnyParser, v7400::Event},
+        tree::v7400::Loader,
+        writer::v7400::binary::Writer,
+    };
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Geometry {
+        #[serde(rename = "$attributes")]
+        attributes: (i64, String, String),
+        #[serde(rename = "Vertices")]
+        vertices: Option<Vertices>,
+        #[serde(rename = "Properties70")]
+        properties: Option<Properties70>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Vertices(Vec<f64>);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Properties70 {
+        #[serde(rename = "P", default)]
+        props: Vec<(String, String, String, String, AttributeValue)>,
+    }
+
+    let tree = tree_v7400! {
+        Objects: {
+            Geometry: [2i64, "Cube\u{0}\u{1}Geometry", "Mesh"] {
+                Properties70: {
+                    P: "Color", "ColorRGB", "Color", "", 0.8f64;
+                    P: "Visibility", "Visibility", "", "A", true;
+                },
+                Vertices: [vec![0.5f64; 6]] {},
+            },
+            Geometry: [3i64, "Sphere\u{0}\u{1}Geometry", "Mesh"] {},
+        },
+    };
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.write_tree(&tree)?;
+    let bin = writer.finalize_and_flush(&Default::default())?.into_inner();
+
+    let mut parser = match AnyParser::from_seekable_reader(Cursor::new(bin))? {
+        AnyParser::V7400(parser) => parser,
+        _ => panic!("Generated data should be parsable with v7400 parser"),
+    };
+    let mut geometries = Vec::new();
+    loop {
+        match parser.next_event()? {
+            Event::StartNode(start) if start.name() == "Geometry" => {
+                let subtree = Loader::new().load_subtree(start)?;
+                let node = subtree.root().first_child().expect("Should be loaded");
+                geometries.push(node.deserialize::<Geometry>()?);
+            }
+            Event::EndFbx(_) => break,
+            _ => {}
+        }
+    }
+
+    assert_eq!(
+        geometries,
+        [
+            Geometry {
+                attributes: (2, "Cube\u{0}\u{1}Geometry".to_owned(), "Mesh".to_owned()),
+                vertices: Some(Vertices(vec![0.5; 6])),
+                properties: Some(Properties70 {
+                    props: vec![
+                        (
+                            "Color".to_owned(),
+                            "ColorRGB".to_owned(),
+                            "Color".to_owned(),
+                            "".to_owned(),
+                            AttributeValue::F64(0.8),
+                        ),
+                        (
+                            "Visibility".to_owned(),
+                            "Visibility".to_owned(),
+                            "".to_owned(),
+                            "A".to_owned(),
+                            AttributeValue::Bool(true),
+                        ),
+                    ],
+                }),
+            },
+            Geometry {
+                attributes: (3, "Sphere\u{0}\u{1}Geometry".to_owned(), "Mesh".to_owned()),
+                vertices: None,
+                properties: None,
+            },
+        ]
+    );
+
+    Ok(())
+}
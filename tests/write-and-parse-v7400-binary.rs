@@ -268,3 +268,80 @@ fn macro_v7400_idempotence() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Checks that array attributes written from slices are identical to the ones
+/// written from iterators, and that they are parsed correctly.
+#[test]
+fn array_from_slice_v7400() -> Result<(), Box<dyn std::error::Error>> {
+    use fbxcel::low::v7400::ArrayAttributeEncoding;
+
+    let bools = [true, false, true, true];
+    let ints = [0i32, 1, -1, i32::MAX, i32::MIN];
+    let floats = [0.0f64, -0.0, 1.5, f64::INFINITY, f64::NAN];
+
+    let mut bins = Vec::new();
+    for &from_slice in &[false, true] {
+        // Use `BufWriter` to ensure the writer does not depend on the
+        // position reported by the sink.
+        let sink = std::io::BufWriter::new(Cursor::new(Vec::new()));
+        let mut writer = Writer::new(sink, FbxVersion::V7_4)?;
+        for &encoding in &[ArrayAttributeEncoding::Direct, ArrayAttributeEncoding::Zlib] {
+            let mut attrs = writer.new_node("Arrays")?;
+            if from_slice {
+                attrs.append_arr_bool(encoding, &bools)?;
+                attrs.append_arr_i32(encoding, &ints)?;
+                attrs.append_arr_f64(encoding, &floats)?;
+            } else {
+                attrs.append_arr_bool_from_iter(encoding, bools.iter().cloned())?;
+                attrs.append_arr_i32_from_iter(encoding, ints.iter().cloned())?;
+                attrs.append_arr_f64_from_iter(encoding, floats.iter().cloned())?;
+            }
+            writer.close_node()?;
+        }
+        let bin = writer
+            .finalize_and_flush(&Default::default())?
+            .into_inner()?
+            .into_inner();
+        bins.push(bin);
+    }
+    assert_eq!(bins[0], bins[1]);
+
+    let mut parser = match AnyParser::from_seekable_reader(Cursor::new(bins.pop().unwrap()))? {
+        AnyParser::V7400(parser) => parser,
+        _ => panic!("Generated data should be parsable with v7400 parser"),
+    };
+    let warnings = Rc::new(RefCell::new(Vec::new()));
+    parser.set_warning_handler({
+        let warnings = warnings.clone();
+        move |warning, _pos| {
+            warnings.borrow_mut().push(warning);
+            Ok(())
+        }
+    });
+
+    for _ in 0..2 {
+        let mut attrs = expect_node_start(&mut parser, "Arrays")?;
+        assert_eq!(attrs.total_count(), 3);
+        assert_eq!(
+            attrs.load_next(DirectLoader)?,
+            Some(AttributeValue::from(&bools[..]))
+        );
+        assert_eq!(
+            attrs.load_next(DirectLoader)?,
+            Some(AttributeValue::from(&ints[..]))
+        );
+        assert!(attrs
+            .load_next(DirectLoader)?
+            .map_or(false, |attr| attr.strict_eq(&floats[..].into())));
+        expect_node_end(&mut parser)?;
+    }
+
+    {
+        let footer_res = expect_fbx_end(&mut parser)?;
+        assert!(footer_res.is_ok());
+    }
+
+    assert_eq!(warnings.borrow().len(), 0);
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+//! Tests for fault injection of the writer.
+#![cfg(feature = "writer")]
+
+use std::{cell::RefCell, io::Cursor, rc::Rc};
+
+use fbxcel::{
+    low::{
+        v7400::{ArrayAttributeEncoding, AttributeValue},
+        FbxVersion,
+    },
+    pull_parser::{
+        any::AnyParser,
+        error::{DataError, Warning},
+        v7400::{attribute::loaders::DirectLoader, Parser},
+    },
+    writer::v7400::binary::{FbxFooter, Writer},
+};
+
+use self::v7400::writer::{
+    expect_fbx_end, expect_node_end, expect_node_start, CUSTOM_UNKNOWN1, MAGIC, UNKNOWN3,
+};
+
+mod v7400;
+
+/// Shared list of warnings.
+type Warnings = Rc<RefCell<Vec<Warning>>>;
+
+/// Creates a parser for the given data, and returns it with the warnings.
+fn parse(data: Vec<u8>) -> (Parser<Cursor<Vec<u8>>>, Warnings) {
+    let mut parser = match AnyParser::from_seekable_reader(Cursor::new(data))
+        .expect("Generated data should be parsable")
+    {
+        AnyParser::V7400(parser) => parser,
+        _ => panic!("Generated data should be parsable with v7400 parser"),
+    };
+    let warnings = Rc::new(RefCell::new(Vec::new()));
+    parser.set_warning_handler({
+        let warnings = warnings.clone();
+        move |warning, _pos| {
+            warnings.borrow_mut().push(warning);
+            Ok(())
+        }
+    });
+    (parser, warnings)
+}
+
+/// Writes missing and extra node end markers.
+#[test]
+fn node_end_marker_faults() -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.new_node("Container")?;
+    {
+        writer.new_node("MissingEnd")?;
+        writer.fault_injection_mut().omit_node_end_marker = true;
+        writer.close_node()?;
+        writer.fault_injection_mut().omit_node_end_marker = false;
+    }
+    {
+        writer.new_node("ExtraEnd")?.append_i32(42)?;
+        writer.fault_injection_mut().extra_node_end_marker = true;
+        writer.close_node()?;
+        writer.fault_injection_mut().extra_node_end_marker = false;
+    }
+    writer.close_node()?;
+    let footer = FbxFooter {
+        unknown1: Some(&CUSTOM_UNKNOWN1),
+        ..Default::default()
+    };
+    let bin = writer.finalize_and_flush(&footer)?.into_inner();
+    assert!(bin.starts_with(MAGIC));
+
+    let (mut parser, warnings) = parse(bin);
+    expect_node_start(&mut parser, "Container")?;
+    expect_node_start(&mut parser, "MissingEnd")?;
+    expect_node_end(&mut parser)?;
+    expect_node_start(&mut parser, "ExtraEnd")?;
+    expect_node_end(&mut parser)?;
+    expect_node_end(&mut parser)?;
+    {
+        let footer = expect_fbx_end(&mut parser)??;
+        assert_eq!(footer.unknown1, CUSTOM_UNKNOWN1);
+        assert_eq!(footer.unknown3, UNKNOWN3);
+    }
+
+    match &warnings.borrow()[..] {
+        [Warning::MissingNodeEndMarker, Warning::ExtraNodeEndMarker] => {}
+        v => panic!("Unexpected warnings: {:?}", v),
+    }
+
+    Ok(())
+}
+
+/// Writes booleans with incorrect representation.
+#[test]
+fn bool_bytes_fault() -> Result<(), Box<dyn std::error::Error>> {
+    let bools = [true, false, true];
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.fault_injection_mut().bool_bytes = Some((b'0', b'1'));
+    {
+        let mut attrs = writer.new_node("Bools")?;
+        attrs.append_bool(true)?;
+        attrs.append_arr_bool(None, &bools)?;
+        attrs.append_arr_bool_from_iter(ArrayAttributeEncoding::Zlib, bools.iter().cloned())?;
+    }
+    writer.close_node()?;
+    let bin = writer.finalize_and_flush(&Default::default())?.into_inner();
+    assert_eq!(bin.windows(3).filter(|w| *w == b"101").count(), 1);
+
+    let (mut parser, warnings) = parse(bin);
+    {
+        let mut attrs = expect_node_start(&mut parser, "Bools")?;
+        assert_eq!(attrs.load_next(DirectLoader)?, Some(true.into()));
+        for _ in 0..2 {
+            assert_eq!(
+                attrs.load_next(DirectLoader)?,
+                Some(AttributeValue::from(&bools[..]))
+            );
+        }
+    }
+    expect_node_end(&mut parser)?;
+    assert!(expect_fbx_end(&mut parser)?.is_ok());
+
+    let warnings = warnings.borrow();
+    assert_eq!(warnings.len(), 3);
+    assert!(warnings
+        .iter()
+        .all(|w| matches!(w, Warning::IncorrectBooleanRepresentation)));
+
+    Ok(())
+}
+
+/// Writes a truncated zlib stream.
+#[test]
+fn zlib_truncate_fault() -> Result<(), Box<dyn std::error::Error>> {
+    let ints: Vec<i32> = (0..256).collect();
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.fault_injection_mut().zlib_truncate_len = 16;
+    writer
+        .new_node("Ints")?
+        .append_arr_i32(ArrayAttributeEncoding::Zlib, &ints)?;
+    writer.close_node()?;
+    let bin = writer.finalize_and_flush(&Default::default())?.into_inner();
+
+    let (mut parser, _warnings) = parse(bin);
+    let mut attrs = expect_node_start(&mut parser, "Ints")?;
+    assert!(
+        attrs.load_next(DirectLoader).is_err(),
+        "Truncated zlib stream should be detected"
+    );
+
+    Ok(())
+}
+
+/// Writes a wrong node end offset.
+#[test]
+fn end_offset_fault() -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_5)?;
+    writer.new_node("Node")?;
+    writer.fault_injection_mut().end_offset_delta = 1;
+    writer.close_node()?;
+    writer.fault_injection_mut().end_offset_delta = 0;
+    let bin = writer.finalize_and_flush(&Default::default())?.into_inner();
+
+    let (mut parser, _warnings) = parse(bin);
+    expect_node_start(&mut parser, "Node")?;
+    let err = parser
+        .next_event()
+        .expect_err("Wrong end offset should be detected");
+    match err.downcast_ref::<DataError>() {
+        Some(DataError::NodeLengthMismatch(..)) => {}
+        v => panic!("Unexpected error: {:?}", v),
+    }
+
+    Ok(())
+}
@@ -51,3 +51,423 @@ fn tree_write_parse_idempotence_v7500() -> Result<(), Box<dyn std::error::Error>
 
     Ok(())
 }
+
+/// Assemble output from pieces of several trees, export it to binary, parse
+/// it and construct tree, and compare it with the expected tree.
+#[test]
+fn write_subtrees_from_multiple_trees() -> Result<(), Box<dyn std::error::Error>> {
+    let template = tree_v7400! {
+        FBXHeaderExtension: {
+            FBXVersion: [7400i32] {},
+        },
+        GlobalSettings: {
+            Version: [1000i32] {},
+            Properties70: {
+                P: ["UpAxis", "int", "Integer", "", 1i32] {},
+            },
+        },
+    };
+    let scene = tree_v7400! {
+        Objects: {
+            Model: [42i64, "Model::Cube", "Mesh"] {},
+            Geometry: [43i64, "Geometry::Cube", "Mesh"] {
+                Vertices: [vec![0.0f64, 1.0, 2.0]] {},
+            },
+        },
+        Connections: {},
+    };
+    let expected = tree_v7400! {
+        GlobalSettings: {
+            Version: [1000i32] {},
+            Properties70: {
+                P: ["UpAxis", "int", "Integer", "", 1i32] {},
+            },
+        },
+        Objects: {
+            Geometry: [43i64, "Geometry::Cube", "Mesh"] {
+                Vertices: [vec![0.0f64, 1.0, 2.0]] {},
+            },
+        },
+        Connections: {
+            Model: [42i64, "Model::Cube", "Mesh"] {},
+        },
+    };
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    {
+        let global_settings = template.root().first_child_by_name("GlobalSettings");
+        let objects = scene.root().first_child_by_name("Objects").unwrap();
+        writer.write_nodes(global_settings)?;
+        writer.new_node("Objects")?;
+        writer.write_nodes(objects.children_by_name("Geometry"))?;
+        writer.close_node()?;
+        writer.new_node("Connections")?;
+        writer.write_subtree(objects.first_child_by_name("Model").unwrap())?;
+        writer.close_node()?;
+    }
+    let bin = writer.finalize_and_flush(&Default::default())?.into_inner();
+
+    let mut parser = match AnyParser::from_seekable_reader(Cursor::new(bin))? {
+        AnyParser::V7400(parser) => parser,
+        _ => panic!("Generated data should be parsable with v7400 parser"),
+    };
+    let (loaded, footer_res) = TreeLoader::new().load(&mut parser)?;
+    assert!(footer_res.is_ok());
+
+    assert!(loaded.strict_eq(&expected));
+
+    // Writing the root node is same as writing the whole tree.
+    let mut writer_tree = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer_tree.write_tree(&scene)?;
+    let mut writer_root = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer_root.write_nodes(std::iter::once(scene.root()))?;
+    assert_eq!(
+        writer_tree.finalize(&Default::default())?.into_inner(),
+        writer_root.finalize(&Default::default())?.into_inner()
+    );
+
+    Ok(())
+}
+
+/// Write a tree through `AnyTree`, load it again, and compare them.
+#[test]
+fn any_tree_write_to() -> Result<(), Box<dyn std::error::Error>> {
+    use fbxcel::tree::any::AnyTree;
+
+    let tree = tree_v7400! {
+        Node0: [true, 42i16] {
+            Node0_0: [vec![1.0f32, 2.0]] {},
+        },
+        Node1: ["Hello, world"] {},
+    };
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.write_tree(&tree)?;
+    let bin = writer.finalize_and_flush(&Default::default())?.into_inner();
+
+    let any_tree = AnyTree::from_seekable_reader(Cursor::new(bin))?;
+    assert_eq!(any_tree.fbx_version(), FbxVersion::V7_4);
+
+    let converted = any_tree.write_to(Cursor::new(Vec::new()), FbxVersion::V7_5)?;
+    let reloaded = AnyTree::from_seekable_reader(Cursor::new(converted.into_inner()))?;
+    assert_eq!(reloaded.fbx_version(), FbxVersion::V7_5);
+    match reloaded {
+        AnyTree::V7400(_, reloaded, footer_res) => {
+            assert!(footer_res.is_ok());
+            assert!(reloaded.strict_eq(&tree));
+        }
+        _ => panic!("Generated data should be loadable as v7400 tree"),
+    }
+
+    Ok(())
+}
+
+/// Copy nodes from a parser to writers without decoding attributes.
+#[test]
+fn copy_current_node_from_parser() -> Result<(), Box<dyn std::error::Error>> {
+    use fbxcel::{
+        low::v7400::ArrayAttributeEncoding,
+        pull_parser::v7400::{attribute::loaders::DirectLoader, Event},
+    };
+
+    let tree = tree_v7400! {
+        Node0: [true, 42i16] {
+            Node0_0: {},
+            Node0_1: ["Hello", vec![1.0f32, 2.0]] {},
+        },
+        Node1: [vec![1i64; 128], 1.5f64] {},
+        Node2: {},
+    };
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.write_tree(&tree)?;
+    writer
+        .new_node("Compressed")?
+        .append_arr_i32(ArrayAttributeEncoding::Zlib, &[7; 1024])?;
+    writer.close_node()?;
+    let source = writer.finalize_and_flush(&Default::default())?.into_inner();
+
+    let copy_all = |fbx_version| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut parser = match AnyParser::from_seekable_reader(Cursor::new(source.clone()))? {
+            AnyParser::V7400(parser) => parser,
+            _ => panic!("Generated data should be parsable with v7400 parser"),
+        };
+        let mut writer = Writer::new(Cursor::new(Vec::new()), fbx_version)?;
+        loop {
+            match parser.next_event()? {
+                Event::StartNode(node) => {
+                    if node.name() == "Node1" {
+                        // Partially read attributes should not matter.
+                        let mut attrs = node.attributes();
+                        attrs.load_next(DirectLoader)?;
+                    }
+                    writer.copy_current_node(&mut parser)?;
+                    assert_eq!(parser.current_depth(), 0);
+                }
+                Event::EndNode => unreachable!("Nodes should be skipped"),
+                Event::EndFbx(footer) => {
+                    assert!(footer.is_ok());
+                    break;
+                }
+            }
+        }
+        Ok(writer.finalize_and_flush(&Default::default())?.into_inner())
+    };
+
+    // Copying into the same version should reproduce the source.
+    assert_eq!(copy_all(FbxVersion::V7_4)?, source);
+
+    // Node headers are rewritten for another version.
+    let copied = copy_all(FbxVersion::V7_5)?;
+    let load = |bin: Vec<u8>| -> Result<_, Box<dyn std::error::Error>> {
+        let mut parser = match AnyParser::from_seekable_reader(Cursor::new(bin))? {
+            AnyParser::V7400(parser) => parser,
+            _ => panic!("Generated data should be parsable with v7400 parser"),
+        };
+        let (tree, footer) = TreeLoader::new().load(&mut parser)?;
+        assert!(footer.is_ok());
+        Ok((parser.fbx_version(), tree))
+    };
+    let (source_version, source_tree) = load(source)?;
+    let (copied_version, copied_tree) = load(copied)?;
+    assert_eq!(source_version, FbxVersion::V7_4);
+    assert_eq!(copied_version, FbxVersion::V7_5);
+    assert!(source_tree.strict_eq(&copied_tree));
+
+    Ok(())
+}
+
+/// Load a tree in the compact storage mode, and compare it with the tree
+/// loaded normally.
+#[test]
+fn load_compact_storage() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = tree_v7400! {
+        Objects: {
+            Model: [1i64, "Cube\u{0}\u{1}Model", "Mesh"] {
+                Properties70: {
+                    P: ["Lcl Translation", "Lcl Translation", "", "A", 0.0f64, 1.0f64, 2.0f64] {},
+                    P: ["Visibility", "Visibility", "", "A", 1.0f64] {},
+                },
+            },
+            Geometry: [2i64, "Cube\u{0}\u{1}Geometry", "Mesh"] {
+                Vertices: [vec![0.5f64; 24]] {},
+            },
+        },
+    };
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.write_tree(&tree)?;
+    let bin = writer.finalize_and_flush(&Default::default())?.into_inner();
+
+    let load = |compact_storage: bool| -> Result<_, Box<dyn std::error::Error>> {
+        let mut parser = match AnyParser::from_seekable_reader(Cursor::new(&bin))? {
+            AnyParser::V7400(parser) => parser,
+            _ => panic!("Generated data should be parsable with v7400 parser"),
+        };
+        let (tree, footer_res) = TreeLoader::new()
+            .compact_storage(compact_storage)
+            .load(&mut parser)?;
+        assert!(footer_res.is_ok());
+        Ok(tree)
+    };
+    let normal = load(false)?;
+    let compact = load(true)?;
+
+    assert!(!normal.is_compact_storage());
+    assert!(compact.is_compact_storage());
+    assert_eq!(
+        compact.memory_usage().total().attributes,
+        normal.memory_usage().total().attributes
+    );
+    assert!(compact.memory_usage().total_bytes() < normal.memory_usage().total_bytes());
+
+    // Loading in the compact storage mode and converting after loading give
+    // the same storage.
+    let mut converted = normal.clone();
+    converted.set_compact_storage(true);
+    assert_eq!(compact.memory_usage(), converted.memory_usage());
+
+    // Reading the whole tree does not copy the attributes.
+    let usage = compact.memory_usage();
+    assert!(compact.strict_eq(&tree));
+    assert!(compact.strict_eq(&normal));
+    assert!(compact.approx_eq(&normal, &Default::default()));
+    assert!(compact.diff(&normal, &Default::default()).is_empty());
+    assert_eq!(compact.root().content_hash(), normal.root().content_hash());
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.write_tree(&compact)?;
+    assert_eq!(
+        writer.finalize_and_flush(&Default::default())?.into_inner(),
+        bin
+    );
+    assert_eq!(compact.memory_usage(), usage);
+    assert!(usage.total_bytes() < normal.memory_usage().total_bytes());
+
+    Ok(())
+}
+
+/// Load subtrees from the current nodes of a parser, and continue parsing.
+#[test]
+fn load_subtree_from_parser() -> Result<(), Box<dyn std::error::Error>> {
+    use fbxcel::pull_parser::v7400::Event;
+
+    let tree = tree_v7400! {
+        Objects: {
+            Model: [1i64, "Cube\u{0}\u{1}Model", "Mesh"] {
+                Properties70: {
+                    P: ["Visibility", "Visibility", "", "A", 1.0f64] {},
+                },
+            },
+            Geometry: [2i64, "Cube\u{0}\u{1}Geometry", "Mesh"] {
+                Vertices: [vec![0.5f64; 24]] {},
+            },
+            Geometry: [3i64, "Sphere\u{0}\u{1}Geometry", "Mesh"] {},
+        },
+        Connections: {},
+    };
+    let expected_geometries = [
+        tree_v7400! {
+            Geometry: [2i64, "Cube\u{0}\u{1}Geometry", "Mesh"] {
+                Vertices: [vec![0.5f64; 24]] {},
+            },
+        },
+        tree_v7400! {
+            Geometry: [3i64, "Sphere\u{0}\u{1}Geometry", "Mesh"] {},
+        },
+    ];
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.write_tree(&tree)?;
+    let bin = writer.finalize_and_flush(&Default::default())?.into_inner();
+
+    let mut parser = match AnyParser::from_seekable_reader(Cursor::new(bin))? {
+        AnyParser::V7400(parser) => parser,
+        _ => panic!("Generated data should be parsable with v7400 parser"),
+    };
+    let mut geometries = Vec::new();
+    let mut other_nodes = Vec::new();
+    let footer_res = loop {
+        match parser.next_event()? {
+            Event::StartNode(start) if start.name() == "Geometry" => {
+                let compact_storage = geometries.len() % 2 == 1;
+                geometries.push(
+                    TreeLoader::new()
+                        .compact_storage(compact_storage)
+                        .load_subtree(start)?,
+                );
+            }
+            Event::StartNode(start) => other_nodes.push(start.name().to_owned()),
+            Event::EndNode => {}
+            Event::EndFbx(footer_res) => break footer_res,
+        }
+    };
+    assert!(footer_res.is_ok());
+
+    assert_eq!(geometries.len(), expected_geometries.len());
+    for (loaded, expected) in geometries.iter().zip(&expected_geometries) {
+        assert!(loaded.strict_eq(expected));
+    }
+    assert_eq!(
+        other_nodes,
+        ["Objects", "Model", "Properties70", "P", "Connections"]
+    );
+
+    Ok(())
+}
+
+/// Write and construct the same data with the extended macro syntax.
+#[test]
+fn extended_macro_syntax() -> Result<(), Box<dyn std::error::Error>> {
+    use fbxcel::{low::v7400::ArrayAttributeEncoding, write_v7400_binary};
+
+    let models = ["Cube", "Sphere", "Cone"];
+    let expected = tree_v7400! {
+        "Node with spaces": {},
+        Objects: {
+            for (i, name) in (models.iter().enumerate()) {
+                (format!("Model::{}", name)): [i as i64] {
+                    Properties70: {
+                        P: "Visibility", "Visibility", "", "A", i != 1;
+                    }
+                }
+            }
+        },
+        Vertices: [vec![0.0f64; 128] => Zlib] {},
+    };
+
+    let write = |encoding| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+        write_v7400_binary!(
+            writer=writer,
+            tree={
+                "Node with spaces": {},
+                Objects: {
+                    for (i, name) in (models.iter().enumerate()) {
+                        (format!("Model::{}", name)): [i as i64] {
+                            Properties70: {
+                                P: "Visibility", "Visibility", "", "A", i != 1;
+                            }
+                        }
+                    }
+                },
+                Vertices: [vec![0.0f64; 128] => encoding] {},
+            },
+        )?;
+        Ok(writer.finalize_and_flush(&Default::default())?.into_inner())
+    };
+    let load = |bin| -> Result<_, Box<dyn std::error::Error>> {
+        let mut parser = match AnyParser::from_seekable_reader(Cursor::new(bin))? {
+            AnyParser::V7400(parser) => parser,
+            _ => panic!("Generated data should be parsable with v7400 parser"),
+        };
+        let (tree, footer_res) = TreeLoader::new().load(&mut parser)?;
+        assert!(footer_res.is_ok());
+        Ok(tree)
+    };
+
+    let direct = write(ArrayAttributeEncoding::Direct)?;
+    let zlib = write(ArrayAttributeEncoding::Zlib)?;
+    // The zero-filled array is compressed well.
+    assert!(zlib.len() + 512 < direct.len());
+    assert!(load(direct)?.strict_eq(&expected));
+    assert!(load(zlib)?.strict_eq(&expected));
+
+    Ok(())
+}
+
+/// Load trees by clones of a loader, and check node IDs of one tree are not
+/// accepted by another.
+#[test]
+fn trees_by_cloned_loaders_have_different_identities() -> Result<(), Box<dyn std::error::Error>> {
+    use fbxcel::tree::v7400::EditError;
+
+    let tree = tree_v7400! {
+        Node0: {},
+        Node1: {},
+    };
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()), FbxVersion::V7_4)?;
+    writer.write_tree(&tree)?;
+    let bin = writer.finalize_and_flush(&Default::default())?.into_inner();
+
+    let loader = TreeLoader::new();
+    let load = |loader: TreeLoader| -> Result<_, Box<dyn std::error::Error>> {
+        let mut parser = match AnyParser::from_seekable_reader(Cursor::new(&bin))? {
+            AnyParser::V7400(parser) => parser,
+            _ => panic!("Generated data should be parsable with v7400 parser"),
+        };
+        let (tree, footer_res) = loader.load(&mut parser)?;
+        assert!(footer_res.is_ok());
+        Ok(tree)
+    };
+    let tree_a = load(loader.clone())?;
+    let mut tree_b = load(loader)?;
+
+    let node0_a = tree_a.root().first_child().unwrap().node_id();
+    assert!(tree_b.get_node_mut(node0_a).is_none());
+    assert_eq!(
+        tree_b.try_detach(node0_a),
+        Err(EditError::ForeignNode(node0_a))
+    );
+    assert!(tree_b.strict_eq(&tree));
+
+    Ok(())
+}